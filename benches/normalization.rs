@@ -1,8 +1,24 @@
-use criterion::{black_box, criterion_group, criterion_main, Criterion};
-use rixty::domain_rc;
-use rixty::index::{Index, Level};
-use rixty::syntax;
+use criterion::{criterion_group, criterion_main, Criterion};
+use rixty::normalizer::Normalizer;
+use rixty::signature::{Definition, Signature};
+use rixty::syntax::{self, TermRef};
+use rixty::{domain, domain_rc};
 
+fn bench_normalization<N: Normalizer>(
+    criterion: &mut Criterion,
+    name: &str,
+    normalizer: &N,
+    term: TermRef<'_>,
+) {
+    criterion.bench_function(name, |bencher| {
+        bencher.iter(|| {
+            let result_builder = syntax::Builder::new();
+            normalizer.normalize(term, &result_builder);
+        });
+    });
+}
+
+#[allow(non_snake_case, unused_variables)]
 pub fn criterion_benchmark(criterion: &mut Criterion) {
     let b = syntax::Builder::new();
     let v0 = b.v(0);
@@ -43,48 +59,131 @@ pub fn criterion_benchmark(criterion: &mut Criterion) {
     let tree_4M = b.application(full_tree, n21);
     let tree_8M = b.application(full_tree, n22);
 
-    criterion.bench_function("Nat 5M normalization", |bencher| {
-        bencher.iter(|| {
-            let result_builder = syntax::Builder::new();
-            n5M.evaluate_rc(&mut domain_rc::Environment::new())
-                .quote(Level(0), &result_builder);
-        });
-    });
+    // The same numerals built from the built-in naturals, so that the two
+    // encodings can be compared.
+    let constant_nat = b.l(b.nat());
+    let native_add = b.l(b.l(b.nat_elim(constant_nat, v0, b.l(b.l(b.suc(v0))), v1)));
+    let native_mul = b.l(b.l(b.nat_elim(
+        constant_nat,
+        b.zero(),
+        b.l(b.l(b.apps(native_add, &[v2, v0]))),
+        v1,
+    )));
+    let native = |n| (0..n).fold(b.zero(), |x, _| b.suc(x));
+    let native_n2 = native(2);
+    let native_n5 = native(5);
+    let native_n10 = b.apps(native_mul, &[native_n2, native_n5]);
+    let native_n100 = b.apps(native_mul, &[native_n10, native_n10]);
+    let native_n10k = b.apps(native_mul, &[native_n100, native_n100]);
+    let native_n1M = b.apps(native_mul, &[native_n10k, native_n100]);
+    let native_n5M = b.apps(native_mul, &[native_n1M, native_n5]);
+    let native_n10M = b.apps(native_mul, &[native_n1M, native_n10]);
 
-    criterion.bench_function("Nat 10M normalization", |bencher| {
-        bencher.iter(|| {
-            let result_builder = syntax::Builder::new();
-            n10M.evaluate_rc(&mut domain_rc::Environment::new())
-                .quote(Level(0), &result_builder);
+    // The Church numerals again, as top-level definitions that refer to each
+    // other by name rather than being inlined into one term.
+    let mut signature = Signature::new();
+    let mut define = |name, term| {
+        let global = signature.define(Definition {
+            name,
+            ty: None,
+            term,
         });
-    });
+        b.global(global.index, global.name)
+    };
+    let global_n2 = define("n2", n2);
+    let global_n5 = define("n5", n5);
+    let global_mul = define("mul", mul);
+    let global_n10 = define("n10", b.apps(global_mul, &[global_n2, global_n5]));
+    let global_n100 = define("n100", b.apps(global_mul, &[global_n10, global_n10]));
+    let global_n10k = define("n10k", b.apps(global_mul, &[global_n100, global_n100]));
+    let global_n1M = define("n1M", b.apps(global_mul, &[global_n10k, global_n100]));
+    let global_n5M = define("n5M", b.apps(global_mul, &[global_n1M, global_n5]));
+    let inlined_n5M = signature.inline(global_n5M, &b);
+
+    bench_normalization(criterion, "Nat 5M normalization", &domain_rc::Backend, n5M);
+    bench_normalization(
+        criterion,
+        "Nat 10M normalization",
+        &domain_rc::Backend,
+        n10M,
+    );
+
+    bench_normalization(
+        criterion,
+        "Nat 5M normalization (arena)",
+        &domain::Backend,
+        n5M,
+    );
+    bench_normalization(
+        criterion,
+        "Nat 10M normalization (arena)",
+        &domain::Backend,
+        n10M,
+    );
+
+    bench_normalization(
+        criterion,
+        "Nat 5M normalization (call-by-need)",
+        &domain_rc::LazyBackend,
+        n5M,
+    );
+    bench_normalization(
+        criterion,
+        "Nat 10M normalization (call-by-need)",
+        &domain_rc::LazyBackend,
+        n10M,
+    );
+
+    bench_normalization(
+        criterion,
+        "Nat 5M normalization (native)",
+        &domain_rc::Backend,
+        native_n5M,
+    );
+    bench_normalization(
+        criterion,
+        "Nat 10M normalization (native)",
+        &domain_rc::Backend,
+        native_n10M,
+    );
 
-    // criterion.bench_function("Tree 2M normalization", |bencher| {
-    //     bencher.iter(|| {
-    //         let result_builder = syntax::Builder::new();
-    //         tree_2M
-    //             .evaluate_rc(&mut domain_rc::Environment::new())
-    //             .quote(Level(0), &result_builder);
-    //     });
-    // });
+    bench_normalization(
+        criterion,
+        "Nat 5M normalization (globals)",
+        &domain_rc::SignatureBackend {
+            signature: &signature,
+        },
+        global_n5M,
+    );
+    bench_normalization(
+        criterion,
+        "Nat 5M normalization (globals inlined)",
+        &domain_rc::Backend,
+        inlined_n5M,
+    );
 
-    // criterion.bench_function("Tree 4M normalization", |bencher| {
-    //     bencher.iter(|| {
-    //         let result_builder = syntax::Builder::new();
-    //         tree_4M
-    //             .evaluate_rc(&mut domain_rc::Environment::new())
-    //             .quote(Level(0), &result_builder);
-    //     });
-    // });
+    // bench_normalization(criterion, "Tree 2M normalization", &domain_rc::Backend, tree_2M);
+    // bench_normalization(criterion, "Tree 4M normalization", &domain_rc::Backend, tree_4M);
+    // bench_normalization(criterion, "Tree 8M normalization", &domain_rc::Backend, tree_8M);
 
-    // criterion.bench_function("Tree 8M normalization", |bencher| {
-    //     bencher.iter(|| {
-    //         let result_builder = syntax::Builder::new();
-    //         tree_8M
-    //             .evaluate_rc(&mut domain_rc::Environment::new())
-    //             .quote(Level(0), &result_builder);
-    //     });
-    // });
+    // bench_normalization(
+    //     criterion,
+    //     "Tree 2M normalization (call-by-need)",
+    //     &domain_rc::LazyBackend,
+    //     tree_2M,
+    // );
+    // bench_normalization(
+    //     criterion,
+    //     "Tree 4M normalization (call-by-need)",
+    //     &domain_rc::LazyBackend,
+    //     tree_4M,
+    // );
+    // bench_normalization(
+    //     criterion,
+    //     "Tree 8M normalization (call-by-need)",
+    //     &domain_rc::LazyBackend,
+    //     tree_8M,
+    // );
 }
 
 criterion_group!(benches, criterion_benchmark);
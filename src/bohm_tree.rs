@@ -0,0 +1,155 @@
+use crate::{
+    domain_rc::{self, Frame, Head, QuoteOptions, Value, ValueRef},
+    fuel::{unmetered, Fuel, Interrupted},
+    index::Level,
+    syntax::{self, Plicity},
+};
+use std::cell::OnceCell;
+
+/// The Böhm tree of a value: its head normal form, whose arguments are Böhm
+/// trees in turn. Each node is only computed when it is expanded, so terms
+/// without a normal form, such as infinite streams built with a fixpoint
+/// combinator, can be explored as deep as needed.
+///
+/// A subterm that has no head normal form makes its expansion diverge; use
+/// [`BohmTree::expand_with_fuel`] to give up on it.
+pub struct BohmTree<'a> {
+    value: ValueRef<'a>,
+    level: Level,
+    node: OnceCell<Node<'a>>,
+}
+
+/// A head normal form `\x1 … xn. h a1 … am`.
+pub struct Node<'a> {
+    /// The plicities of the lambdas in front of the body.
+    pub binders: Vec<Plicity>,
+    /// The level of the body, under the binders.
+    pub level: Level,
+    pub body: Body<'a>,
+}
+
+pub enum Body<'a> {
+    /// A head applied to arguments, each of which is a Böhm tree of its own.
+    Application {
+        head: Head<'a>,
+        arguments: Vec<(Plicity, BohmTree<'a>)>,
+    },
+    /// A weak head normal form that is neither a lambda nor a neutral applied
+    /// to arguments, such as a literal, a type or a neutral stuck on a
+    /// projection. It is a leaf of the tree, read back in full when rendered.
+    Leaf(ValueRef<'a>),
+}
+
+/// The name of the holes that stand for the subtrees [`BohmTree::truncate`]
+/// cuts off.
+pub const TRUNCATED: &str = "_";
+
+impl<'a> BohmTree<'a> {
+    /// The Böhm tree of `value`, whose free variables are below `level`.
+    pub fn new(value: ValueRef<'a>, level: Level) -> Self {
+        BohmTree {
+            value,
+            level,
+            node: OnceCell::new(),
+        }
+    }
+
+    /// The root of the tree, computing it the first time.
+    pub fn expand(&self) -> &Node<'a> {
+        unmetered(|fuel| self.expand_with_fuel(fuel))
+    }
+
+    /// [`BohmTree::expand`], metered by `fuel`. An interrupted expansion
+    /// leaves the tree unexpanded, so it can be retried.
+    pub fn expand_with_fuel(&self, fuel: &Fuel) -> Result<&Node<'a>, Interrupted> {
+        if let Some(node) = self.node.get() {
+            return Ok(node);
+        }
+        let node = self.compute(fuel)?;
+        Ok(self.node.get_or_init(|| node))
+    }
+
+    fn compute(&self, fuel: &Fuel) -> Result<Node<'a>, Interrupted> {
+        let mut binders = Vec::new();
+        let mut level = self.level;
+        let mut value = domain_rc::unfold_with_fuel(&self.value, fuel)?;
+        while let Value::Lambda(plicity, closure) = &*value {
+            binders.push(*plicity);
+            let body = closure.apply_with_fuel(Value::variable(level), fuel)?;
+            level = level + 1;
+            value = domain_rc::unfold_with_fuel(&body, fuel)?;
+        }
+        let body = match &*value {
+            Value::Neutral { head, spine }
+                if spine
+                    .iter()
+                    .all(|frame| matches!(frame, Frame::Application(..))) =>
+            {
+                let arguments = (spine.iter())
+                    .map(|frame| match frame {
+                        Frame::Application(plicity, argument) => {
+                            (*plicity, BohmTree::new(argument.clone(), level))
+                        }
+                        _ => unreachable!(),
+                    })
+                    .collect();
+                Body::Application {
+                    head: head.clone(),
+                    arguments,
+                }
+            }
+            _ => Body::Leaf(value),
+        };
+        Ok(Node {
+            binders,
+            level,
+            body,
+        })
+    }
+
+    /// The tree down to `depth` nodes, with the subtrees below that replaced
+    /// by holes named [`TRUNCATED`].
+    pub fn truncate<'b>(
+        &self,
+        depth: usize,
+        syntax_builder: &'b syntax::Builder,
+    ) -> syntax::TermRef<'b> {
+        unmetered(|fuel| self.truncate_with_fuel(depth, syntax_builder, fuel))
+    }
+
+    /// [`BohmTree::truncate`], metered by `fuel`.
+    pub fn truncate_with_fuel<'b>(
+        &self,
+        depth: usize,
+        syntax_builder: &'b syntax::Builder,
+        fuel: &Fuel,
+    ) -> Result<syntax::TermRef<'b>, Interrupted> {
+        let Some(depth) = depth.checked_sub(1) else {
+            return Ok(syntax_builder.hole(TRUNCATED, &[]));
+        };
+        let node = self.expand_with_fuel(fuel)?;
+        let mut term = match &node.body {
+            Body::Application { head, arguments } => {
+                let mut term = head.quote(&[], node.level, syntax_builder);
+                for (plicity, argument) in arguments {
+                    let argument = argument.truncate_with_fuel(depth, syntax_builder, fuel)?;
+                    term = syntax_builder.application_with(*plicity, term, argument);
+                }
+                term
+            }
+            Body::Leaf(value) => {
+                value.quote_with_fuel(node.level, syntax_builder, QuoteOptions::default(), fuel)?
+            }
+        };
+        for plicity in node.binders.iter().rev() {
+            term = syntax_builder.lambda_with(*plicity, None, term);
+        }
+        Ok(term)
+    }
+
+    /// Renders the tree down to `depth` nodes, with `?_` in place of the
+    /// subtrees below that.
+    pub fn render(&self, depth: usize) -> String {
+        self.truncate(depth, &syntax::Builder::new()).to_string()
+    }
+}
@@ -0,0 +1,926 @@
+use crate::{
+    domain_rc::{self, Closure, Environment, Frame, Head, QuoteOptions, TypeRef, Value, ValueRef},
+    index::Level,
+    meta::{MetaContext, UnifyError},
+    pretty::{render, DEFAULT_WIDTH},
+    signature::{Datatype, Definition, Item, Signature},
+    syntax::{self, Branch, Hole, Plicity, Term, TermRef},
+};
+use rclite::Rc;
+
+/// Type errors. Types are read back at the level of the context they were
+/// found in, so their free variables are the variables of that context.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Error<'a> {
+    /// A term was expected to have one type but has another.
+    Mismatch {
+        expected: TermRef<'a>,
+        found: TermRef<'a>,
+        context: usize,
+    },
+    /// Two types could only be made equal by solving a meta in a way that
+    /// unification does not find.
+    Unification {
+        expected: TermRef<'a>,
+        found: TermRef<'a>,
+        context: usize,
+        error: UnifyError,
+    },
+    /// A term of a type other than a function type was applied.
+    NotAFunction { found: TermRef<'a>, context: usize },
+    /// A term was given an implicit argument, but its type is not an implicit
+    /// function type.
+    NotImplicit { found: TermRef<'a>, context: usize },
+    /// A term of a type other than a pair type was projected out of.
+    NotAPair { found: TermRef<'a>, context: usize },
+    /// A term of a type other than a datatype was analysed by cases.
+    NotADatatype { found: TermRef<'a>, context: usize },
+    /// A case analysis has no branch for a constructor of its datatype.
+    MissingBranch { constructor: &'a str },
+    /// A case analysis has a second branch for a constructor, or a branch
+    /// for a constructor of another datatype.
+    UnexpectedBranch { constructor: &'a str },
+    /// A branch binds a different number of fields than its constructor has.
+    BranchArity {
+        constructor: &'a str,
+        expected: usize,
+        found: usize,
+    },
+    /// A term was projected out of by a field name that its type, a record
+    /// type or otherwise, does not have.
+    NoField {
+        field: &'a str,
+        found: TermRef<'a>,
+        context: usize,
+    },
+    /// A record or record type has two fields of the same name.
+    DuplicateField { name: &'a str },
+    /// A lambda without an annotation, a pair or a meta was found where its
+    /// type is not known.
+    CannotInfer,
+}
+
+/// Names the free variables of types in an error by their levels.
+fn render_in(term: TermRef<'_>, context: usize) -> String {
+    let names: Vec<String> = (0..context).map(|level| format!("x{level}")).collect();
+    let names: Vec<&str> = names.iter().map(String::as_str).collect();
+    render(term, &names, DEFAULT_WIDTH)
+}
+
+impl std::fmt::Display for Error<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Mismatch {
+                expected,
+                found,
+                context,
+            } => write!(
+                f,
+                "expected type {}, found {}",
+                render_in(expected, *context),
+                render_in(found, *context)
+            ),
+            Error::Unification {
+                expected,
+                found,
+                context,
+                error,
+            } => write!(
+                f,
+                "cannot unify type {} with {}: {error}",
+                render_in(expected, *context),
+                render_in(found, *context)
+            ),
+            Error::NotAFunction { found, context } => write!(
+                f,
+                "applied a term of type {}, which is not a function type",
+                render_in(found, *context)
+            ),
+            Error::NotImplicit { found, context } => write!(
+                f,
+                "applied a term of type {} to an implicit argument, \
+                 which is not an implicit function type",
+                render_in(found, *context)
+            ),
+            Error::NotAPair { found, context } => write!(
+                f,
+                "projected out of a term of type {}, which is not a pair type",
+                render_in(found, *context)
+            ),
+            Error::NotADatatype { found, context } => write!(
+                f,
+                "analysed a term of type {} by cases, which is not a datatype",
+                render_in(found, *context)
+            ),
+            Error::MissingBranch { constructor } => {
+                write!(f, "missing a branch for constructor {constructor}")
+            }
+            Error::UnexpectedBranch { constructor } => write!(
+                f,
+                "unexpected branch for constructor {constructor}, which is covered \
+                 already or is of another datatype"
+            ),
+            Error::BranchArity {
+                constructor,
+                expected,
+                found,
+            } => write!(
+                f,
+                "constructor {constructor} has {expected} fields, but its branch binds {found}"
+            ),
+            Error::NoField {
+                field,
+                found,
+                context,
+            } => write!(
+                f,
+                "projected field {field} out of a term of type {}, \
+                 which is not a record type with that field",
+                render_in(found, *context)
+            ),
+            Error::DuplicateField { name } => write!(f, "duplicate field {name}"),
+            Error::CannotInfer => write!(
+                f,
+                "cannot infer the type of an unannotated lambda, a pair or a meta"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error<'_> {}
+
+/// A hole that was checked, with what is known about what should fill it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Goal<'a> {
+    pub name: &'a str,
+    /// The variables in scope at the hole, outermost first, with their
+    /// types. Each type is in the context of the variables before it.
+    pub context: Vec<(&'a str, TermRef<'a>)>,
+    /// The type the hole is expected to have, in the context of all of them.
+    pub ty: TermRef<'a>,
+}
+
+/// Shows the goal on its first line and the context below it, one variable
+/// per line.
+impl std::fmt::Display for Goal<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let names: Vec<&str> = self.context.iter().map(|&(name, _)| name).collect();
+        write!(
+            f,
+            "?{} : {}",
+            self.name,
+            render(self.ty, &names, DEFAULT_WIDTH)
+        )?;
+        for (level, (name, ty)) in self.context.iter().enumerate() {
+            write!(
+                f,
+                "\n  {name} : {}",
+                render(ty, &names[..level], DEFAULT_WIDTH)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// A hole as it was found, with the variables in scope at it. It is only
+/// read back when it is reported.
+struct PendingGoal<'a> {
+    hole: Hole<'a>,
+    ty: TypeRef<'a>,
+    types: Vec<TypeRef<'a>>,
+    bindings: Vec<Binding>,
+}
+
+/// How a variable came to be in the context.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Binding {
+    /// Bound by a binder of the term being checked.
+    Bound,
+    /// Defined by a `let`.
+    Defined,
+    /// Bound by an implicit lambda that the checker inserted. It does not
+    /// appear in the term being checked, so its indices skip it.
+    Inserted,
+}
+
+/// A datatype in scope, with the types of its type constructor and of its
+/// constructors.
+struct DatatypeTypes<'a> {
+    datatype: Datatype<'a>,
+    ty: TypeRef<'a>,
+    constructors: Vec<TypeRef<'a>>,
+}
+
+/// The variables in scope during checking, with their values and types.
+///
+/// Checking is bidirectional: [`Context::infer`] synthesizes the type of a
+/// term, while [`Context::check`] pushes a known type into it, which is what
+/// lets unannotated lambdas be checked. Both elaborate the term as they go,
+/// replacing each hole with a meta, and it is the elaborated term that is
+/// evaluated when types depend on it.
+///
+/// Implicit arguments that are left out are elaborated to metas: a term
+/// whose type is an implicit function type is applied to a fresh meta for
+/// each implicit argument, unless it is given one in braces, and a term
+/// checked against an implicit function type is wrapped in an implicit
+/// lambda unless it is one. Types are compared by unification,
+/// which solves the metas of the context. `Type` is its own type, so the
+/// system is not a logic.
+pub struct Context<'a> {
+    environment: Environment<'a>,
+    metas: MetaContext<'a>,
+    types: Vec<TypeRef<'a>>,
+    /// How each variable came to be in scope. Metas abstract over the bound
+    /// and inserted ones.
+    bindings: Vec<Binding>,
+    /// The types of the globals in scope.
+    globals: Vec<TypeRef<'a>>,
+    /// The elaborated definitions of the globals in scope.
+    definitions: &'a [Definition<'a>],
+    datatypes: Vec<DatatypeTypes<'a>>,
+    goals: Vec<PendingGoal<'a>>,
+    syntax_builder: &'a syntax::Builder,
+}
+
+impl<'a> Context<'a> {
+    /// An empty context. Types in errors are read back into `syntax_builder`.
+    pub fn new(syntax_builder: &'a syntax::Builder) -> Self {
+        let metas = MetaContext::new(syntax_builder);
+        Context {
+            environment: Environment::new().with_metas(&metas),
+            metas,
+            types: Vec::new(),
+            bindings: Vec::new(),
+            globals: Vec::new(),
+            definitions: &[],
+            datatypes: Vec::new(),
+            goals: Vec::new(),
+            syntax_builder,
+        }
+    }
+
+    /// An empty context in which terms can refer to the definitions and
+    /// datatypes of `signature`. They are checked, and the definitions
+    /// elaborated, in order first; see [`Context::definitions`].
+    pub fn with_signature(
+        signature: &Signature<'a>,
+        syntax_builder: &'a syntax::Builder,
+    ) -> Result<Self, Error<'a>> {
+        let mut context = Context::new(syntax_builder);
+        let mut definitions = Vec::new();
+        for item in signature.items() {
+            let global = match *item {
+                Item::Definition(global) => global,
+                Item::Datatype(type_constructor) => {
+                    context.declare(*signature.datatype(type_constructor))?;
+                    continue;
+                }
+            };
+            let definition = signature.definition(global);
+            let (term, ty) = match definition.ty {
+                Some(ty) => {
+                    let (ty, ty_value) = context.check_type(ty)?;
+                    (context.check(definition.term, &ty_value)?, ty)
+                }
+                None => {
+                    let (term, ty) = context.infer(definition.term)?;
+                    (term, context.quote(&ty))
+                }
+            };
+            definitions.push(Definition {
+                name: definition.name,
+                ty: Some(ty),
+                term,
+            });
+            context.globals.push(context.evaluate(ty));
+            context.definitions = syntax_builder.copy_slice(&definitions);
+            context.metas.set_definitions(context.definitions);
+            context.environment = Environment::new()
+                .with_definitions(context.definitions)
+                .with_metas(&context.metas);
+        }
+        Ok(context)
+    }
+
+    /// Checks the parameters and the fields of the constructors of
+    /// `datatype`, and brings it into scope. The type constructor is in
+    /// scope for the fields, but not the constructors.
+    ///
+    /// The type constructor takes the parameters, and each constructor takes
+    /// them implicitly and then its fields, as in `{A : Type} -> A -> List A
+    /// -> List A`.
+    fn declare(&mut self, datatype: Datatype<'a>) -> Result<(), Error<'a>> {
+        let b = self.syntax_builder;
+        let index = self.datatypes.len();
+        let (parameters, constructors) = self.local(|context| {
+            let mut parameters = Vec::new();
+            for parameter in datatype.parameters {
+                let (parameter, parameter_value) = context.check_type(parameter)?;
+                parameters.push(parameter);
+                context.bind(parameter_value);
+            }
+            let ty =
+                (parameters.iter().rev()).fold(b.universe(), |ty, parameter| b.pi(parameter, ty));
+            context.datatypes.push(DatatypeTypes {
+                datatype,
+                ty: context.evaluate(ty),
+                constructors: Vec::new(),
+            });
+            let mut constructors = Vec::new();
+            for constructor in datatype.constructors {
+                let fields = context.local(|context| {
+                    let mut fields = Vec::new();
+                    for field in constructor.fields {
+                        let (field, field_value) = context.check_type(field)?;
+                        fields.push(field);
+                        context.bind(field_value);
+                    }
+                    Ok(fields)
+                })?;
+                constructors.push(fields);
+            }
+            Ok((parameters, constructors))
+        })?;
+        let type_constructor = b.type_constructor(index, datatype.name);
+        let constructors = (constructors.iter())
+            .map(|fields| {
+                let depth = parameters.len() + fields.len();
+                let arguments: Vec<_> = (0..parameters.len())
+                    .map(|parameter| b.v(depth - parameter - 1))
+                    .collect();
+                let ty = (fields.iter().rev())
+                    .fold(b.apps(type_constructor, &arguments), |ty, field| {
+                        b.pi(field, ty)
+                    });
+                let ty = (parameters.iter().rev()).fold(ty, |ty, parameter| {
+                    b.pi_with(Plicity::Implicit, parameter, ty)
+                });
+                self.evaluate(ty)
+            })
+            .collect();
+        self.datatypes[index].constructors = constructors;
+        Ok(())
+    }
+
+    /// The elaborated definitions of the globals in scope, each with its
+    /// type. Terms that refer to them should be evaluated with these rather
+    /// than with the definitions they were elaborated from.
+    pub fn definitions(&self) -> &'a [Definition<'a>] {
+        self.definitions
+    }
+
+    /// The metas of the terms being checked.
+    pub fn metas(&self) -> &MetaContext<'a> {
+        &self.metas
+    }
+
+    /// The goals of the holes checked so far, in the order they were found.
+    /// Their types are read back now, so they show whatever unification has
+    /// found out about them since.
+    pub fn goals(&self) -> Vec<Goal<'a>> {
+        let b = self.syntax_builder;
+        let options = QuoteOptions {
+            keep_globals: true,
+            ..QuoteOptions::default()
+        };
+        self.goals
+            .iter()
+            .map(|goal| {
+                // Variables without a name in the source, such as those bound
+                // by arrows or inserted lambdas, are named by their levels, as
+                // in errors.
+                let mut scope = goal.hole.scope.iter();
+                let names = goal.bindings.iter().enumerate().map(|(level, binding)| {
+                    let name = match binding {
+                        Binding::Inserted => None,
+                        _ => scope.next().filter(|name| !name.is_empty()),
+                    };
+                    name.copied()
+                        .unwrap_or_else(|| b.copy_str(&format!("x{level}")))
+                });
+                let context = names
+                    .zip(&goal.types)
+                    .enumerate()
+                    .map(|(level, (name, ty))| (name, ty.quote_with(Level(level), b, options)))
+                    .collect();
+                Goal {
+                    name: goal.hole.name,
+                    context,
+                    ty: goal.ty.quote_with(Level(goal.types.len()), b, options),
+                }
+            })
+            .collect()
+    }
+
+    pub fn level(&self) -> Level {
+        Level(self.types.len())
+    }
+
+    /// Adds a variable of type `ty` to the context.
+    pub fn bind(&mut self, ty: TypeRef<'a>) {
+        self.push(Value::variable(self.level()), ty, Binding::Bound);
+    }
+
+    /// Adds a variable that stands for `value`, of type `ty`.
+    pub fn define(&mut self, value: ValueRef<'a>, ty: TypeRef<'a>) {
+        self.push(value, ty, Binding::Defined);
+    }
+
+    fn push(&mut self, value: ValueRef<'a>, ty: TypeRef<'a>, binding: Binding) {
+        self.environment.extend(value);
+        self.types.push(ty);
+        self.bindings.push(binding);
+    }
+
+    /// The level of the variable that `index` refers to in the term being
+    /// checked, which does not count inserted lambdas.
+    fn source_level(&self, index: usize) -> Level {
+        let level = (self.bindings.iter().enumerate().rev())
+            .filter(|&(_, &binding)| binding != Binding::Inserted)
+            .nth(index)
+            .map(|(level, _)| level)
+            .expect("variable out of scope");
+        Level(level)
+    }
+
+    /// Runs `f` in this context and removes whatever it binds afterwards.
+    fn local<F, A>(&mut self, f: F) -> A
+    where
+        F: FnOnce(&mut Self) -> A,
+    {
+        let len_before = self.types.len();
+        let result = f(self);
+        self.types.truncate(len_before);
+        self.bindings.truncate(len_before);
+        self.environment.truncate(len_before);
+        result
+    }
+
+    pub fn evaluate(&self, term: TermRef<'a>) -> ValueRef<'a> {
+        term.evaluate_rc(&mut self.environment.clone())
+    }
+
+    /// Reads `value` back, keeping globals folded so that types are shown
+    /// by name.
+    pub fn quote(&self, value: &Value<'a>) -> TermRef<'a> {
+        let options = QuoteOptions {
+            keep_globals: true,
+            ..QuoteOptions::default()
+        };
+        value.quote_with(self.level(), self.syntax_builder, options)
+    }
+
+    fn mismatch(&self, expected: &Value<'a>, found: &Value<'a>) -> Error<'a> {
+        Error::Mismatch {
+            expected: self.quote(expected),
+            found: self.quote(found),
+            context: self.types.len(),
+        }
+    }
+
+    /// Checks that `found` and `expected` are the same type, solving metas
+    /// to make them so.
+    fn unify(&self, expected: &Value<'a>, found: &Value<'a>) -> Result<(), Error<'a>> {
+        match self.metas.unify(self.level(), expected, found) {
+            Ok(()) => Ok(()),
+            Err(UnifyError::Mismatch) => Err(self.mismatch(expected, found)),
+            Err(error) => Err(Error::Unification {
+                expected: self.quote(expected),
+                found: self.quote(found),
+                context: self.types.len(),
+                error,
+            }),
+        }
+    }
+
+    /// A fresh meta applied to the bound variables of the context: a term
+    /// that is yet to be found, and may mention them.
+    fn fresh_meta(&self) -> TermRef<'a> {
+        let b = self.syntax_builder;
+        let arguments: Vec<_> = (self.bindings.iter().enumerate())
+            .filter(|&(_, &binding)| binding != Binding::Defined)
+            .map(|(level, _)| b.variable(Level(level).to_index(self.level())))
+            .collect();
+        b.apps(b.meta(self.metas.fresh()), &arguments)
+    }
+
+    /// Records the goal of `hole`, of type `ty`, and elaborates it.
+    fn hole(&mut self, hole: Hole<'a>, ty: TypeRef<'a>) -> TermRef<'a> {
+        self.goals.push(PendingGoal {
+            hole,
+            ty,
+            types: self.types.clone(),
+            bindings: self.bindings.clone(),
+        });
+        self.fresh_meta()
+    }
+
+    /// Applies `term`, of type `ty`, to a fresh meta for each implicit
+    /// argument it takes before its first explicit one.
+    fn insert_implicits(
+        &self,
+        mut term: TermRef<'a>,
+        mut ty: TypeRef<'a>,
+    ) -> (TermRef<'a>, TypeRef<'a>) {
+        let b = self.syntax_builder;
+        loop {
+            let unfolded = domain_rc::unfold(&ty);
+            let Value::Pi(Plicity::Implicit, _, codomain) = &*unfolded else {
+                return (term, ty);
+            };
+            let argument = self.fresh_meta();
+            term = b.application_with(Plicity::Implicit, term, argument);
+            ty = codomain.apply(self.evaluate(argument));
+        }
+    }
+
+    /// Checks that `term` is a type, and elaborates and evaluates it.
+    fn check_type(&mut self, term: TermRef<'a>) -> Result<(TermRef<'a>, TypeRef<'a>), Error<'a>> {
+        let term = self.check(term, &Rc::new(Value::Type))?;
+        Ok((term, self.evaluate(term)))
+    }
+
+    /// Checks that `scrutinee` is of a datatype and that `branches` cover
+    /// each of its constructors once, with bodies of type `ty`, and
+    /// elaborates the case analysis.
+    fn case(
+        &mut self,
+        scrutinee: TermRef<'a>,
+        branches: &[Branch<'a>],
+        ty: &TypeRef<'a>,
+    ) -> Result<TermRef<'a>, Error<'a>> {
+        let (scrutinee, scrutinee_type) = self.infer(scrutinee)?;
+        let (scrutinee, scrutinee_type) = self.insert_implicits(scrutinee, scrutinee_type);
+        let scrutinee_type = domain_rc::unfold(&scrutinee_type);
+        let Value::Neutral {
+            head: Head::TypeConstructor(type_constructor),
+            spine,
+        } = &*scrutinee_type
+        else {
+            return Err(Error::NotADatatype {
+                found: self.quote(&scrutinee_type),
+                context: self.types.len(),
+            });
+        };
+        let parameters: Vec<_> = (spine.iter())
+            .filter_map(|frame| match frame {
+                Frame::Application(_, parameter) => Some(parameter.clone()),
+                _ => None,
+            })
+            .collect();
+        let datatype = self.datatypes[type_constructor.index].datatype;
+        let mut covered = vec![false; datatype.constructors.len()];
+        let mut elaborated = Vec::new();
+        for branch in branches {
+            let constructor = branch.constructor;
+            if constructor.datatype != type_constructor.index || covered[constructor.index] {
+                return Err(Error::UnexpectedBranch {
+                    constructor: constructor.name,
+                });
+            }
+            covered[constructor.index] = true;
+            let fields = datatype.constructors[constructor.index].fields.len();
+            if branch.arity != fields {
+                return Err(Error::BranchArity {
+                    constructor: constructor.name,
+                    expected: fields,
+                    found: branch.arity,
+                });
+            }
+            // Instantiate the parameters of the type of the constructor, and
+            // bind its fields.
+            let mut constructor_type =
+                self.datatypes[constructor.datatype].constructors[constructor.index].clone();
+            for parameter in &parameters {
+                constructor_type = pi_codomain(&constructor_type, parameter.clone());
+            }
+            let body = self.local(|context| {
+                for _ in 0..fields {
+                    let unfolded = domain_rc::unfold(&constructor_type);
+                    let Value::Pi(_, field, codomain) = &*unfolded else {
+                        unreachable!("constructors have a function type for each field")
+                    };
+                    constructor_type = codomain.apply(Value::variable(context.level()));
+                    context.bind(field.clone());
+                }
+                context.check(branch.body, ty)
+            })?;
+            elaborated.push(Branch { body, ..*branch });
+        }
+        if let Some(missing) = covered.iter().position(|&covered| !covered) {
+            return Err(Error::MissingBranch {
+                constructor: datatype.constructors[missing].name,
+            });
+        }
+        Ok(self.syntax_builder.case(scrutinee, &elaborated))
+    }
+
+    /// Infers the type of the field `name` of `record`, and elaborates the
+    /// projection. The types of the fields after the first depend on the
+    /// fields before them, which are projected out of `record` too.
+    fn project(
+        &mut self,
+        record: TermRef<'a>,
+        name: &'a str,
+    ) -> Result<(TermRef<'a>, TypeRef<'a>), Error<'a>> {
+        let (record, record_type) = self.infer(record)?;
+        let (record, record_type) = self.insert_implicits(record, record_type);
+        let record_type = domain_rc::unfold(&record_type);
+        let field = match &*record_type {
+            Value::RecordType(fields, environment) => (fields.iter())
+                .position(|&(field, _)| field == name)
+                .map(|position| (&fields[..=position], environment)),
+            _ => None,
+        };
+        let Some((fields, environment)) = field else {
+            return Err(Error::NoField {
+                field: name,
+                found: self.quote(&record_type),
+                context: self.types.len(),
+            });
+        };
+        let record_value = self.evaluate(record);
+        let mut types = domain_rc::field_types(fields, environment, |_, field| {
+            domain_rc::project_field(&record_value, field)
+        });
+        Ok((
+            self.syntax_builder.project(record, name),
+            types.pop().unwrap(),
+        ))
+    }
+
+    /// Infers the type of `term`, and elaborates it.
+    pub fn infer(&mut self, term: TermRef<'a>) -> Result<(TermRef<'a>, TypeRef<'a>), Error<'a>> {
+        let b = self.syntax_builder;
+        match term {
+            Term::Variable(index) => {
+                let level = self.source_level(index.to_int());
+                Ok((
+                    b.variable(level.to_index(self.level())),
+                    self.types[level.to_int()].clone(),
+                ))
+            }
+            Term::Lambda(_, None, _) => Err(Error::CannotInfer),
+            Term::Lambda(plicity, Some(domain), body) => {
+                let (domain, domain_value) = self.check_type(domain)?;
+                let (body, codomain) = self.local(|context| {
+                    context.bind(domain_value.clone());
+                    let (body, codomain) = context.infer(body)?;
+                    Ok((body, context.quote(&codomain)))
+                })?;
+                Ok((
+                    b.lambda_with(*plicity, Some(domain), body),
+                    Rc::new(Value::Pi(
+                        *plicity,
+                        domain_value,
+                        Closure::new(codomain, self.environment.clone()),
+                    )),
+                ))
+            }
+            Term::Application(plicity, function, argument) => {
+                let (function, function_type) = self.infer(function)?;
+                let (function, function_type) = match plicity {
+                    Plicity::Explicit => self.insert_implicits(function, function_type),
+                    Plicity::Implicit => (function, function_type),
+                };
+                let function_type = domain_rc::unfold(&function_type);
+                match &*function_type {
+                    Value::Pi(domain_plicity, domain, codomain) if domain_plicity == plicity => {
+                        let argument = self.check(argument, domain)?;
+                        Ok((
+                            b.application_with(*plicity, function, argument),
+                            codomain.apply(self.evaluate(argument)),
+                        ))
+                    }
+                    Value::Pi(..) => Err(Error::NotImplicit {
+                        found: self.quote(&function_type),
+                        context: self.types.len(),
+                    }),
+                    _ => Err(Error::NotAFunction {
+                        found: self.quote(&function_type),
+                        context: self.types.len(),
+                    }),
+                }
+            }
+            Term::Let(value, body) => {
+                let (value, ty) = self.infer(value)?;
+                let value_value = self.evaluate(value);
+                let (body, body_type) = self.local(|context| {
+                    context.define(value_value, ty);
+                    context.infer(body)
+                })?;
+                Ok((b.let_in(value, body), body_type))
+            }
+            Term::Pi(_, domain, codomain) | Term::Sigma(domain, codomain) => {
+                let (domain, domain_value) = self.check_type(domain)?;
+                let (codomain, _) = self.local(|context| {
+                    context.bind(domain_value);
+                    context.check_type(codomain)
+                })?;
+                let term = match term {
+                    Term::Pi(plicity, ..) => b.pi_with(*plicity, domain, codomain),
+                    _ => b.sigma(domain, codomain),
+                };
+                Ok((term, Rc::new(Value::Type)))
+            }
+            Term::Pair(..) | Term::Meta(_) => Err(Error::CannotInfer),
+            Term::Hole(hole) => {
+                let ty = self.evaluate(self.fresh_meta());
+                Ok((self.hole(*hole, ty.clone()), ty))
+            }
+            Term::Fst(pair) | Term::Snd(pair) => {
+                let (pair, pair_type) = self.infer(pair)?;
+                let pair_type = domain_rc::unfold(&pair_type);
+                match (term, &*pair_type) {
+                    (Term::Fst(_), Value::Sigma(domain, _)) => Ok((b.fst(pair), domain.clone())),
+                    (_, Value::Sigma(_, codomain)) => {
+                        Ok((b.snd(pair), codomain.apply(self.evaluate(b.fst(pair)))))
+                    }
+                    _ => Err(Error::NotAPair {
+                        found: self.quote(&pair_type),
+                        context: self.types.len(),
+                    }),
+                }
+            }
+            Term::Type | Term::Nat => Ok((term, Rc::new(Value::Type))),
+            Term::Zero | Term::Natural(_) => Ok((term, Rc::new(Value::Nat))),
+            Term::Suc(predecessor) => {
+                let nat = Rc::new(Value::Nat);
+                Ok((b.suc(self.check(predecessor, &nat)?), nat))
+            }
+            Term::NatElim(motive, zero, suc, scrutinee) => {
+                let motive = self.check(motive, &self.evaluate(b.pi(b.nat(), b.universe())))?;
+                let motive_value = self.evaluate(motive);
+                let scrutinee = self.check(scrutinee, &Rc::new(Value::Nat))?;
+                let zero_type = domain_rc::apply(&motive_value, Rc::new(Value::Natural(0)));
+                let zero = self.check(zero, &zero_type)?;
+                // (k : Nat) -> motive k -> motive (suc k), with the motive as
+                // the only variable in scope.
+                let suc_type = b.pi(
+                    b.nat(),
+                    b.pi(
+                        b.application(b.v(1), b.v(0)),
+                        b.application(b.v(2), b.suc(b.v(1))),
+                    ),
+                );
+                let mut environment = Environment::new();
+                environment.extend(motive_value.clone());
+                let suc = self.check(suc, &suc_type.evaluate_rc(&mut environment))?;
+                Ok((
+                    b.nat_elim(motive, zero, suc, scrutinee),
+                    domain_rc::apply(&motive_value, self.evaluate(scrutinee)),
+                ))
+            }
+            Term::Global(global) => Ok((term, self.globals[global.index].clone())),
+            Term::TypeConstructor(type_constructor) => {
+                Ok((term, self.datatypes[type_constructor.index].ty.clone()))
+            }
+            Term::Constructor(constructor) => Ok((
+                term,
+                self.datatypes[constructor.datatype].constructors[constructor.index].clone(),
+            )),
+            Term::Case(scrutinee, branches) => {
+                let ty = self.evaluate(self.fresh_meta());
+                Ok((self.case(scrutinee, branches, &ty)?, ty))
+            }
+            Term::RecordType(fields) => {
+                check_distinct(fields)?;
+                let fields = self.local(|context| {
+                    let mut elaborated = Vec::new();
+                    for &(name, ty) in *fields {
+                        let (ty, ty_value) = context.check_type(ty)?;
+                        elaborated.push((name, ty));
+                        context.bind(ty_value);
+                    }
+                    Ok(elaborated)
+                })?;
+                Ok((b.record_type(&fields), Rc::new(Value::Type)))
+            }
+            // The type of each field is read back under binders for the
+            // fields before it, which it does not depend on.
+            Term::Record(fields) => {
+                check_distinct(fields)?;
+                let mut elaborated = Vec::new();
+                let mut types = Vec::new();
+                for (position, &(name, field)) in fields.iter().enumerate() {
+                    let (field, ty) = self.infer(field)?;
+                    let (field, ty) = self.insert_implicits(field, ty);
+                    elaborated.push((name, field));
+                    let options = QuoteOptions {
+                        keep_globals: true,
+                        ..QuoteOptions::default()
+                    };
+                    types.push((name, ty.quote_with(self.level() + position, b, options)));
+                }
+                Ok((b.record(&elaborated), self.evaluate(b.record_type(&types))))
+            }
+            Term::Project(record, name) => self.project(record, name),
+            Term::IntegerType(_) => Ok((term, Rc::new(Value::Type))),
+            Term::Integer(value) => Ok((term, Rc::new(Value::IntegerType(value.ty())))),
+            Term::Primitive(primitive) => {
+                let argument = b.integer_type(primitive.ty);
+                let result = if primitive.operation.is_comparison() {
+                    b.nat()
+                } else {
+                    argument
+                };
+                Ok((term, self.evaluate(b.pi(argument, b.pi(argument, result)))))
+            }
+        }
+    }
+
+    /// Checks that `term` has type `ty`, and elaborates it.
+    pub fn check(&mut self, term: TermRef<'a>, ty: &TypeRef<'a>) -> Result<TermRef<'a>, Error<'a>> {
+        let b = self.syntax_builder;
+        let unfolded = domain_rc::unfold(ty);
+        match (term, &*unfolded) {
+            (
+                Term::Lambda(plicity, annotation, body),
+                Value::Pi(domain_plicity, domain, codomain),
+            ) if plicity == domain_plicity => {
+                let annotation = match annotation {
+                    Some(annotation) => {
+                        let (annotation, annotation_value) = self.check_type(annotation)?;
+                        self.unify(domain, &annotation_value)?;
+                        Some(annotation)
+                    }
+                    None => None,
+                };
+                let codomain = codomain.apply(Value::variable(self.level()));
+                let body = self.local(|context| {
+                    context.bind(domain.clone());
+                    context.check(body, &codomain)
+                })?;
+                Ok(b.lambda_with(*plicity, annotation, body))
+            }
+            (_, Value::Pi(Plicity::Implicit, domain, codomain)) => {
+                let codomain = codomain.apply(Value::variable(self.level()));
+                let body = self.local(|context| {
+                    context.push(
+                        Value::variable(context.level()),
+                        domain.clone(),
+                        Binding::Inserted,
+                    );
+                    context.check(term, &codomain)
+                })?;
+                Ok(b.lambda_with(Plicity::Implicit, None, body))
+            }
+            (Term::Pair(first, second), Value::Sigma(domain, codomain)) => {
+                let first = self.check(first, domain)?;
+                let second = self.check(second, &codomain.apply(self.evaluate(first)))?;
+                Ok(b.pair(first, second))
+            }
+            (Term::Let(value, body), _) => {
+                let (value, value_type) = self.infer(value)?;
+                let value_value = self.evaluate(value);
+                let body = self.local(|context| {
+                    context.define(value_value, value_type);
+                    context.check(body, ty)
+                })?;
+                Ok(b.let_in(value, body))
+            }
+            (Term::Record(fields), Value::RecordType(types, environment))
+                if domain_rc::same_names(fields, types) =>
+            {
+                let mut environment = environment.clone();
+                let mut elaborated = Vec::new();
+                for (&(name, field), &(_, field_type)) in fields.iter().zip(*types) {
+                    let field_type = field_type.evaluate_rc(&mut environment.clone());
+                    let field = self.check(field, &field_type)?;
+                    environment.extend(self.evaluate(field));
+                    elaborated.push((name, field));
+                }
+                Ok(b.record(&elaborated))
+            }
+            (Term::Hole(hole), _) => Ok(self.hole(*hole, ty.clone())),
+            (Term::Case(scrutinee, branches), _) => self.case(scrutinee, branches, ty),
+            _ => {
+                let (elaborated, found) = self.infer(term)?;
+                let (elaborated, found) = match term {
+                    Term::Lambda(Plicity::Implicit, ..) => (elaborated, found),
+                    _ => self.insert_implicits(elaborated, found),
+                };
+                self.unify(ty, &found)?;
+                Ok(elaborated)
+            }
+        }
+    }
+}
+
+/// Checks that no two of `fields` have the same name.
+fn check_distinct<'a, A>(fields: &[(&'a str, A)]) -> Result<(), Error<'a>> {
+    for (position, &(name, _)) in fields.iter().enumerate() {
+        if fields[..position].iter().any(|&(field, _)| field == name) {
+            return Err(Error::DuplicateField { name });
+        }
+    }
+    Ok(())
+}
+
+/// The codomain of the function type `ty` at `argument`.
+fn pi_codomain<'a>(ty: &TypeRef<'a>, argument: ValueRef<'a>) -> TypeRef<'a> {
+    match &*domain_rc::unfold(ty) {
+        Value::Pi(_, _, codomain) => codomain.apply(argument),
+        _ => unreachable!("constructors have a function type for each argument"),
+    }
+}
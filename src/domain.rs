@@ -1,28 +1,147 @@
 use crate::{
+    domain_rc::same_names,
+    fuel::{unmetered, Fuel, Interrupted},
     index::{Index, Level},
-    syntax::{self, Term, TermRef},
+    meta::MetaContext,
+    normalizer::Normalizer,
+    primitive::{Integer, IntegerType, Output, Primitive},
+    signature::{Definition, Signature},
+    syntax::{
+        self, Branch, Constructor, Global, Hole, Meta, Plicity, Term, TermRef, TypeConstructor,
+    },
 };
+use std::cell::OnceCell;
 
 #[derive(Clone)]
-pub enum Head {
+pub enum Head<'a> {
     Variable(Level),
+    /// A primitive operation, stuck until it is applied to as many literals
+    /// as it takes.
+    Primitive(Primitive),
+    /// A value that the first frame of the spine cannot eliminate, such as a
+    /// type applied to an argument, which only happens in ill-typed terms.
+    Stuck(ValueRef<'a>),
+    /// A metavariable that was unsolved when its environment was set up.
+    Meta(Meta),
+    /// A hole that was not elaborated to a meta, as when normalizing a term
+    /// without checking it. It blocks like a variable.
+    Hole(Hole<'a>),
+    /// The type constructor of a datatype, applied to its parameters by the
+    /// spine.
+    TypeConstructor(TypeConstructor<'a>),
+    /// A constructor, applied to the parameters of its datatype and its
+    /// fields by the spine. Case analysis on it reduces rather than getting
+    /// stuck.
+    Constructor(Constructor<'a>),
+}
+
+/// Type constructors and constructors are compared by position, not by
+/// name. Stuck heads are only equal to themselves here; conversion compares the
+/// values in them.
+impl PartialEq for Head<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Head::Variable(level1), Head::Variable(level2)) => level1 == level2,
+            (Head::Primitive(primitive1), Head::Primitive(primitive2)) => primitive1 == primitive2,
+            (Head::Stuck(value1), Head::Stuck(value2)) => std::ptr::eq(*value1, *value2),
+            (Head::Meta(meta1), Head::Meta(meta2)) => meta1 == meta2,
+            (Head::Hole(hole1), Head::Hole(hole2)) => hole1 == hole2,
+            (
+                Head::TypeConstructor(type_constructor1),
+                Head::TypeConstructor(type_constructor2),
+            ) => type_constructor1.index == type_constructor2.index,
+            (Head::Constructor(constructor1), Head::Constructor(constructor2)) => {
+                constructor1.same(constructor2)
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Head<'_> {}
+
+impl<'a> Head<'a> {
+    pub fn variable(&self) -> Option<Level> {
+        match self {
+            Head::Variable(level) => Some(*level),
+            _ => None,
+        }
+    }
+
+    pub fn primitive(&self) -> Option<Primitive> {
+        match self {
+            Head::Primitive(primitive) => Some(*primitive),
+            _ => None,
+        }
+    }
+
+    pub fn meta(&self) -> Option<Meta> {
+        match self {
+            Head::Meta(meta) => Some(*meta),
+            _ => None,
+        }
+    }
+
+    pub fn hole(&self) -> Option<Hole<'a>> {
+        match self {
+            Head::Hole(hole) => Some(*hole),
+            _ => None,
+        }
+    }
+
+    pub fn type_constructor(&self) -> Option<TypeConstructor<'a>> {
+        match self {
+            Head::TypeConstructor(type_constructor) => Some(*type_constructor),
+            _ => None,
+        }
+    }
+
+    pub fn constructor(&self) -> Option<Constructor<'a>> {
+        match self {
+            Head::Constructor(constructor) => Some(*constructor),
+            _ => None,
+        }
+    }
+}
+
+/// An elimination waiting for a value: an argument to apply it to,
+/// explicitly or implicitly, a projection out of a pair or a record,
+/// recursion on a natural number, or case analysis on a value of a datatype.
+#[derive(Clone, Copy)]
+pub enum Frame<'a> {
+    Application(Plicity, ValueRef<'a>),
+    First,
+    Second,
+    /// The field of a record with the given name.
+    Project(&'a str),
+    NatElim {
+        motive: ValueRef<'a>,
+        zero: ValueRef<'a>,
+        suc: ValueRef<'a>,
+    },
+    /// The branches of a case analysis, whose bodies are evaluated in
+    /// `environment` extended with the fields of the scrutinee.
+    Case {
+        branches: &'a [Branch<'a>],
+        environment: &'a ConstantEnvironment<'a>,
+    },
 }
 
 pub struct Spine<'a> {
-    reversed_values: Vec<ValueRef<'a>>,
+    reversed_frames: Vec<Frame<'a>>,
 }
 
 pub struct ConstantSpine<'a> {
-    values: &'a [ValueRef<'a>],
+    frames: &'a [Frame<'a>],
 }
 
 impl<'a> ConstantSpine<'a> {
     pub fn from_iter<It>(iter: It, builder: &'a Builder) -> Self
     where
-        It: Iterator<Item = ValueRef<'a>>,
+        It: Iterator<Item = Frame<'a>>,
     {
         ConstantSpine {
-            values: builder.arena.emplace_no_drop().from_iter(iter),
+            frames: builder.arena.emplace_no_drop().from_iter(iter),
         }
     }
 
@@ -30,40 +149,58 @@ impl<'a> ConstantSpine<'a> {
         Self::from_iter(spine.iter().copied(), builder)
     }
 
-    pub fn iter(&self) -> impl Iterator<Item = ValueRef<'a>> {
-        self.values.iter().copied()
+    pub fn iter(&self) -> impl Iterator<Item = Frame<'a>> {
+        self.frames.iter().copied()
     }
 }
 
 impl<'a> From<&ConstantSpine<'a>> for Spine<'a> {
     fn from(spine: &ConstantSpine<'a>) -> Self {
         Spine {
-            reversed_values: Vec::from_iter(spine.values.iter().rev().map(|&v| v)),
+            reversed_frames: Vec::from_iter(spine.frames.iter().rev().copied()),
         }
     }
 }
 
+impl<'a> Default for Spine<'a> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<'a> Spine<'a> {
     pub fn new() -> Self {
         Spine {
-            reversed_values: Vec::new(),
+            reversed_frames: Vec::new(),
         }
     }
 
-    pub fn push_front(&mut self, value: ValueRef<'a>) {
-        self.reversed_values.push(value)
+    pub fn push_front(&mut self, frame: Frame<'a>) {
+        self.reversed_frames.push(frame)
+    }
+
+    pub fn pop_front(&mut self) -> Option<Frame<'a>> {
+        self.reversed_frames.pop()
     }
 
-    pub fn pop_front(&mut self) -> Option<ValueRef<'a>> {
-        self.reversed_values.pop()
+    /// Pops the first frame if it is an application, without its plicity:
+    /// lambdas take their argument whichever way it is applied.
+    pub fn pop_argument(&mut self) -> Option<ValueRef<'a>> {
+        match self.reversed_frames.last() {
+            Some(&Frame::Application(_, argument)) => {
+                self.reversed_frames.pop();
+                Some(argument)
+            }
+            _ => None,
+        }
     }
 
-    pub fn iter<'s>(&'s self) -> impl Iterator<Item = &'s ValueRef<'a>> {
-        self.reversed_values.iter().rev()
+    pub fn iter<'s>(&'s self) -> impl Iterator<Item = &'s Frame<'a>> {
+        self.reversed_frames.iter().rev()
     }
 
     pub fn is_empty(&self) -> bool {
-        self.reversed_values.is_empty()
+        self.reversed_frames.is_empty()
     }
 }
 
@@ -72,12 +209,51 @@ pub struct Closure<'a> {
     environment: ConstantEnvironment<'a>,
 }
 
+impl<'a> Closure<'a> {
+    /// Evaluates the body with its bound variable instantiated to `argument`.
+    /// Instantiating it with [`Builder::variable`] at the level of the binder
+    /// opens the body for readback.
+    pub fn apply(&self, argument: ValueRef<'a>, builder: &'a Builder) -> ValueRef<'a> {
+        unmetered(|fuel| self.apply_with_fuel(argument, builder, fuel))
+    }
+
+    /// [`Closure::apply`], which takes a step of `fuel` and then whatever
+    /// evaluating the body takes.
+    pub fn apply_with_fuel(
+        &self,
+        argument: ValueRef<'a>,
+        builder: &'a Builder,
+        fuel: &Fuel,
+    ) -> Result<ValueRef<'a>, Interrupted> {
+        fuel.consume()?;
+        let mut environment = Environment::from(&self.environment);
+        environment.extend(argument);
+        self.term
+            .evaluate_with_fuel(&mut environment, builder, fuel)
+    }
+}
+
 pub enum Value<'a> {
     Neutral {
-        head: Head,
+        head: Head<'a>,
         spine: ConstantSpine<'a>,
     },
-    Lambda(Closure<'a>),
+    Lambda(Plicity, Closure<'a>),
+    Pair(ValueRef<'a>, ValueRef<'a>),
+    Pi(Plicity, TypeRef<'a>, Closure<'a>),
+    Sigma(TypeRef<'a>, Closure<'a>),
+    /// A record type, whose field types are evaluated in the environment
+    /// extended with the fields before them when they are needed.
+    RecordType(&'a [(&'a str, TermRef<'a>)], ConstantEnvironment<'a>),
+    Record(&'a [(&'a str, ValueRef<'a>)]),
+    Type,
+    Nat,
+    /// A natural number literal.
+    Natural(u64),
+    /// The successor of a natural number that is not a literal.
+    Suc(ValueRef<'a>),
+    IntegerType(IntegerType),
+    Integer(Integer),
 }
 
 pub type Type<'a> = Value<'a>;
@@ -87,10 +263,23 @@ pub type TypeRef<'a> = &'a Type<'a>;
 #[derive(Clone)]
 pub struct Environment<'a> {
     values: Vec<ValueRef<'a>>,
+    scope: Scope<'a>,
 }
 
 pub struct ConstantEnvironment<'a> {
     values: &'a [ValueRef<'a>],
+    scope: Scope<'a>,
+}
+
+/// The definitions that globals refer to, along with their unfoldings, and
+/// the solutions of metas. A definition is evaluated the first time one of
+/// its globals is, and its value is shared by every environment the scope is
+/// passed on to.
+#[derive(Clone, Copy, Default)]
+struct Scope<'a> {
+    definitions: &'a [Definition<'a>],
+    unfoldings: &'a [OnceCell<ValueRef<'a>>],
+    solutions: &'a [Option<TermRef<'a>>],
 }
 
 pub struct Builder {
@@ -101,7 +290,7 @@ impl<'a> std::ops::Index<Index> for Environment<'a> {
     type Output = ValueRef<'a>;
 
     fn index(&self, index: Index) -> &Self::Output {
-        &self.values[index.to_int()]
+        &self.values[self.values.len() - index.to_int() - 1]
     }
 }
 
@@ -109,6 +298,7 @@ impl<'a> From<&ConstantEnvironment<'a>> for Environment<'a> {
     fn from(environment: &ConstantEnvironment<'a>) -> Self {
         Environment {
             values: environment.values.into(),
+            scope: environment.scope,
         }
     }
 }
@@ -119,31 +309,276 @@ impl<'a> ConstantEnvironment<'a> {
             values: builder
                 .arena
                 .emplace_no_drop()
-                .from_iter(environment.values.iter().map(|&v| v)),
+                .from_iter(environment.values.iter().copied()),
+            scope: environment.scope,
         }
     }
 }
 
+impl Default for Builder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Builder {
+    pub fn new() -> Self {
+        Builder {
+            arena: blink_alloc::Blink::new(),
+        }
+    }
+
+    /// Normalizes `term`, whose free variables are the first `context` levels,
+    /// and reads the normal form back into `syntax_builder`.
+    pub fn normalize<'a, 'b>(
+        &'a self,
+        term: TermRef<'a>,
+        context: Level,
+        syntax_builder: &'b syntax::Builder,
+    ) -> TermRef<'b> {
+        let mut environment = Environment::free_variables(context.to_int(), self);
+        term.evaluate(&mut environment, self)
+            .quote(context, self, syntax_builder)
+    }
+
+    /// [`Builder::normalize`], metered by `fuel`.
+    pub fn normalize_with_fuel<'a, 'b>(
+        &'a self,
+        term: TermRef<'a>,
+        context: Level,
+        syntax_builder: &'b syntax::Builder,
+        fuel: &Fuel,
+    ) -> Result<TermRef<'b>, Interrupted> {
+        let mut environment = Environment::free_variables(context.to_int(), self);
+        term.evaluate_with_fuel(&mut environment, self, fuel)?
+            .quote_with_fuel(context, self, syntax_builder, fuel)
+    }
+
+    /// Frees every value allocated in this builder, such as those left by an
+    /// interrupted run, so that its memory can be reused.
+    pub fn reset(&mut self) {
+        self.arena.reset();
+    }
+
     pub fn variable<'a>(&'a self, variable: Level) -> ValueRef<'a> {
         self.neutral(
             Head::Variable(variable),
             ConstantSpine {
-                values: self.arena.put_no_drop([]),
+                frames: self.arena.put_no_drop([]),
+            },
+        )
+    }
+
+    pub fn primitive<'a>(&'a self, primitive: Primitive) -> ValueRef<'a> {
+        self.neutral(
+            Head::Primitive(primitive),
+            ConstantSpine {
+                frames: self.arena.put_no_drop([]),
+            },
+        )
+    }
+
+    pub fn meta<'a>(&'a self, meta: Meta) -> ValueRef<'a> {
+        self.neutral(
+            Head::Meta(meta),
+            ConstantSpine {
+                frames: self.arena.put_no_drop([]),
             },
         )
     }
 
-    pub fn neutral<'a>(&'a self, head: Head, spine: ConstantSpine<'a>) -> ValueRef<'a> {
+    pub fn hole<'a>(&'a self, hole: Hole<'a>) -> ValueRef<'a> {
+        self.neutral(
+            Head::Hole(hole),
+            ConstantSpine {
+                frames: self.arena.put_no_drop([]),
+            },
+        )
+    }
+
+    pub fn type_constructor<'a>(&'a self, type_constructor: TypeConstructor<'a>) -> ValueRef<'a> {
+        self.neutral(
+            Head::TypeConstructor(type_constructor),
+            ConstantSpine {
+                frames: self.arena.put_no_drop([]),
+            },
+        )
+    }
+
+    pub fn constructor<'a>(&'a self, constructor: Constructor<'a>) -> ValueRef<'a> {
+        self.neutral(
+            Head::Constructor(constructor),
+            ConstantSpine {
+                frames: self.arena.put_no_drop([]),
+            },
+        )
+    }
+
+    pub fn neutral<'a>(&'a self, head: Head<'a>, spine: ConstantSpine<'a>) -> ValueRef<'a> {
         self.arena.put_no_drop(Value::Neutral { head, spine })
     }
 
     pub fn lambda<'a>(&'a self, body: Closure<'a>) -> ValueRef<'a> {
-        self.arena.put_no_drop(Value::Lambda(body))
+        self.lambda_with(Plicity::Explicit, body)
+    }
+
+    pub fn lambda_with<'a>(&'a self, plicity: Plicity, body: Closure<'a>) -> ValueRef<'a> {
+        self.arena.put_no_drop(Value::Lambda(plicity, body))
+    }
+
+    pub fn pair<'a>(&'a self, first: ValueRef<'a>, second: ValueRef<'a>) -> ValueRef<'a> {
+        self.arena.put_no_drop(Value::Pair(first, second))
+    }
+
+    pub fn pi<'a>(&'a self, domain: TypeRef<'a>, codomain: Closure<'a>) -> ValueRef<'a> {
+        self.pi_with(Plicity::Explicit, domain, codomain)
+    }
+
+    pub fn pi_with<'a>(
+        &'a self,
+        plicity: Plicity,
+        domain: TypeRef<'a>,
+        codomain: Closure<'a>,
+    ) -> ValueRef<'a> {
+        self.arena.put_no_drop(Value::Pi(plicity, domain, codomain))
+    }
+
+    pub fn sigma<'a>(&'a self, domain: TypeRef<'a>, codomain: Closure<'a>) -> ValueRef<'a> {
+        self.arena.put_no_drop(Value::Sigma(domain, codomain))
+    }
+
+    pub fn record_type<'a>(
+        &'a self,
+        fields: &'a [(&'a str, TermRef<'a>)],
+        environment: ConstantEnvironment<'a>,
+    ) -> ValueRef<'a> {
+        self.arena
+            .put_no_drop(Value::RecordType(fields, environment))
+    }
+
+    pub fn record<'a>(
+        &'a self,
+        fields: impl Iterator<Item = (&'a str, ValueRef<'a>)>,
+    ) -> ValueRef<'a> {
+        let fields = self.arena.emplace_no_drop().from_iter(fields);
+        self.arena.put_no_drop(Value::Record(fields))
+    }
+
+    pub fn universe<'a>(&'a self) -> ValueRef<'a> {
+        self.arena.put_no_drop(Value::Type)
+    }
+
+    pub fn nat<'a>(&'a self) -> ValueRef<'a> {
+        self.arena.put_no_drop(Value::Nat)
+    }
+
+    pub fn natural<'a>(&'a self, value: u64) -> ValueRef<'a> {
+        self.arena.put_no_drop(Value::Natural(value))
+    }
+
+    /// The successor of `value`, which is a literal if `value` is a literal
+    /// below `u64::MAX`.
+    pub fn suc<'a>(&'a self, value: ValueRef<'a>) -> ValueRef<'a> {
+        match value {
+            Value::Natural(value) if *value < u64::MAX => self.natural(value + 1),
+            _ => self.arena.put_no_drop(Value::Suc(value)),
+        }
+    }
+
+    pub fn integer_type<'a>(&'a self, ty: IntegerType) -> ValueRef<'a> {
+        self.arena.put_no_drop(Value::IntegerType(ty))
+    }
+
+    pub fn integer<'a>(&'a self, value: Integer) -> ValueRef<'a> {
+        self.arena.put_no_drop(Value::Integer(value))
+    }
+}
+
+impl<'a> Default for Environment<'a> {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
 impl<'a> Environment<'a> {
+    pub fn new() -> Self {
+        Environment {
+            values: Vec::new(),
+            scope: Scope::default(),
+        }
+    }
+
+    /// An environment for an open term with `size` free variables, each bound
+    /// to itself. Values evaluated in it should be quoted at `Level(size)`.
+    pub fn free_variables(size: usize, builder: &'a Builder) -> Self {
+        Environment {
+            values: (0..size)
+                .map(|level| builder.variable(Level(level)))
+                .collect(),
+            scope: Scope::default(),
+        }
+    }
+
+    /// Brings the definitions of `signature` into scope for the globals of
+    /// the terms evaluated in this environment.
+    pub fn with_signature(self, signature: &'a Signature<'a>, builder: &'a Builder) -> Self {
+        let definitions = signature.definitions();
+        let unfoldings = builder
+            .arena
+            .emplace_no_drop()
+            .from_iter(definitions.iter().map(|_| OnceCell::new()));
+        Environment {
+            scope: Scope {
+                definitions,
+                unfoldings,
+                ..self.scope
+            },
+            ..self
+        }
+    }
+
+    /// Makes the metas of the terms evaluated in this environment refer to
+    /// `metas`, as they are solved now: metas solved later stay neutral. The
+    /// solutions refer to the globals of the signature of `metas`, which
+    /// should be the one in scope here.
+    pub fn with_metas<'m: 'a>(self, metas: &MetaContext<'m>, builder: &'a Builder) -> Self {
+        let solutions = builder
+            .arena
+            .emplace_no_drop()
+            .from_iter(metas.solutions().into_iter());
+        Environment {
+            scope: Scope {
+                solutions,
+                ..self.scope
+            },
+            ..self
+        }
+    }
+
+    /// The unfolding of `global` if it has been evaluated already, or else
+    /// the body of its definition, to be evaluated in an environment of its
+    /// own.
+    fn global(&self, global: Global<'a>) -> Result<ValueRef<'a>, TermRef<'a>> {
+        let definition = self
+            .scope
+            .definitions
+            .get(global.index)
+            .unwrap_or_else(|| panic!("global {} is not in the signature", global.name));
+        match self.scope.unfoldings[global.index].get() {
+            Some(unfolding) => Ok(unfolding),
+            None => Err(definition.term),
+        }
+    }
+
+    /// The solution of `meta`, if it has one.
+    fn meta(&self, meta: Meta) -> Option<TermRef<'a>> {
+        *self
+            .scope
+            .solutions
+            .get(meta.0)
+            .unwrap_or_else(|| panic!("meta {meta} is not in the metacontext"))
+    }
+
     pub fn extend(&mut self, value: ValueRef<'a>) {
         self.values.push(value)
     }
@@ -161,49 +596,454 @@ impl<'a> Environment<'a> {
 }
 
 impl<'a> Value<'a> {
-    pub fn apply(&self, argument: ValueRef<'a>, builder: &'a Builder) -> ValueRef<'a> {
+    /// The head of a neutral.
+    pub fn head(&self) -> Option<&Head<'a>> {
         match self {
-            Value::Neutral { head, spine } => builder.neutral(
-                head.clone(),
-                ConstantSpine::from_iter(spine.iter().chain(std::iter::once(argument)), builder),
-            ),
-            Value::Lambda(Closure { term, environment }) => {
-                let mut environment = Environment::from(environment);
-                environment.extend(argument);
-                term.evaluate(&mut environment, builder)
-            }
+            Value::Neutral { head, .. } => Some(head),
+            _ => None,
         }
     }
 
-    pub fn apply_spine(
+    /// The frames of the spine of a neutral, the one applied to the head
+    /// first; none for other values.
+    pub fn spine(&self) -> impl Iterator<Item = Frame<'a>> + '_ {
+        match self {
+            Value::Neutral { spine, .. } => spine.frames.iter(),
+            _ => [].iter(),
+        }
+        .copied()
+    }
+
+    pub fn apply(self: ValueRef<'a>, argument: ValueRef<'a>, builder: &'a Builder) -> ValueRef<'a> {
+        self.apply_with(Plicity::Explicit, argument, builder)
+    }
+
+    /// Applies this value to `argument`, explicitly or implicitly.
+    pub fn apply_with(
         self: ValueRef<'a>,
-        mut spine: Spine<'a>,
+        plicity: Plicity,
+        argument: ValueRef<'a>,
+        builder: &'a Builder,
+    ) -> ValueRef<'a> {
+        unmetered(|fuel| self.apply_with_fuel(plicity, argument, builder, fuel))
+    }
+
+    /// [`Value::apply_with`], which takes a step of `fuel` if this is a
+    /// lambda, and then whatever evaluating its body takes.
+    pub fn apply_with_fuel(
+        self: ValueRef<'a>,
+        plicity: Plicity,
+        argument: ValueRef<'a>,
+        builder: &'a Builder,
+        fuel: &Fuel,
+    ) -> Result<ValueRef<'a>, Interrupted> {
+        match self {
+            Value::Lambda(_, closure) => closure.apply_with_fuel(argument, builder, fuel),
+            _ => self.eliminate(Frame::Application(plicity, argument), builder, fuel),
+        }
+    }
+
+    /// Projects a component out of a pair, where `projection` is
+    /// [`Frame::First`] or [`Frame::Second`].
+    pub fn project(
+        self: ValueRef<'a>,
+        projection: Frame<'a>,
         builder: &'a Builder,
     ) -> ValueRef<'a> {
-        if spine.is_empty() {
-            return self;
+        unmetered(|fuel| self.project_with_fuel(projection, builder, fuel))
+    }
+
+    fn project_with_fuel(
+        self: ValueRef<'a>,
+        projection: Frame<'a>,
+        builder: &'a Builder,
+        fuel: &Fuel,
+    ) -> Result<ValueRef<'a>, Interrupted> {
+        match (self, projection) {
+            (
+                _,
+                Frame::Application(..)
+                | Frame::Project(_)
+                | Frame::NatElim { .. }
+                | Frame::Case { .. },
+            ) => panic!("projected with a frame that is not a projection out of a pair"),
+            (Value::Pair(first, _), Frame::First) => Ok(first),
+            (Value::Pair(_, second), Frame::Second) => Ok(second),
+            _ => self.eliminate(projection, builder, fuel),
         }
+    }
+
+    /// Projects the field `name` out of a record, which is stuck if this is
+    /// not a record with that field.
+    pub fn project_field(self: ValueRef<'a>, name: &'a str, builder: &'a Builder) -> ValueRef<'a> {
+        unmetered(|fuel| self.project_field_with_fuel(name, builder, fuel))
+    }
+
+    fn project_field_with_fuel(
+        self: ValueRef<'a>,
+        name: &'a str,
+        builder: &'a Builder,
+        fuel: &Fuel,
+    ) -> Result<ValueRef<'a>, Interrupted> {
         match self {
-            Value::Neutral {
-                head,
-                spine: function_spine,
-            } => {
-                let spine = ConstantSpine::from_iter(
-                    function_spine.iter().chain(spine.iter().copied()),
-                    builder,
-                );
-                builder.neutral(head.clone(), spine)
+            Value::Record(fields) => match fields.iter().find(|&&(field, _)| field == name) {
+                Some(&(_, value)) => Ok(value),
+                None => self.eliminate(Frame::Project(name), builder, fuel),
+            },
+            _ => self.eliminate(Frame::Project(name), builder, fuel),
+        }
+    }
+
+    /// Primitive recursion on a natural number, which blocks if it is
+    /// neutral and is stuck if it is not a natural number.
+    ///
+    /// The recursion runs bottom up in a loop, applying `suc` once for each
+    /// successor, so large literals do not need a deep stack.
+    pub fn nat_elim(
+        self: ValueRef<'a>,
+        motive: ValueRef<'a>,
+        zero: ValueRef<'a>,
+        suc: ValueRef<'a>,
+        builder: &'a Builder,
+    ) -> ValueRef<'a> {
+        unmetered(|fuel| self.nat_elim_with_fuel(motive, zero, suc, builder, fuel))
+    }
+
+    fn nat_elim_with_fuel(
+        self: ValueRef<'a>,
+        motive: ValueRef<'a>,
+        zero: ValueRef<'a>,
+        suc: ValueRef<'a>,
+        builder: &'a Builder,
+        fuel: &Fuel,
+    ) -> Result<ValueRef<'a>, Interrupted> {
+        let mut predecessors = Vec::new();
+        let mut scrutinee = self;
+        while let Value::Suc(predecessor) = scrutinee {
+            predecessors.push(*predecessor);
+            scrutinee = predecessor;
+        }
+        let mut result = match scrutinee {
+            Value::Natural(value) => (0..*value).try_fold(zero, |result, predecessor| {
+                apply_suc(suc, builder.natural(predecessor), result, builder, fuel)
+            })?,
+            _ => scrutinee.eliminate(Frame::NatElim { motive, zero, suc }, builder, fuel)?,
+        };
+        for predecessor in predecessors.into_iter().rev() {
+            result = apply_suc(suc, predecessor, result, builder, fuel)?;
+        }
+        Ok(result)
+    }
+
+    /// Case analysis on a value of a datatype, which selects a branch if it
+    /// is a constructor, blocks if it is any other neutral, and is stuck if it
+    /// is not of a datatype.
+    pub fn case(
+        self: ValueRef<'a>,
+        branches: &'a [Branch<'a>],
+        environment: &'a ConstantEnvironment<'a>,
+        builder: &'a Builder,
+    ) -> ValueRef<'a> {
+        let frame = Frame::Case {
+            branches,
+            environment,
+        };
+        unmetered(|fuel| self.eliminate(frame, builder, fuel))
+    }
+
+    /// Extends a neutral with `frame`, or makes a stuck neutral of a value
+    /// that `frame` cannot eliminate.
+    fn eliminate(
+        self: ValueRef<'a>,
+        frame: Frame<'a>,
+        builder: &'a Builder,
+        fuel: &Fuel,
+    ) -> Result<ValueRef<'a>, Interrupted> {
+        match self {
+            Value::Neutral { head, spine } => {
+                extend_neutral(head, spine, std::iter::once(frame), builder, fuel)
             }
-            Value::Lambda(Closure { term, environment }) => {
-                if let Some(argument) = spine.pop_front() {
+            _ => Ok(builder.neutral(
+                Head::Stuck(self),
+                ConstantSpine::from_iter(std::iter::once(frame), builder),
+            )),
+        }
+    }
+
+    pub fn apply_spine(self: ValueRef<'a>, spine: Spine<'a>, builder: &'a Builder) -> ValueRef<'a> {
+        unmetered(|fuel| self.apply_spine_with_fuel(spine, builder, fuel))
+    }
+
+    /// [`Value::apply_spine`], which takes a step of `fuel` for each lambda
+    /// that is applied, and then whatever evaluating their bodies takes.
+    pub fn apply_spine_with_fuel(
+        self: ValueRef<'a>,
+        mut spine: Spine<'a>,
+        builder: &'a Builder,
+        fuel: &Fuel,
+    ) -> Result<ValueRef<'a>, Interrupted> {
+        let mut function = self;
+        while let Some(frame) = spine.pop_front() {
+            function = match (function, frame) {
+                (
+                    Value::Neutral {
+                        head,
+                        spine: function_spine,
+                    },
+                    frame,
+                ) => {
+                    return extend_neutral(
+                        head,
+                        function_spine,
+                        std::iter::once(frame).chain(spine.iter().copied()),
+                        builder,
+                        fuel,
+                    )
+                }
+                (
+                    Value::Lambda(_, Closure { term, environment }),
+                    Frame::Application(_, argument),
+                ) => {
+                    fuel.consume()?;
                     let mut environment = Environment::from(environment);
                     environment.extend(argument);
-                    term.evaluate_with_spine(spine, &mut environment, builder)
-                } else {
-                    self
+                    return term.machine(spine, &mut environment, builder, fuel);
+                }
+                (Value::Pair(first, _), Frame::First) => first,
+                (Value::Pair(_, second), Frame::Second) => second,
+                (Value::Record(_), Frame::Project(name)) => {
+                    function.project_field_with_fuel(name, builder, fuel)?
+                }
+                (Value::Natural(_) | Value::Suc(_), Frame::NatElim { motive, zero, suc }) => {
+                    function.nat_elim_with_fuel(motive, zero, suc, builder, fuel)?
+                }
+                (_, frame) => {
+                    let spine = ConstantSpine::from_iter(
+                        std::iter::once(frame).chain(spine.iter().copied()),
+                        builder,
+                    );
+                    return Ok(builder.neutral(Head::Stuck(function), spine));
+                }
+            };
+        }
+        Ok(function)
+    }
+}
+
+/// Adds `frames` to a neutral. A primitive that this saturates with literals
+/// computes, and the frames beyond its arguments are applied to its result.
+/// A constructor that this analyses by cases selects its branch, which the
+/// frames after the case analysis are applied to.
+fn extend_neutral<'a>(
+    head: &Head<'a>,
+    spine: &ConstantSpine<'a>,
+    frames: impl Iterator<Item = Frame<'a>>,
+    builder: &'a Builder,
+    fuel: &Fuel,
+) -> Result<ValueRef<'a>, Interrupted> {
+    let mut frames = spine.iter().chain(frames);
+    if let Head::Primitive(primitive) = head {
+        let arguments = Vec::from_iter(frames.by_ref().take(primitive.arity()));
+        if arguments.len() == primitive.arity() {
+            if let Some(result) = compute(*primitive, &arguments, builder) {
+                let mut reversed_frames = Vec::from_iter(frames);
+                reversed_frames.reverse();
+                return result.apply_spine_with_fuel(Spine { reversed_frames }, builder, fuel);
+            }
+        }
+        let spine = ConstantSpine::from_iter(arguments.into_iter().chain(frames), builder);
+        return Ok(builder.neutral(head.clone(), spine));
+    }
+    if let Head::Constructor(constructor) = head {
+        let mut arguments = Vec::new();
+        for frame in frames.by_ref() {
+            if let Frame::Case {
+                branches,
+                environment,
+            } = frame
+            {
+                if let Some((body, mut environment)) =
+                    select(constructor, &arguments, branches, environment)
+                {
+                    let mut reversed_frames = Vec::from_iter(frames);
+                    reversed_frames.reverse();
+                    return body.machine(
+                        Spine { reversed_frames },
+                        &mut environment,
+                        builder,
+                        fuel,
+                    );
                 }
             }
+            arguments.push(frame);
         }
+        return Ok(builder.neutral(
+            head.clone(),
+            ConstantSpine::from_iter(arguments.into_iter(), builder),
+        ));
+    }
+    Ok(builder.neutral(head.clone(), ConstantSpine::from_iter(frames, builder)))
+}
+
+/// The body of the branch for `constructor`, applied to `arguments`, and the
+/// environment to evaluate it in: that of the branches, extended with the
+/// fields, which are the last arguments. Returns `None` if there is no such
+/// branch, which leaves the case analysis stuck.
+fn select<'a>(
+    constructor: &Constructor<'a>,
+    arguments: &[Frame<'a>],
+    branches: &'a [Branch<'a>],
+    environment: &ConstantEnvironment<'a>,
+) -> Option<(TermRef<'a>, Environment<'a>)> {
+    let branch = (branches.iter()).find(|branch| branch.constructor.same(constructor))?;
+    let parameters = arguments.len().checked_sub(branch.arity)?;
+    let mut environment = Environment::from(environment);
+    for frame in &arguments[parameters..] {
+        match frame {
+            Frame::Application(_, field) => environment.extend(field),
+            _ => return None,
+        }
+    }
+    Some((branch.body, environment))
+}
+
+/// The types of the fields of the record type of `fields` in `environment`,
+/// each evaluated with the fields before it bound to what `field` gives for
+/// their positions and names.
+fn field_types<'a>(
+    fields: &'a [(&'a str, TermRef<'a>)],
+    environment: &ConstantEnvironment<'a>,
+    field: impl FnMut(usize, &'a str) -> ValueRef<'a>,
+    builder: &'a Builder,
+) -> Vec<ValueRef<'a>> {
+    unmetered(|fuel| field_types_with_fuel(fields, environment, field, builder, fuel))
+}
+
+fn field_types_with_fuel<'a>(
+    fields: &'a [(&'a str, TermRef<'a>)],
+    environment: &ConstantEnvironment<'a>,
+    mut field: impl FnMut(usize, &'a str) -> ValueRef<'a>,
+    builder: &'a Builder,
+    fuel: &Fuel,
+) -> Result<Vec<ValueRef<'a>>, Interrupted> {
+    let mut environment = Environment::from(environment);
+    (fields.iter().enumerate())
+        .map(|(position, &(name, ty))| {
+            let ty = ty.evaluate_with_fuel(&mut environment.clone(), builder, fuel)?;
+            environment.extend(field(position, name));
+            Ok(ty)
+        })
+        .collect()
+}
+
+/// Applies `primitive` to the arguments in `spine`, if they are all literals.
+fn compute<'a>(
+    primitive: Primitive,
+    spine: &[Frame<'a>],
+    builder: &'a Builder,
+) -> Option<ValueRef<'a>> {
+    let mut arguments = Vec::with_capacity(spine.len());
+    for frame in spine {
+        match frame {
+            Frame::Application(_, Value::Integer(value)) => arguments.push(*value),
+            _ => return None,
+        }
+    }
+    Some(match primitive.apply(&arguments)? {
+        Output::Integer(value) => builder.integer(value),
+        Output::Natural(value) => builder.natural(value),
+    })
+}
+
+/// Applies the successor case of a recursion to a predecessor and the
+/// result for it, in one run of the evaluator.
+fn apply_suc<'a>(
+    suc: ValueRef<'a>,
+    predecessor: ValueRef<'a>,
+    result: ValueRef<'a>,
+    builder: &'a Builder,
+    fuel: &Fuel,
+) -> Result<ValueRef<'a>, Interrupted> {
+    let mut spine = Spine::new();
+    spine.push_front(Frame::Application(Plicity::Explicit, result));
+    spine.push_front(Frame::Application(Plicity::Explicit, predecessor));
+    suc.apply_spine_with_fuel(spine, builder, fuel)
+}
+
+/// What to do with the value of the term currently being evaluated. The
+/// evaluator keeps these on an explicit stack rather than recursing, so the
+/// depth of the terms it can handle is bounded by the heap.
+enum Continuation<'a> {
+    /// The value is the argument of an application: push it onto `spine`
+    /// and evaluate `function`, in the environment as it was when the
+    /// argument was entered.
+    Argument {
+        plicity: Plicity,
+        function: TermRef<'a>,
+        spine: Spine<'a>,
+        environment_length: usize,
+    },
+    /// The value is bound by a `let`: extend the environment, as it was when
+    /// the bound term was entered, with it and evaluate `body` against `spine`.
+    Let {
+        body: TermRef<'a>,
+        spine: Spine<'a>,
+        environment_length: usize,
+    },
+    /// The value is the domain of a dependent function type whose codomain
+    /// is `codomain`, in the environment as it was when the domain was
+    /// entered.
+    Pi {
+        plicity: Plicity,
+        codomain: TermRef<'a>,
+        spine: Spine<'a>,
+        environment_length: usize,
+    },
+    /// The value is the domain of a dependent pair type whose codomain is
+    /// `codomain`, in the environment as it was when the domain was entered.
+    Sigma {
+        codomain: TermRef<'a>,
+        spine: Spine<'a>,
+        environment_length: usize,
+    },
+    /// The value is the first component of a pair whose second component is
+    /// `second`, in the environment as it was when the first was entered.
+    PairFirst {
+        second: TermRef<'a>,
+        spine: Spine<'a>,
+        environment_length: usize,
+    },
+    /// The value is the second component of a pair whose first component
+    /// is `first`.
+    PairSecond {
+        first: ValueRef<'a>,
+        spine: Spine<'a>,
+    },
+    /// The value is the predecessor of a natural number.
+    Suc { spine: Spine<'a> },
+    /// The value is that of the definition of `global`, which should be
+    /// memoised as its unfolding before it is applied to `spine`.
+    Unfold {
+        global: Global<'a>,
+        spine: Spine<'a>,
+    },
+    /// The value is the body of a closure that was entered from `Environment`,
+    /// which should be reinstated.
+    Restore(Environment<'a>),
+}
+
+/// Replaces the current environment with `new_environment`, remembering the
+/// current one if any pending continuation still refers to it.
+fn switch_environment<'a>(
+    environment: &mut Environment<'a>,
+    new_environment: Environment<'a>,
+    continuations: &mut Vec<Continuation<'a>>,
+) {
+    let environment = std::mem::replace(environment, new_environment);
+    // Only the continuations pushed since the last `Restore` refer to the
+    // current environment.
+    if !matches!(continuations.last(), Some(Continuation::Restore(_))) {
+        continuations.push(Continuation::Restore(environment));
     }
 }
 
@@ -218,72 +1058,951 @@ impl<'a> Term<'a> {
 
     pub fn evaluate_with_spine(
         &self,
-        mut spine: Spine<'a>,
+        spine: Spine<'a>,
         environment: &mut Environment<'a>,
         builder: &'a Builder,
     ) -> ValueRef<'a> {
-        match self {
-            Term::Variable(index) => {
-                let head = environment[*index];
-                head.apply_spine(spine, builder)
+        unmetered(|fuel| self.machine(spine, environment, builder, fuel))
+    }
+
+    /// [`Term::evaluate`], which is [`Interrupted`] once it has taken more beta
+    /// steps than `fuel` allows or `fuel` is cancelled, with `environment` put
+    /// back as it was. What it allocated stays in `builder` until that is
+    /// [reset](Builder::reset) or dropped.
+    pub fn evaluate_with_fuel(
+        &self,
+        environment: &mut Environment<'a>,
+        builder: &'a Builder,
+        fuel: &Fuel,
+    ) -> Result<ValueRef<'a>, Interrupted> {
+        self.machine(Spine::new(), environment, builder, fuel)
+    }
+
+    /// Evaluates the term to weak head normal form, without reading it back.
+    /// Globals and solved metas are unfolded as they are evaluated and
+    /// evaluation stops at the head anyway, so this is [`Term::evaluate`].
+    pub fn whnf(&self, environment: &mut Environment<'a>, builder: &'a Builder) -> ValueRef<'a> {
+        self.evaluate(environment, builder)
+    }
+
+    /// [`Term::whnf`], metered by `fuel`.
+    pub fn whnf_with_fuel(
+        &self,
+        environment: &mut Environment<'a>,
+        builder: &'a Builder,
+        fuel: &Fuel,
+    ) -> Result<ValueRef<'a>, Interrupted> {
+        self.evaluate_with_fuel(environment, builder, fuel)
+    }
+
+    fn machine(
+        &self,
+        spine: Spine<'a>,
+        environment: &mut Environment<'a>,
+        builder: &'a Builder,
+        fuel: &Fuel,
+    ) -> Result<ValueRef<'a>, Interrupted> {
+        let environment_length = environment.values.len();
+        let mut continuations = Vec::new();
+        let result = self.run_machine(spine, environment, &mut continuations, builder, fuel);
+        if result.is_err() {
+            // Put back the environment the interrupted run was entered with,
+            // which the earliest `Restore` holds.
+            while let Some(continuation) = continuations.pop() {
+                if let Continuation::Restore(saved_environment) = continuation {
+                    *environment = saved_environment;
+                }
             }
-            Term::Lambda(body) => {
-                if let Some(argument) = spine.pop_front() {
-                    environment.extend(argument);
-                    body.evaluate_with_spine(spine, environment, builder)
-                } else {
-                    builder.lambda(Closure {
-                        term: body,
-                        environment: ConstantEnvironment::from(environment, builder),
-                    })
+            environment.values.truncate(environment_length);
+        }
+        result
+    }
+
+    fn run_machine(
+        &self,
+        mut spine: Spine<'a>,
+        environment: &mut Environment<'a>,
+        continuations: &mut Vec<Continuation<'a>>,
+        builder: &'a Builder,
+        fuel: &Fuel,
+    ) -> Result<ValueRef<'a>, Interrupted> {
+        let mut head = self;
+        loop {
+            let mut value = match head {
+                Term::Variable(index) => {
+                    let function = environment[*index];
+                    if let Value::Lambda(
+                        _,
+                        Closure {
+                            term,
+                            environment: closure_environment,
+                        },
+                    ) = function
+                    {
+                        if let Some(argument) = spine.pop_argument() {
+                            fuel.consume()?;
+                            let mut closure_environment = Environment::from(closure_environment);
+                            closure_environment.extend(argument);
+                            switch_environment(environment, closure_environment, continuations);
+                            head = term;
+                            continue;
+                        }
+                    }
+                    function.apply_spine_with_fuel(std::mem::take(&mut spine), builder, fuel)?
+                }
+                Term::Lambda(plicity, _, body) => {
+                    if let Some(argument) = spine.pop_argument() {
+                        fuel.consume()?;
+                        environment.extend(argument);
+                        head = body;
+                        continue;
+                    } else {
+                        builder
+                            .lambda_with(
+                                *plicity,
+                                Closure {
+                                    term: body,
+                                    environment: ConstantEnvironment::from(environment, builder),
+                                },
+                            )
+                            .apply_spine_with_fuel(std::mem::take(&mut spine), builder, fuel)?
+                    }
+                }
+                Term::Application(plicity, function, argument) => {
+                    continuations.push(Continuation::Argument {
+                        plicity: *plicity,
+                        function,
+                        spine: std::mem::take(&mut spine),
+                        environment_length: environment.values.len(),
+                    });
+                    head = argument;
+                    continue;
+                }
+                Term::Let(value, body) => {
+                    continuations.push(Continuation::Let {
+                        body,
+                        spine: std::mem::take(&mut spine),
+                        environment_length: environment.values.len(),
+                    });
+                    head = value;
+                    continue;
+                }
+                Term::Pi(plicity, domain, codomain) => {
+                    continuations.push(Continuation::Pi {
+                        plicity: *plicity,
+                        codomain,
+                        spine: std::mem::take(&mut spine),
+                        environment_length: environment.values.len(),
+                    });
+                    head = domain;
+                    continue;
+                }
+                Term::Sigma(domain, codomain) => {
+                    continuations.push(Continuation::Sigma {
+                        codomain,
+                        spine: std::mem::take(&mut spine),
+                        environment_length: environment.values.len(),
+                    });
+                    head = domain;
+                    continue;
+                }
+                Term::Pair(first, second) => {
+                    continuations.push(Continuation::PairFirst {
+                        second,
+                        spine: std::mem::take(&mut spine),
+                        environment_length: environment.values.len(),
+                    });
+                    head = first;
+                    continue;
+                }
+                Term::RecordType(fields) => builder
+                    .record_type(fields, ConstantEnvironment::from(environment, builder))
+                    .apply_spine_with_fuel(std::mem::take(&mut spine), builder, fuel)?,
+                Term::Record(fields) => {
+                    let fields = (fields.iter())
+                        .map(|&(name, field)| {
+                            let field = field.evaluate_with_fuel(
+                                &mut environment.clone(),
+                                builder,
+                                fuel,
+                            )?;
+                            Ok((name, field))
+                        })
+                        .collect::<Result<Vec<_>, _>>()?;
+                    builder.record(fields.into_iter()).apply_spine_with_fuel(
+                        std::mem::take(&mut spine),
+                        builder,
+                        fuel,
+                    )?
+                }
+                Term::Project(record, name) => {
+                    spine.push_front(Frame::Project(name));
+                    head = record;
+                    continue;
+                }
+                Term::Fst(pair) => {
+                    spine.push_front(Frame::First);
+                    head = pair;
+                    continue;
+                }
+                Term::Snd(pair) => {
+                    spine.push_front(Frame::Second);
+                    head = pair;
+                    continue;
+                }
+                Term::Type => builder.universe().apply_spine_with_fuel(
+                    std::mem::take(&mut spine),
+                    builder,
+                    fuel,
+                )?,
+                Term::Nat => builder.nat().apply_spine_with_fuel(
+                    std::mem::take(&mut spine),
+                    builder,
+                    fuel,
+                )?,
+                Term::Zero => builder.natural(0).apply_spine_with_fuel(
+                    std::mem::take(&mut spine),
+                    builder,
+                    fuel,
+                )?,
+                Term::Natural(value) => builder.natural(*value).apply_spine_with_fuel(
+                    std::mem::take(&mut spine),
+                    builder,
+                    fuel,
+                )?,
+                Term::IntegerType(ty) => builder.integer_type(*ty).apply_spine_with_fuel(
+                    std::mem::take(&mut spine),
+                    builder,
+                    fuel,
+                )?,
+                Term::Integer(value) => builder.integer(*value).apply_spine_with_fuel(
+                    std::mem::take(&mut spine),
+                    builder,
+                    fuel,
+                )?,
+                Term::Primitive(primitive) => builder.primitive(*primitive).apply_spine_with_fuel(
+                    std::mem::take(&mut spine),
+                    builder,
+                    fuel,
+                )?,
+                Term::Global(global) => match environment.global(*global) {
+                    Ok(unfolding) => unfolding.apply_spine_with_fuel(
+                        std::mem::take(&mut spine),
+                        builder,
+                        fuel,
+                    )?,
+                    Err(term) => {
+                        let definition_environment = Environment {
+                            values: Vec::new(),
+                            scope: environment.scope,
+                        };
+                        switch_environment(environment, definition_environment, continuations);
+                        continuations.push(Continuation::Unfold {
+                            global: *global,
+                            spine: std::mem::take(&mut spine),
+                        });
+                        head = term;
+                        continue;
+                    }
+                },
+                // A solution is closed, so it is evaluated in an empty
+                // environment, where it takes the spine as its arguments.
+                Term::Meta(meta) => match environment.meta(*meta) {
+                    Some(solution) => {
+                        let solution_environment = Environment {
+                            values: Vec::new(),
+                            scope: environment.scope,
+                        };
+                        switch_environment(environment, solution_environment, continuations);
+                        head = solution;
+                        continue;
+                    }
+                    None => builder.meta(*meta).apply_spine_with_fuel(
+                        std::mem::take(&mut spine),
+                        builder,
+                        fuel,
+                    )?,
+                },
+                Term::Hole(hole) => builder.hole(*hole).apply_spine_with_fuel(
+                    std::mem::take(&mut spine),
+                    builder,
+                    fuel,
+                )?,
+                Term::TypeConstructor(type_constructor) => builder
+                    .type_constructor(*type_constructor)
+                    .apply_spine_with_fuel(std::mem::take(&mut spine), builder, fuel)?,
+                Term::Constructor(constructor) => builder
+                    .constructor(*constructor)
+                    .apply_spine_with_fuel(std::mem::take(&mut spine), builder, fuel)?,
+                Term::Case(scrutinee, branches) => {
+                    spine.push_front(Frame::Case {
+                        branches,
+                        environment: builder
+                            .arena
+                            .put_no_drop(ConstantEnvironment::from(environment, builder)),
+                    });
+                    head = scrutinee;
+                    continue;
+                }
+                Term::Suc(predecessor) => {
+                    continuations.push(Continuation::Suc {
+                        spine: std::mem::take(&mut spine),
+                    });
+                    head = predecessor;
+                    continue;
+                }
+                Term::NatElim(motive, zero, suc, scrutinee) => {
+                    spine.push_front(Frame::NatElim {
+                        motive: motive.evaluate_with_fuel(
+                            &mut environment.clone(),
+                            builder,
+                            fuel,
+                        )?,
+                        zero: zero.evaluate_with_fuel(&mut environment.clone(), builder, fuel)?,
+                        suc: suc.evaluate_with_fuel(&mut environment.clone(), builder, fuel)?,
+                    });
+                    head = scrutinee;
+                    continue;
+                }
+            };
+            loop {
+                match continuations.pop() {
+                    None => return Ok(value),
+                    Some(Continuation::Restore(saved_environment)) => {
+                        *environment = saved_environment;
+                    }
+                    Some(Continuation::Argument {
+                        plicity,
+                        function,
+                        spine: function_spine,
+                        environment_length,
+                    }) => {
+                        environment.values.truncate(environment_length);
+                        spine = function_spine;
+                        spine.push_front(Frame::Application(plicity, value));
+                        head = function;
+                        break;
+                    }
+                    Some(Continuation::Let {
+                        body,
+                        spine: body_spine,
+                        environment_length,
+                    }) => {
+                        environment.values.truncate(environment_length);
+                        environment.extend(value);
+                        spine = body_spine;
+                        head = body;
+                        break;
+                    }
+                    Some(Continuation::Pi {
+                        plicity,
+                        codomain,
+                        spine: pi_spine,
+                        environment_length,
+                    }) => {
+                        environment.values.truncate(environment_length);
+                        value = builder
+                            .pi_with(
+                                plicity,
+                                value,
+                                Closure {
+                                    term: codomain,
+                                    environment: ConstantEnvironment::from(environment, builder),
+                                },
+                            )
+                            .apply_spine_with_fuel(pi_spine, builder, fuel)?;
+                    }
+                    Some(Continuation::Sigma {
+                        codomain,
+                        spine: sigma_spine,
+                        environment_length,
+                    }) => {
+                        environment.values.truncate(environment_length);
+                        value = builder
+                            .sigma(
+                                value,
+                                Closure {
+                                    term: codomain,
+                                    environment: ConstantEnvironment::from(environment, builder),
+                                },
+                            )
+                            .apply_spine_with_fuel(sigma_spine, builder, fuel)?;
+                    }
+                    Some(Continuation::PairFirst {
+                        second,
+                        spine: pair_spine,
+                        environment_length,
+                    }) => {
+                        environment.values.truncate(environment_length);
+                        continuations.push(Continuation::PairSecond {
+                            first: value,
+                            spine: pair_spine,
+                        });
+                        head = second;
+                        break;
+                    }
+                    Some(Continuation::PairSecond {
+                        first,
+                        spine: pair_spine,
+                    }) => {
+                        value = builder
+                            .pair(first, value)
+                            .apply_spine_with_fuel(pair_spine, builder, fuel)?;
+                    }
+                    Some(Continuation::Suc { spine: suc_spine }) => {
+                        value = builder
+                            .suc(value)
+                            .apply_spine_with_fuel(suc_spine, builder, fuel)?;
+                    }
+                    Some(Continuation::Unfold {
+                        global,
+                        spine: global_spine,
+                    }) => {
+                        value = environment.scope.unfoldings[global.index].get_or_init(|| value);
+                        value = value.apply_spine_with_fuel(global_spine, builder, fuel)?;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// A unit of pending readback work. Quoting uses these on an explicit stack
+/// instead of recursing, so arbitrarily deep values can be read back.
+enum Task<'a> {
+    Quote(ValueRef<'a>, Level),
+    Lambda(Plicity),
+    Application(Plicity),
+    Pair,
+    First,
+    Second,
+    RecordType(&'a [(&'a str, TermRef<'a>)]),
+    Record(&'a [(&'a str, ValueRef<'a>)]),
+    Project(&'a str),
+    Pi(Plicity),
+    Sigma,
+    Suc,
+    NatElim,
+    Case(&'a [Branch<'a>]),
+}
+
+struct Readback<'a, 'b> {
+    tasks: Vec<Task<'a>>,
+    results: Vec<syntax::TermRef<'b>>,
+    builder: &'a Builder,
+    syntax_builder: &'b syntax::Builder,
+}
+
+impl<'a, 'b> Readback<'a, 'b> {
+    fn new(builder: &'a Builder, syntax_builder: &'b syntax::Builder) -> Self {
+        Readback {
+            tasks: Vec::new(),
+            results: Vec::new(),
+            builder,
+            syntax_builder,
+        }
+    }
+
+    fn value(&mut self, value: &Value<'a>, level: Level, fuel: &Fuel) -> Result<(), Interrupted> {
+        match value {
+            Value::Neutral { head, spine } => self.neutral(head, spine, level, fuel)?,
+            Value::Lambda(plicity, closure) => {
+                let variable = self.builder.variable(level);
+                let body = closure.apply_with_fuel(variable, self.builder, fuel)?;
+                self.tasks.push(Task::Lambda(*plicity));
+                self.tasks.push(Task::Quote(body, level + 1));
+            }
+            Value::Pair(first, second) => {
+                self.tasks.push(Task::Pair);
+                self.tasks.push(Task::Quote(second, level));
+                self.tasks.push(Task::Quote(first, level));
+            }
+            Value::Pi(_, domain, codomain) | Value::Sigma(domain, codomain) => {
+                let variable = self.builder.variable(level);
+                let codomain = codomain.apply_with_fuel(variable, self.builder, fuel)?;
+                self.tasks.push(match value {
+                    Value::Pi(plicity, ..) => Task::Pi(*plicity),
+                    _ => Task::Sigma,
+                });
+                self.tasks.push(Task::Quote(codomain, level + 1));
+                self.tasks.push(Task::Quote(domain, level));
+            }
+            Value::RecordType(fields, environment) => {
+                let types = field_types_with_fuel(
+                    fields,
+                    environment,
+                    |position, _| self.builder.variable(level + position),
+                    self.builder,
+                    fuel,
+                )?;
+                self.tasks.push(Task::RecordType(fields));
+                for (position, ty) in types.into_iter().enumerate().rev() {
+                    self.tasks.push(Task::Quote(ty, level + position));
+                }
+            }
+            Value::Record(fields) => {
+                self.tasks.push(Task::Record(fields));
+                for &(_, field) in fields.iter().rev() {
+                    self.tasks.push(Task::Quote(field, level));
                 }
             }
-            Term::Application(function, argument) => {
-                let argument =
-                    environment.local(|environment| argument.evaluate(environment, builder));
-                spine.push_front(argument);
-                function.evaluate_with_spine(spine, environment, builder)
+            Value::Type => self.results.push(self.syntax_builder.universe()),
+            Value::Nat => self.results.push(self.syntax_builder.nat()),
+            Value::Natural(value) => self.results.push(self.syntax_builder.natural(*value)),
+            Value::IntegerType(ty) => self.results.push(self.syntax_builder.integer_type(*ty)),
+            Value::Integer(value) => self.results.push(self.syntax_builder.integer(*value)),
+            Value::Suc(predecessor) => {
+                self.tasks.push(Task::Suc);
+                self.tasks.push(Task::Quote(predecessor, level));
             }
         }
+        Ok(())
+    }
+
+    /// Pushes the quoted head, followed by tasks that eliminate it with each
+    /// frame of the spine in turn. A stuck head is quoted by a task of its
+    /// own, which runs before the eliminations.
+    fn neutral(
+        &mut self,
+        head: &Head<'a>,
+        spine: &ConstantSpine<'a>,
+        level: Level,
+        fuel: &Fuel,
+    ) -> Result<(), Interrupted> {
+        match head {
+            Head::Variable(var_level) => self
+                .results
+                .push(self.syntax_builder.variable(var_level.to_index(level))),
+            Head::Primitive(primitive) => {
+                self.results.push(self.syntax_builder.primitive(*primitive))
+            }
+            Head::Stuck(_) => {}
+            Head::Meta(meta) => self.results.push(self.syntax_builder.meta(*meta)),
+            Head::Hole(hole) => self
+                .results
+                .push(self.syntax_builder.hole(hole.name, hole.scope)),
+            Head::TypeConstructor(type_constructor) => self.results.push(
+                self.syntax_builder
+                    .type_constructor(type_constructor.index, type_constructor.name),
+            ),
+            Head::Constructor(constructor) => self
+                .results
+                .push(self.syntax_builder.constructor(*constructor)),
+        }
+        for frame in spine.frames.iter().rev() {
+            match *frame {
+                Frame::Application(plicity, argument) => {
+                    self.tasks.push(Task::Application(plicity));
+                    self.tasks.push(Task::Quote(argument, level));
+                }
+                Frame::First => self.tasks.push(Task::First),
+                Frame::Second => self.tasks.push(Task::Second),
+                Frame::Project(name) => self.tasks.push(Task::Project(name)),
+                Frame::NatElim { motive, zero, suc } => {
+                    self.tasks.push(Task::NatElim);
+                    self.tasks.push(Task::Quote(suc, level));
+                    self.tasks.push(Task::Quote(zero, level));
+                    self.tasks.push(Task::Quote(motive, level));
+                }
+                Frame::Case {
+                    branches,
+                    environment,
+                } => {
+                    self.tasks.push(Task::Case(branches));
+                    for branch in branches.iter().rev() {
+                        let body =
+                            branch_body_with_fuel(branch, environment, level, self.builder, fuel)?;
+                        self.tasks.push(Task::Quote(body, level + branch.arity));
+                    }
+                }
+            }
+        }
+        if let Head::Stuck(value) = head {
+            self.tasks.push(Task::Quote(value, level));
+        }
+        Ok(())
+    }
+
+    /// Runs the pending tasks, each of which takes a step of `fuel`.
+    fn run(mut self, fuel: &Fuel) -> Result<syntax::TermRef<'b>, Interrupted> {
+        while let Some(task) = self.tasks.pop() {
+            fuel.consume()?;
+            match task {
+                Task::Quote(value, level) => self.value(value, level, fuel)?,
+                Task::Lambda(plicity) => {
+                    let body = self.results.pop().unwrap();
+                    self.results
+                        .push(self.syntax_builder.lambda_with(plicity, None, body));
+                }
+                Task::Application(plicity) => {
+                    let argument = self.results.pop().unwrap();
+                    let function = self.results.pop().unwrap();
+                    self.results.push(
+                        self.syntax_builder
+                            .application_with(plicity, function, argument),
+                    );
+                }
+                Task::Pair => {
+                    let second = self.results.pop().unwrap();
+                    let first = self.results.pop().unwrap();
+                    self.results.push(self.syntax_builder.pair(first, second));
+                }
+                Task::First => {
+                    let pair = self.results.pop().unwrap();
+                    self.results.push(self.syntax_builder.fst(pair));
+                }
+                Task::Second => {
+                    let pair = self.results.pop().unwrap();
+                    self.results.push(self.syntax_builder.snd(pair));
+                }
+                Task::RecordType(fields) => {
+                    let types = self.results.split_off(self.results.len() - fields.len());
+                    let fields: Vec<_> = (fields.iter().zip(types))
+                        .map(|(&(name, _), ty)| (name, ty))
+                        .collect();
+                    self.results.push(self.syntax_builder.record_type(&fields));
+                }
+                Task::Record(fields) => {
+                    let values = self.results.split_off(self.results.len() - fields.len());
+                    let fields: Vec<_> = (fields.iter().zip(values))
+                        .map(|(&(name, _), value)| (name, value))
+                        .collect();
+                    self.results.push(self.syntax_builder.record(&fields));
+                }
+                Task::Project(name) => {
+                    let record = self.results.pop().unwrap();
+                    self.results.push(self.syntax_builder.project(record, name));
+                }
+                Task::Pi(plicity) => {
+                    let codomain = self.results.pop().unwrap();
+                    let domain = self.results.pop().unwrap();
+                    self.results
+                        .push(self.syntax_builder.pi_with(plicity, domain, codomain));
+                }
+                Task::Sigma => {
+                    let codomain = self.results.pop().unwrap();
+                    let domain = self.results.pop().unwrap();
+                    self.results
+                        .push(self.syntax_builder.sigma(domain, codomain));
+                }
+                Task::Suc => {
+                    let predecessor = self.results.pop().unwrap();
+                    self.results.push(self.syntax_builder.suc(predecessor));
+                }
+                Task::NatElim => {
+                    let suc = self.results.pop().unwrap();
+                    let zero = self.results.pop().unwrap();
+                    let motive = self.results.pop().unwrap();
+                    let scrutinee = self.results.pop().unwrap();
+                    self.results
+                        .push(self.syntax_builder.nat_elim(motive, zero, suc, scrutinee));
+                }
+                Task::Case(branches) => {
+                    let bodies = self.results.split_off(self.results.len() - branches.len());
+                    let scrutinee = self.results.pop().unwrap();
+                    let branches: Vec<_> = (branches.iter().zip(bodies))
+                        .map(|(branch, body)| {
+                            self.syntax_builder
+                                .branch(branch.constructor, branch.arity, body)
+                        })
+                        .collect();
+                    self.results
+                        .push(self.syntax_builder.case(scrutinee, &branches));
+                }
+            }
+        }
+        let result = self.results.pop().unwrap();
+        assert!(self.results.is_empty());
+        Ok(result)
     }
 }
 
+/// The body of a branch of a stuck case analysis, with the fields bound to
+/// fresh variables from `level` on.
+fn branch_body<'a>(
+    branch: &Branch<'a>,
+    environment: &ConstantEnvironment<'a>,
+    level: Level,
+    builder: &'a Builder,
+) -> ValueRef<'a> {
+    unmetered(|fuel| branch_body_with_fuel(branch, environment, level, builder, fuel))
+}
+
+fn branch_body_with_fuel<'a>(
+    branch: &Branch<'a>,
+    environment: &ConstantEnvironment<'a>,
+    level: Level,
+    builder: &'a Builder,
+    fuel: &Fuel,
+) -> Result<ValueRef<'a>, Interrupted> {
+    let mut environment = Environment::from(environment);
+    for field in 0..branch.arity {
+        environment.extend(builder.variable(level + field));
+    }
+    branch
+        .body
+        .evaluate_with_fuel(&mut environment, builder, fuel)
+}
+
 impl<'a> Value<'a> {
-    pub fn quote(
+    pub fn quote<'b>(
         &self,
         level: Level,
         builder: &'a Builder,
-        syntax_builder: &'a syntax::Builder,
-    ) -> syntax::TermRef<'a> {
-        match self {
-            Value::Neutral { head, spine } => head.quote(spine, level, builder, syntax_builder),
-            Value::Lambda(closure) => syntax_builder.lambda({
-                let mut environment = Environment::from(&closure.environment);
-                environment.extend(builder.variable(level));
-                closure.term.evaluate(&mut environment, builder).quote(
-                    level + 1,
-                    builder,
-                    syntax_builder,
-                )
-            }),
-        }
+        syntax_builder: &'b syntax::Builder,
+    ) -> syntax::TermRef<'b> {
+        unmetered(|fuel| self.quote_with_fuel(level, builder, syntax_builder, fuel))
+    }
+
+    /// [`Value::quote`], which is [`Interrupted`] once the steps it takes,
+    /// evaluating under binders and reading back, run past `fuel` or `fuel` is
+    /// cancelled.
+    pub fn quote_with_fuel<'b>(
+        &self,
+        level: Level,
+        builder: &'a Builder,
+        syntax_builder: &'b syntax::Builder,
+        fuel: &Fuel,
+    ) -> Result<syntax::TermRef<'b>, Interrupted> {
+        let mut readback = Readback::new(builder, syntax_builder);
+        readback.value(self, level, fuel)?;
+        readback.run(fuel)
     }
 }
 
-impl Head {
-    pub fn quote<'a>(
+impl<'a> Head<'a> {
+    pub fn quote<'b>(
         &self,
         spine: &ConstantSpine<'a>,
         level: Level,
         builder: &'a Builder,
-        syntax_builder: &'a syntax::Builder,
-    ) -> syntax::TermRef<'a> {
-        let mut result = match self {
-            Head::Variable(var_level) => syntax_builder.variable(var_level.to_index(level)),
-        };
-        for arg in spine.iter() {
-            result = syntax_builder.application(result, arg.quote(level, builder, syntax_builder));
+        syntax_builder: &'b syntax::Builder,
+    ) -> syntax::TermRef<'b> {
+        let mut readback = Readback::new(builder, syntax_builder);
+        unmetered(|fuel| {
+            readback.neutral(self, spine, level, fuel)?;
+            readback.run(fuel)
+        })
+    }
+}
+
+impl<'a> Value<'a> {
+    /// Whether two values at `level` are equal up to beta and eta.
+    ///
+    /// Neutrals are compared head first and frame by frame, and lambdas
+    /// are only opened when the other side has been reached too, so the
+    /// comparison stops at the first mismatch without building normal forms.
+    pub fn conv(
+        level: Level,
+        value1: ValueRef<'a>,
+        value2: ValueRef<'a>,
+        builder: &'a Builder,
+    ) -> bool {
+        let mut pairs = vec![(level, value1, value2)];
+        while let Some((level, value1, value2)) = pairs.pop() {
+            match (value1, value2) {
+                (
+                    Value::Neutral {
+                        head: head1,
+                        spine: spine1,
+                    },
+                    Value::Neutral {
+                        head: head2,
+                        spine: spine2,
+                    },
+                ) => {
+                    match (head1, head2) {
+                        (Head::Stuck(value1), Head::Stuck(value2)) => {
+                            pairs.push((level, value1, value2))
+                        }
+                        _ if head1 != head2 => return false,
+                        _ => {}
+                    }
+                    if spine1.frames.len() != spine2.frames.len() {
+                        return false;
+                    }
+                    for frames in spine1
+                        .frames
+                        .iter()
+                        .copied()
+                        .zip(spine2.frames.iter().copied())
+                        .rev()
+                    {
+                        match frames {
+                            (
+                                Frame::Application(_, argument1),
+                                Frame::Application(_, argument2),
+                            ) => pairs.push((level, argument1, argument2)),
+                            (Frame::First, Frame::First) | (Frame::Second, Frame::Second) => {}
+                            (Frame::Project(name1), Frame::Project(name2)) if name1 == name2 => {}
+                            (
+                                Frame::NatElim {
+                                    motive: motive1,
+                                    zero: zero1,
+                                    suc: suc1,
+                                },
+                                Frame::NatElim {
+                                    motive: motive2,
+                                    zero: zero2,
+                                    suc: suc2,
+                                },
+                            ) => pairs.extend([
+                                (level, suc1, suc2),
+                                (level, zero1, zero2),
+                                (level, motive1, motive2),
+                            ]),
+                            (
+                                Frame::Case {
+                                    branches: branches1,
+                                    environment: environment1,
+                                },
+                                Frame::Case {
+                                    branches: branches2,
+                                    environment: environment2,
+                                },
+                            ) => {
+                                if branches1.len() != branches2.len() {
+                                    return false;
+                                }
+                                for (branch1, branch2) in branches1.iter().zip(branches2).rev() {
+                                    if !branch1.constructor.same(&branch2.constructor)
+                                        || branch1.arity != branch2.arity
+                                    {
+                                        return false;
+                                    }
+                                    pairs.push((
+                                        level + branch1.arity,
+                                        branch_body(branch1, environment1, level, builder),
+                                        branch_body(branch2, environment2, level, builder),
+                                    ));
+                                }
+                            }
+                            _ => return false,
+                        }
+                    }
+                }
+                // Eta: a lambda is compared with a lambda or a neutral by
+                // applying both sides to a fresh variable.
+                (Value::Lambda(plicity, _), Value::Lambda(..) | Value::Neutral { .. })
+                | (Value::Neutral { .. }, Value::Lambda(plicity, _)) => {
+                    let variable = builder.variable(level);
+                    pairs.push((
+                        level + 1,
+                        value1.apply_with(*plicity, variable, builder),
+                        value2.apply_with(*plicity, variable, builder),
+                    ));
+                }
+                (Value::Pair(first1, second1), Value::Pair(first2, second2)) => {
+                    pairs.push((level, second1, second2));
+                    pairs.push((level, first1, first2));
+                }
+                // Eta: a pair is compared with a neutral by projecting both
+                // sides.
+                (Value::Pair(..), Value::Neutral { .. })
+                | (Value::Neutral { .. }, Value::Pair(..)) => {
+                    pairs.push((
+                        level,
+                        value1.project(Frame::Second, builder),
+                        value2.project(Frame::Second, builder),
+                    ));
+                    pairs.push((
+                        level,
+                        value1.project(Frame::First, builder),
+                        value2.project(Frame::First, builder),
+                    ));
+                }
+                (Value::Record(fields1), Value::Record(fields2)) => {
+                    if !same_names(fields1, fields2) {
+                        return false;
+                    }
+                    for (&(_, field1), &(_, field2)) in fields1.iter().zip(*fields2).rev() {
+                        pairs.push((level, field1, field2));
+                    }
+                }
+                // Eta: a record is compared with a neutral by projecting each
+                // field out of both sides.
+                (Value::Record(fields), Value::Neutral { .. })
+                | (Value::Neutral { .. }, Value::Record(fields)) => {
+                    for &(name, _) in fields.iter().rev() {
+                        pairs.push((
+                            level,
+                            value1.project_field(name, builder),
+                            value2.project_field(name, builder),
+                        ));
+                    }
+                }
+                (
+                    Value::RecordType(fields1, environment1),
+                    Value::RecordType(fields2, environment2),
+                ) => {
+                    if !same_names(fields1, fields2) {
+                        return false;
+                    }
+                    let variable = |position, _| builder.variable(level + position);
+                    let types1 = field_types(fields1, environment1, variable, builder);
+                    let types2 = field_types(fields2, environment2, variable, builder);
+                    for (position, (type1, type2)) in
+                        types1.into_iter().zip(types2).enumerate().rev()
+                    {
+                        pairs.push((level + position, type1, type2));
+                    }
+                }
+                (Value::Pi(plicity1, ..), Value::Pi(plicity2, ..)) if plicity1 != plicity2 => {
+                    return false
+                }
+                (Value::Pi(_, domain1, codomain1), Value::Pi(_, domain2, codomain2))
+                | (Value::Sigma(domain1, codomain1), Value::Sigma(domain2, codomain2)) => {
+                    let variable = builder.variable(level);
+                    pairs.push((
+                        level + 1,
+                        codomain1.apply(variable, builder),
+                        codomain2.apply(variable, builder),
+                    ));
+                    pairs.push((level, domain1, domain2));
+                }
+                (Value::Type, Value::Type) | (Value::Nat, Value::Nat) => {}
+                (Value::Natural(value1), Value::Natural(value2)) if value1 == value2 => {}
+                (Value::IntegerType(ty1), Value::IntegerType(ty2)) if ty1 == ty2 => {}
+                (Value::Integer(value1), Value::Integer(value2)) if value1 == value2 => {}
+                (Value::Suc(predecessor1), Value::Suc(predecessor2)) => {
+                    pairs.push((level, predecessor1, predecessor2))
+                }
+                _ => return false,
+            }
         }
-        result
+        true
+    }
+}
+
+/// The arena-allocated backend. Values live in a [`Builder`] that is dropped
+/// as a whole once the normal form has been read back.
+pub struct Backend;
+
+impl Normalizer for Backend {
+    fn normalize<'out>(
+        &self,
+        term: TermRef<'_>,
+        syntax_builder: &'out syntax::Builder,
+    ) -> TermRef<'out> {
+        Builder::new().normalize(term, Level(0), syntax_builder)
+    }
+}
+
+/// The arena-allocated backend with the definitions of a signature in scope.
+/// Globals are unfolded as they are evaluated, each definition at most once.
+pub struct SignatureBackend<'s> {
+    pub signature: &'s Signature<'s>,
+}
+
+impl Normalizer for SignatureBackend<'_> {
+    fn normalize<'out>(
+        &self,
+        term: TermRef<'_>,
+        syntax_builder: &'out syntax::Builder,
+    ) -> TermRef<'out> {
+        let builder = Builder::new();
+        let mut environment = Environment::new().with_signature(self.signature, &builder);
+        term.evaluate(&mut environment, &builder)
+            .quote(Level(0), &builder, syntax_builder)
     }
 }
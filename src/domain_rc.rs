@@ -1,57 +1,460 @@
 use crate::{
+    fuel::{unmetered, Fuel, Interrupted},
     index::{Index, Level},
-    syntax::{self, Term, TermRef},
+    meta::MetaContext,
+    normalizer::Normalizer,
+    primitive::{Integer, IntegerType, Output, Primitive},
+    signature::{Definition, Signature},
+    syntax::{
+        self, Branch, Constructor, Global, Hole, Meta, Plicity, Term, TermRef, TypeConstructor,
+    },
 };
 use rclite::Rc;
+use std::cell::{OnceCell, RefCell};
 
 #[derive(Clone)]
-pub enum Head {
+pub enum Head<'a> {
     Variable(Level),
+    /// A primitive operation, stuck until it is applied to as many literals
+    /// as it takes.
+    Primitive(Primitive),
+    /// A value that the first argument of the spine cannot eliminate, such as
+    /// a type applied to an argument. Only ill-typed terms get stuck like
+    /// this; they are kept as they are written so that any untyped term can
+    /// be normalized.
+    Stuck(ValueRef<'a>),
+    /// A top-level definition, glued to its unfolding: the value of its body,
+    /// which is only computed if the definition has to be unfolded. The
+    /// neutral stands for the unfolding applied to its spine.
+    Global {
+        global: Global<'a>,
+        unfolding: ValueRef<'a>,
+    },
+    /// A metavariable of `metas`. Once it is solved, the neutral stands for
+    /// the solution applied to its spine, and is replaced by that whenever
+    /// it is eliminated, compared or read back.
+    Meta {
+        meta: Meta,
+        metas: MetaContext<'a>,
+    },
+    /// A hole that was not elaborated to a meta, as when normalizing a term
+    /// without checking it. It blocks like a variable.
+    Hole(Hole<'a>),
+    /// The type constructor of a datatype, applied to its parameters by the
+    /// spine.
+    TypeConstructor(TypeConstructor<'a>),
+    /// A constructor, applied to the parameters of its datatype and its
+    /// fields by the spine. Case analysis on it reduces rather than getting
+    /// stuck.
+    Constructor(Constructor<'a>),
+}
+
+/// Heads are equal when they are the same variable, primitive, global, meta,
+/// hole, type constructor or constructor; the unfoldings of globals are not
+/// compared. Stuck heads are only equal to themselves here; conversion
+/// compares the values in them.
+impl PartialEq for Head<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Head::Variable(level1), Head::Variable(level2)) => level1 == level2,
+            (Head::Primitive(primitive1), Head::Primitive(primitive2)) => primitive1 == primitive2,
+            (Head::Stuck(value1), Head::Stuck(value2)) => Rc::ptr_eq(value1, value2),
+            (
+                Head::Global {
+                    global: global1, ..
+                },
+                Head::Global {
+                    global: global2, ..
+                },
+            ) => global1.index == global2.index,
+            (Head::Meta { meta: meta1, .. }, Head::Meta { meta: meta2, .. }) => meta1 == meta2,
+            (Head::Hole(hole1), Head::Hole(hole2)) => hole1 == hole2,
+            (
+                Head::TypeConstructor(type_constructor1),
+                Head::TypeConstructor(type_constructor2),
+            ) => type_constructor1.index == type_constructor2.index,
+            (Head::Constructor(constructor1), Head::Constructor(constructor2)) => {
+                constructor1.same(constructor2)
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Head<'_> {}
+
+impl<'a> Head<'a> {
+    pub fn variable(&self) -> Option<Level> {
+        match self {
+            Head::Variable(level) => Some(*level),
+            _ => None,
+        }
+    }
+
+    pub fn primitive(&self) -> Option<Primitive> {
+        match self {
+            Head::Primitive(primitive) => Some(*primitive),
+            _ => None,
+        }
+    }
+
+    pub fn global(&self) -> Option<Global<'a>> {
+        match self {
+            Head::Global { global, .. } => Some(*global),
+            _ => None,
+        }
+    }
+
+    pub fn meta(&self) -> Option<Meta> {
+        match self {
+            Head::Meta { meta, .. } => Some(*meta),
+            _ => None,
+        }
+    }
+
+    pub fn hole(&self) -> Option<Hole<'a>> {
+        match self {
+            Head::Hole(hole) => Some(*hole),
+            _ => None,
+        }
+    }
+
+    pub fn type_constructor(&self) -> Option<TypeConstructor<'a>> {
+        match self {
+            Head::TypeConstructor(type_constructor) => Some(*type_constructor),
+            _ => None,
+        }
+    }
+
+    pub fn constructor(&self) -> Option<Constructor<'a>> {
+        match self {
+            Head::Constructor(constructor) => Some(*constructor),
+            _ => None,
+        }
+    }
+}
+
+/// An elimination waiting for a value: an argument to apply it to, a
+/// projection out of a pair or a record, recursion on a natural number, or
+/// case analysis on a value of a datatype.
+#[derive(Clone)]
+pub enum Frame<'a> {
+    Application(Plicity, ValueRef<'a>),
+    First,
+    Second,
+    /// The field of a record with the given name.
+    Project(&'a str),
+    NatElim {
+        motive: ValueRef<'a>,
+        zero: ValueRef<'a>,
+        suc: ValueRef<'a>,
+    },
+    /// The branches of a case analysis, whose bodies are evaluated in
+    /// `environment` extended with the fields of the scrutinee. The
+    /// environment is shared, which keeps frames small.
+    Case {
+        branches: &'a [Branch<'a>],
+        environment: Rc<Environment<'a>>,
+    },
 }
 
 pub struct Spine<'a> {
-    reversed_values: Vec<ValueRef<'a>>,
+    reversed_frames: Vec<Frame<'a>>,
+}
+
+impl<'a> Default for Spine<'a> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl<'a> Spine<'a> {
     pub fn new() -> Self {
         Spine {
-            reversed_values: Vec::new(),
+            reversed_frames: Vec::new(),
         }
     }
 
-    pub fn push_front(&mut self, value: ValueRef<'a>) {
-        self.reversed_values.push(value)
+    pub fn push_front(&mut self, frame: Frame<'a>) {
+        self.reversed_frames.push(frame)
     }
 
-    pub fn pop_front(&mut self) -> Option<ValueRef<'a>> {
-        self.reversed_values.pop()
+    pub fn pop_front(&mut self) -> Option<Frame<'a>> {
+        self.reversed_frames.pop()
     }
 
-    pub fn iter<'s>(&'s self) -> impl Iterator<Item = &'s ValueRef<'a>> {
-        self.reversed_values.iter().rev()
+    /// Pops the first frame if it is an application.
+    pub fn pop_argument(&mut self) -> Option<ValueRef<'a>> {
+        match self.reversed_frames.last() {
+            Some(Frame::Application(..)) => match self.reversed_frames.pop() {
+                Some(Frame::Application(_, argument)) => Some(argument),
+                _ => unreachable!(),
+            },
+            _ => None,
+        }
     }
 
-    pub fn into_iter<'s>(self) -> impl Iterator<Item = ValueRef<'a>> {
-        self.reversed_values.into_iter().rev()
+    pub fn iter<'s>(&'s self) -> impl Iterator<Item = &'s Frame<'a>> {
+        self.reversed_frames.iter().rev()
+    }
+
+    pub fn into_frames(self) -> impl Iterator<Item = Frame<'a>> {
+        self.reversed_frames.into_iter().rev()
     }
 
     pub fn is_empty(&self) -> bool {
-        self.reversed_values.is_empty()
+        self.reversed_frames.is_empty()
+    }
+
+    /// A spine of `frames`, the first of which is eliminated first.
+    pub fn from_frames(frames: impl IntoIterator<Item = Frame<'a>>) -> Self {
+        let mut reversed_frames = Vec::from_iter(frames);
+        reversed_frames.reverse();
+        Spine { reversed_frames }
     }
 }
 
+#[derive(Clone)]
 pub struct Closure<'a> {
     term: TermRef<'a>,
     environment: Environment<'a>,
 }
 
+impl<'a> Closure<'a> {
+    pub fn new(term: TermRef<'a>, environment: Environment<'a>) -> Self {
+        Closure { term, environment }
+    }
+
+    /// Evaluates the body with its bound variable instantiated to `argument`.
+    /// Instantiating it with [`Value::variable`] at the level of the binder
+    /// opens the body for readback.
+    pub fn apply(&self, argument: ValueRef<'a>) -> ValueRef<'a> {
+        unmetered(|fuel| self.apply_with_fuel(argument, fuel))
+    }
+
+    /// [`Closure::apply`], which takes a step of `fuel` and then whatever
+    /// evaluating the body takes.
+    pub fn apply_with_fuel(
+        &self,
+        argument: ValueRef<'a>,
+        fuel: &Fuel,
+    ) -> Result<ValueRef<'a>, Interrupted> {
+        fuel.consume()?;
+        let mut environment = self.environment.clone();
+        environment.extend(argument);
+        self.term.evaluate_rc_with_fuel(&mut environment, fuel)
+    }
+}
+
 pub enum Value<'a> {
     Neutral {
-        head: Head,
-        spine: Vec<ValueRef<'a>>,
+        head: Head<'a>,
+        spine: Vec<Frame<'a>>,
     },
-    Lambda(Closure<'a>),
+    Lambda(Plicity, Closure<'a>),
+    Pair(ValueRef<'a>, ValueRef<'a>),
+    Pi(Plicity, TypeRef<'a>, Closure<'a>),
+    Sigma(TypeRef<'a>, Closure<'a>),
+    /// A record type, whose field types are evaluated in the environment
+    /// extended with the fields before them when they are needed.
+    RecordType(&'a [(&'a str, TermRef<'a>)], Environment<'a>),
+    Record(Vec<(&'a str, ValueRef<'a>)>),
+    Type,
+    Nat,
+    /// A natural number literal.
+    Natural(u64),
+    /// The successor of a natural number that is not a literal.
+    Suc(ValueRef<'a>),
+    IntegerType(IntegerType),
+    Integer(Integer),
+    /// A suspended computation, only created under [`Strategy::CallByNeed`].
+    /// Thunks appear in environments and spines, never as the result of
+    /// evaluation; use [`force`] to get at the value behind one.
+    Thunk(Thunk<'a>),
+}
+
+/// How arguments and `let`-bound terms are evaluated. The strategy is a
+/// property of the [`Environment`], so closures and thunks keep the strategy
+/// they were created with.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Strategy {
+    /// Evaluate them before they are bound.
+    #[default]
+    CallByValue,
+    /// Bind them as thunks that are evaluated the first time they are needed,
+    /// at most once.
+    CallByNeed,
+}
+
+pub struct Thunk<'a> {
+    state: RefCell<ThunkState<'a>>,
+}
+
+enum ThunkState<'a> {
+    Suspended(TermRef<'a>, Environment<'a>),
+    /// Being forced. If the run forcing it can be interrupted, a copy of the
+    /// suspended computation is kept to be put back.
+    Forcing(Option<(TermRef<'a>, Environment<'a>)>),
+    Forced(ValueRef<'a>),
+}
+
+impl<'a> Thunk<'a> {
+    /// Returns the memoised value, or marks the thunk as being forced with
+    /// `fuel` and returns the suspended computation.
+    fn start_forcing(&self, fuel: &Fuel) -> Result<ValueRef<'a>, (TermRef<'a>, Environment<'a>)> {
+        let mut state = self.state.borrow_mut();
+        match std::mem::replace(&mut *state, ThunkState::Forcing(None)) {
+            ThunkState::Forced(value) => {
+                *state = ThunkState::Forced(value.clone());
+                Ok(value)
+            }
+            ThunkState::Suspended(term, environment) => {
+                if fuel.is_interruptible() {
+                    *state = ThunkState::Forcing(Some((term, environment.clone())));
+                }
+                Err((term, environment))
+            }
+            ThunkState::Forcing(_) => panic!("thunk forced while it was being forced"),
+        }
+    }
+
+    fn finish_forcing(&self, value: ValueRef<'a>) {
+        *self.state.borrow_mut() = ThunkState::Forced(value);
+    }
+
+    /// Suspends the thunk again after the run forcing it was interrupted, so
+    /// that another run can force it.
+    fn abandon_forcing(&self) {
+        let mut state = self.state.borrow_mut();
+        if let ThunkState::Forcing(suspended) = &mut *state {
+            if let Some((term, environment)) = suspended.take() {
+                *state = ThunkState::Suspended(term, environment);
+            }
+        }
+    }
+
+    pub(crate) fn force(&self) -> ValueRef<'a> {
+        unmetered(|fuel| self.force_with_fuel(fuel))
+    }
+
+    fn force_with_fuel(&self, fuel: &Fuel) -> Result<ValueRef<'a>, Interrupted> {
+        match self.start_forcing(fuel) {
+            Ok(value) => Ok(value),
+            Err((term, mut environment)) => {
+                match term.evaluate_rc_with_fuel(&mut environment, fuel) {
+                    Ok(value) => {
+                        self.finish_forcing(value.clone());
+                        Ok(value)
+                    }
+                    Err(error) => {
+                        self.abandon_forcing();
+                        Err(error)
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The value behind a thunk, evaluating it if this is the first time it is
+/// needed. Other values are returned as they are.
+pub fn force<'a>(value: &ValueRef<'a>) -> ValueRef<'a> {
+    unmetered(|fuel| force_with_fuel(value, fuel))
+}
+
+fn force_with_fuel<'a>(value: &ValueRef<'a>, fuel: &Fuel) -> Result<ValueRef<'a>, Interrupted> {
+    match &**value {
+        Value::Thunk(thunk) => thunk.force_with_fuel(fuel),
+        _ => Ok(value.clone()),
+    }
+}
+
+/// Values can be nested far deeper than the native stack allows, so they are
+/// dropped with an explicit stack: the children of a value that is about to
+/// be freed are moved out before it is. A child held in a field of its own,
+/// rather than in a collection that can be drained, is swapped for a
+/// placeholder that the whole drop shares.
+impl<'a> Drop for Value<'a> {
+    fn drop(&mut self) {
+        let mut values = Vec::new();
+        let mut placeholder = None;
+        self.take_children(&mut values, &mut placeholder);
+        while let Some(mut value) = values.pop() {
+            if let Some(value) = Rc::get_mut(&mut value) {
+                value.take_children(&mut values, &mut placeholder);
+            }
+        }
+    }
+}
+
+/// Moves `child` out of a value that is being dropped, leaving `placeholder`
+/// in its place.
+fn take_child<'a>(
+    child: &mut ValueRef<'a>,
+    placeholder: &mut Option<ValueRef<'a>>,
+) -> ValueRef<'a> {
+    let placeholder = placeholder.get_or_insert_with(|| Rc::new(Value::Type));
+    std::mem::replace(child, placeholder.clone())
+}
+
+impl<'a> Value<'a> {
+    fn take_children(
+        &mut self,
+        values: &mut Vec<ValueRef<'a>>,
+        placeholder: &mut Option<ValueRef<'a>>,
+    ) {
+        match self {
+            Value::Neutral { head, spine } => {
+                if let Head::Stuck(value)
+                | Head::Global {
+                    unfolding: value, ..
+                } = head
+                {
+                    values.push(take_child(value, placeholder));
+                }
+                for frame in spine.drain(..) {
+                    match frame {
+                        Frame::Application(_, argument) => values.push(argument),
+                        Frame::First | Frame::Second | Frame::Project(_) => {}
+                        Frame::NatElim { motive, zero, suc } => values.extend([motive, zero, suc]),
+                        Frame::Case { environment, .. } => {
+                            if let Ok(mut environment) = Rc::try_unwrap(environment) {
+                                values.append(&mut environment.values)
+                            }
+                        }
+                    }
+                }
+            }
+            Value::Lambda(_, Closure { environment, .. }) => values.append(&mut environment.values),
+            Value::Pair(first, second) => {
+                values.push(take_child(first, placeholder));
+                values.push(take_child(second, placeholder));
+            }
+            Value::Pi(_, domain, Closure { environment, .. })
+            | Value::Sigma(domain, Closure { environment, .. }) => {
+                values.push(take_child(domain, placeholder));
+                values.append(&mut environment.values);
+            }
+            Value::RecordType(_, environment) => values.append(&mut environment.values),
+            Value::Record(fields) => values.extend(fields.drain(..).map(|(_, field)| field)),
+            Value::Type
+            | Value::Nat
+            | Value::Natural(_)
+            | Value::IntegerType(_)
+            | Value::Integer(_) => {}
+            Value::Suc(predecessor) => values.push(take_child(predecessor, placeholder)),
+            Value::Thunk(thunk) => {
+                match std::mem::replace(thunk.state.get_mut(), ThunkState::Forcing(None)) {
+                    ThunkState::Suspended(_, mut environment)
+                    | ThunkState::Forcing(Some((_, mut environment))) => {
+                        values.append(&mut environment.values)
+                    }
+                    ThunkState::Forced(value) => values.push(value),
+                    ThunkState::Forcing(None) => {}
+                }
+            }
+        }
+    }
 }
 
 pub type Type<'a> = Value<'a>;
@@ -61,6 +464,53 @@ pub type TypeRef<'a> = Rc<Type<'a>>;
 #[derive(Clone)]
 pub struct Environment<'a> {
     values: Vec<ValueRef<'a>>,
+    strategy: Strategy,
+    /// The definitions that globals refer to.
+    definitions: &'a [Definition<'a>],
+    unfoldings: Unfoldings<'a>,
+    /// The metacontext that metas refer to, if there is one.
+    metas: Option<MetaContext<'a>>,
+}
+
+/// The unfoldings of the definitions of an [`Environment`], each built the
+/// first time one of its globals is evaluated and then shared by all of
+/// them, so that a definition is evaluated at most once.
+///
+/// The environments that unfoldings are evaluated in only hold weak
+/// references to them: the unfoldings would otherwise keep themselves alive.
+/// Once the environments that own them are gone, globals are unfolded
+/// without sharing.
+#[derive(Clone)]
+enum Unfoldings<'a> {
+    None,
+    Owned(std::rc::Rc<[OnceCell<ValueRef<'a>>]>),
+    Unfolding(std::rc::Weak<[OnceCell<ValueRef<'a>>]>),
+}
+
+impl<'a> Unfoldings<'a> {
+    fn new(size: usize) -> Self {
+        if size == 0 {
+            return Unfoldings::None;
+        }
+        Unfoldings::Owned((0..size).map(|_| OnceCell::new()).collect())
+    }
+
+    fn upgrade(&self) -> Option<std::rc::Rc<[OnceCell<ValueRef<'a>>]>> {
+        match self {
+            Unfoldings::None => None,
+            Unfoldings::Owned(unfoldings) => Some(unfoldings.clone()),
+            Unfoldings::Unfolding(unfoldings) => unfoldings.upgrade(),
+        }
+    }
+
+    fn downgrade(&self) -> Self {
+        match self {
+            Unfoldings::Owned(unfoldings) => {
+                Unfoldings::Unfolding(std::rc::Rc::downgrade(unfoldings))
+            }
+            unfoldings => unfoldings.clone(),
+        }
+    }
 }
 
 impl<'a> std::ops::Index<Index> for Environment<'a> {
@@ -71,15 +521,143 @@ impl<'a> std::ops::Index<Index> for Environment<'a> {
     }
 }
 
+impl<'a> Default for Environment<'a> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<'a> Environment<'a> {
     pub fn new() -> Self {
-        Environment { values: Vec::new() }
+        Environment {
+            values: Vec::new(),
+            strategy: Strategy::default(),
+            definitions: &[],
+            unfoldings: Unfoldings::None,
+            metas: None,
+        }
+    }
+
+    /// An environment for an open term with `size` free variables, each bound
+    /// to itself. Values evaluated in it should be quoted at `Level(size)`.
+    pub fn free_variables(size: usize) -> Self {
+        Environment {
+            values: (0..size)
+                .map(|level| Value::variable(Level(level)))
+                .collect(),
+            strategy: Strategy::default(),
+            definitions: &[],
+            unfoldings: Unfoldings::None,
+            metas: None,
+        }
+    }
+
+    pub fn with_strategy(self, strategy: Strategy) -> Self {
+        Environment {
+            strategy,
+            unfoldings: Unfoldings::new(self.definitions.len()),
+            ..self
+        }
+    }
+
+    /// Brings the definitions of `signature` into scope for the globals of
+    /// the terms evaluated in this environment.
+    pub fn with_signature(self, signature: &'a Signature<'a>) -> Self {
+        self.with_definitions(signature.definitions())
+    }
+
+    pub(crate) fn with_definitions(self, definitions: &'a [Definition<'a>]) -> Self {
+        Environment {
+            definitions,
+            unfoldings: Unfoldings::new(definitions.len()),
+            ..self
+        }
+    }
+
+    /// Makes the metas of the terms evaluated in this environment refer to
+    /// `metas`.
+    pub fn with_metas(self, metas: &MetaContext<'a>) -> Self {
+        Environment {
+            metas: Some(metas.clone()),
+            unfoldings: Unfoldings::new(self.definitions.len()),
+            ..self
+        }
+    }
+
+    /// The global `global`, glued to the unfolding of its definition, which
+    /// every occurrence of the global evaluated in this environment shares.
+    fn global(&self, global: Global<'a>) -> ValueRef<'a> {
+        let definition = self
+            .definitions
+            .get(global.index)
+            .unwrap_or_else(|| panic!("global {} is not in the signature", global.name));
+        let unfold = || {
+            let environment = Environment {
+                values: Vec::new(),
+                strategy: self.strategy,
+                definitions: self.definitions,
+                unfoldings: self.unfoldings.downgrade(),
+                metas: self.metas.clone(),
+            };
+            environment.delay(definition.term)
+        };
+        let unfolding = match self.unfoldings.upgrade() {
+            Some(unfoldings) => unfoldings[global.index].get_or_init(unfold).clone(),
+            None => unfold(),
+        };
+        Rc::new(Value::Neutral {
+            head: Head::Global { global, unfolding },
+            spine: Vec::new(),
+        })
+    }
+
+    /// The value of the meta `meta`.
+    fn meta(&self, meta: Meta) -> ValueRef<'a> {
+        self.metas
+            .as_ref()
+            .unwrap_or_else(|| panic!("meta {meta} is not in the metacontext"))
+            .value(meta)
+    }
+
+    pub fn strategy(&self) -> Strategy {
+        self.strategy
+    }
+
+    /// Suspends `term` in this environment, or evaluates it right away if
+    /// that is as cheap as suspending it.
+    fn delay(&self, term: TermRef<'a>) -> ValueRef<'a> {
+        match term {
+            Term::Variable(index) => self[*index].clone(),
+            Term::Lambda(plicity, _, body) => Rc::new(Value::Lambda(
+                *plicity,
+                Closure {
+                    term: body,
+                    environment: self.clone(),
+                },
+            )),
+            _ => Rc::new(Value::Thunk(Thunk {
+                state: RefCell::new(ThunkState::Suspended(term, self.clone())),
+            })),
+        }
+    }
+
+    /// Evaluates `term` right away, or suspends it under
+    /// [`Strategy::CallByNeed`].
+    fn argument(&self, term: TermRef<'a>, fuel: &Fuel) -> Result<ValueRef<'a>, Interrupted> {
+        match self.strategy {
+            Strategy::CallByValue => term.evaluate_rc_with_fuel(&mut self.clone(), fuel),
+            Strategy::CallByNeed => Ok(self.delay(term)),
+        }
     }
 
     pub fn extend(&mut self, value: ValueRef<'a>) {
         self.values.push(value)
     }
 
+    pub(crate) fn truncate(&mut self, length: usize) {
+        self.values.truncate(length)
+    }
+
     pub fn local<F, A>(&mut self, f: F) -> A
     where
         F: FnOnce(&mut Self) -> A,
@@ -99,47 +677,597 @@ impl<'a> Value<'a> {
             spine: Vec::new(),
         })
     }
+
+    pub fn primitive(primitive: Primitive) -> ValueRef<'a> {
+        Rc::new(Value::Neutral {
+            head: Head::Primitive(primitive),
+            spine: Vec::new(),
+        })
+    }
+
+    pub fn hole(hole: Hole<'a>) -> ValueRef<'a> {
+        Rc::new(Value::Neutral {
+            head: Head::Hole(hole),
+            spine: Vec::new(),
+        })
+    }
+
+    /// The head of a neutral.
+    pub fn head(&self) -> Option<&Head<'a>> {
+        match self {
+            Value::Neutral { head, .. } => Some(head),
+            _ => None,
+        }
+    }
+
+    /// The frames of the spine of a neutral, the one applied to the head
+    /// first; none for other values.
+    pub fn spine(&self) -> impl Iterator<Item = &Frame<'a>> {
+        match self {
+            Value::Neutral { spine, .. } => spine.iter(),
+            _ => [].iter(),
+        }
+    }
+}
+
+/// Adds `frames` to a neutral. A primitive that this saturates with literals
+/// computes, and the frames beyond its arguments are applied to its result.
+/// A constructor that this analyses by cases selects its branch, which the
+/// frames after the case analysis are applied to. A meta that has been solved
+/// since the neutral was made is replaced by its solution.
+fn extend_neutral<'a>(
+    head: &Head<'a>,
+    spine: &[Frame<'a>],
+    frames: impl IntoIterator<Item = Frame<'a>>,
+    fuel: &Fuel,
+) -> Result<ValueRef<'a>, Interrupted> {
+    if let Head::Meta { meta, metas } = head {
+        if let Some(solution) = metas.solution(*meta) {
+            let spine = Spine::from_frames(spine.iter().cloned().chain(frames));
+            return apply_spine_with_fuel(&solution, spine, fuel);
+        }
+    }
+    let mut spine = Vec::from_iter(spine.iter().cloned().chain(frames));
+    if let Head::Primitive(primitive) = head {
+        if spine.len() >= primitive.arity() {
+            let rest = spine.split_off(primitive.arity());
+            if let Some(result) = compute(*primitive, &spine, fuel)? {
+                return apply_spine_with_fuel(&result, Spine::from_frames(rest), fuel);
+            }
+            spine.extend(rest);
+        }
+    }
+    if let Head::Constructor(constructor) = head {
+        let case = spine
+            .iter()
+            .position(|frame| matches!(frame, Frame::Case { .. }));
+        if let Some(case) = case {
+            let rest = spine.split_off(case + 1);
+            if let Some(Frame::Case {
+                branches,
+                environment,
+            }) = spine.last()
+            {
+                if let Some((body, mut environment)) =
+                    select(constructor, &spine[..case], branches, environment)
+                {
+                    return body.machine_rc(Spine::from_frames(rest), &mut environment, fuel);
+                }
+            }
+            spine.extend(rest);
+        }
+    }
+    Ok(Rc::new(Value::Neutral {
+        head: head.clone(),
+        spine,
+    }))
+}
+
+/// The body of the branch for `constructor`, applied to `arguments`, and the
+/// environment to evaluate it in: that of the branches, extended with the
+/// fields, which are the last arguments. Returns `None` if there is no such
+/// branch, which leaves the case analysis stuck.
+fn select<'a>(
+    constructor: &Constructor<'a>,
+    arguments: &[Frame<'a>],
+    branches: &'a [Branch<'a>],
+    environment: &Environment<'a>,
+) -> Option<(TermRef<'a>, Environment<'a>)> {
+    let branch = (branches.iter()).find(|branch| branch.constructor.same(constructor))?;
+    let parameters = arguments.len().checked_sub(branch.arity)?;
+    let mut environment = environment.clone();
+    for frame in &arguments[parameters..] {
+        match frame {
+            Frame::Application(_, field) => environment.extend(field.clone()),
+            _ => return None,
+        }
+    }
+    Some((branch.body, environment))
+}
+
+/// Applies `primitive` to the arguments in `spine`, if they are all literals.
+fn compute<'a>(
+    primitive: Primitive,
+    spine: &[Frame<'a>],
+    fuel: &Fuel,
+) -> Result<Option<ValueRef<'a>>, Interrupted> {
+    let mut arguments = Vec::with_capacity(spine.len());
+    for frame in spine {
+        match frame {
+            Frame::Application(_, argument) => match &*unfold_with_fuel(argument, fuel)? {
+                Value::Integer(value) => arguments.push(*value),
+                _ => return Ok(None),
+            },
+            _ => return Ok(None),
+        }
+    }
+    Ok(primitive.apply(&arguments).map(|output| {
+        Rc::new(match output {
+            Output::Integer(value) => Value::Integer(value),
+            Output::Natural(value) => Value::Natural(value),
+        })
+    }))
 }
 
 pub fn apply<'a>(function: &Value<'a>, argument: ValueRef<'a>) -> ValueRef<'a> {
+    apply_with(function, Plicity::Explicit, argument)
+}
+
+/// Applies `function` to `argument`, explicitly or implicitly.
+pub fn apply_with<'a>(
+    function: &Value<'a>,
+    plicity: Plicity,
+    argument: ValueRef<'a>,
+) -> ValueRef<'a> {
+    unmetered(|fuel| apply_with_fuel(function, plicity, argument, fuel))
+}
+
+/// [`apply_with`], which takes a step of `fuel` if `function` is a lambda,
+/// and then whatever evaluating its body takes.
+pub fn apply_with_fuel<'a>(
+    function: &Value<'a>,
+    plicity: Plicity,
+    argument: ValueRef<'a>,
+    fuel: &Fuel,
+) -> Result<ValueRef<'a>, Interrupted> {
     match function {
-        Value::Neutral { head, spine } => Rc::new(Value::Neutral {
-            head: head.clone(),
-            spine: Vec::from_iter(spine.iter().cloned().chain(std::iter::once(argument))),
-        }),
-        Value::Lambda(Closure { term, environment }) => {
-            let mut environment = environment.clone();
-            environment.extend(argument);
-            term.evaluate_rc(&mut environment)
+        Value::Neutral { head, spine } => {
+            extend_neutral(head, spine, [Frame::Application(plicity, argument)], fuel)
+        }
+        Value::Lambda(_, closure) => closure.apply_with_fuel(argument, fuel),
+        Value::Pair(..)
+        | Value::Record(_)
+        | Value::Pi(..)
+        | Value::Sigma(..)
+        | Value::RecordType(..)
+        | Value::Type
+        | Value::Nat
+        | Value::Natural(_)
+        | Value::Suc(_)
+        | Value::IntegerType(_)
+        | Value::Integer(_) => Ok(stuck(function, Frame::Application(plicity, argument))),
+        Value::Thunk(thunk) => {
+            apply_with_fuel(&*thunk.force_with_fuel(fuel)?, plicity, argument, fuel)
         }
     }
 }
 
-pub fn apply_spine<'a>(function: &ValueRef<'a>, mut spine: Spine<'a>) -> ValueRef<'a> {
-    if spine.is_empty() {
-        return function.clone();
+/// `value` eliminated by `frame`, which cannot eliminate it.
+fn stuck<'a>(value: &Value<'a>, frame: Frame<'a>) -> ValueRef<'a> {
+    let value = match value {
+        Value::Lambda(plicity, closure) => Value::Lambda(*plicity, closure.clone()),
+        Value::Pair(first, second) => Value::Pair(first.clone(), second.clone()),
+        Value::Pi(plicity, domain, codomain) => {
+            Value::Pi(*plicity, domain.clone(), codomain.clone())
+        }
+        Value::Sigma(domain, codomain) => Value::Sigma(domain.clone(), codomain.clone()),
+        Value::RecordType(fields, environment) => Value::RecordType(fields, environment.clone()),
+        Value::Record(fields) => Value::Record(fields.clone()),
+        Value::Type => Value::Type,
+        Value::Nat => Value::Nat,
+        Value::Natural(value) => Value::Natural(*value),
+        Value::Suc(predecessor) => Value::Suc(predecessor.clone()),
+        Value::IntegerType(ty) => Value::IntegerType(*ty),
+        Value::Integer(value) => Value::Integer(*value),
+        Value::Neutral { .. } | Value::Thunk(_) => {
+            unreachable!("neutrals and thunks can always be eliminated")
+        }
+    };
+    Rc::new(Value::Neutral {
+        head: Head::Stuck(Rc::new(value)),
+        spine: vec![frame],
+    })
+}
+
+/// The value behind `value` with thunks forced, and the globals and solved
+/// metas at its head unfolded, so that it is neither a thunk nor a neutral
+/// headed by a global or a solved meta.
+pub fn unfold<'a>(value: &ValueRef<'a>) -> ValueRef<'a> {
+    unmetered(|fuel| unfold_with_fuel(value, fuel))
+}
+
+/// [`unfold`], metered by `fuel`.
+pub fn unfold_with_fuel<'a>(
+    value: &ValueRef<'a>,
+    fuel: &Fuel,
+) -> Result<ValueRef<'a>, Interrupted> {
+    let mut value = force_with_fuel(value, fuel)?;
+    loop {
+        let unfolded = match unfold_global_with_fuel(&value, fuel)? {
+            Some(unfolded) => unfolded,
+            None => match force_meta_with_fuel(&value, fuel)? {
+                Some(unfolded) => unfolded,
+                None => return Ok(value),
+            },
+        };
+        value = force_with_fuel(&unfolded, fuel)?;
     }
-    match &**function {
+}
+
+/// Applies the solution of the meta at the head of `value` to its spine, if
+/// `value` is a neutral headed by a solved meta.
+pub(crate) fn force_meta<'a>(value: &Value<'a>) -> Option<ValueRef<'a>> {
+    unmetered(|fuel| force_meta_with_fuel(value, fuel))
+}
+
+fn force_meta_with_fuel<'a>(
+    value: &Value<'a>,
+    fuel: &Fuel,
+) -> Result<Option<ValueRef<'a>>, Interrupted> {
+    match value {
         Value::Neutral {
+            head: Head::Meta { meta, metas },
+            spine,
+        } => match metas.solution(*meta) {
+            Some(solution) => {
+                let spine = Spine::from_frames(spine.iter().cloned());
+                apply_spine_with_fuel(&solution, spine, fuel).map(Some)
+            }
+            None => Ok(None),
+        },
+        _ => Ok(None),
+    }
+}
+
+/// Projects a component out of `pair`, where `projection` is
+/// [`Frame::First`] or [`Frame::Second`].
+pub fn project<'a>(pair: &Value<'a>, projection: Frame<'a>) -> ValueRef<'a> {
+    unmetered(|fuel| project_with_fuel(pair, projection, fuel))
+}
+
+fn project_with_fuel<'a>(
+    pair: &Value<'a>,
+    projection: Frame<'a>,
+    fuel: &Fuel,
+) -> Result<ValueRef<'a>, Interrupted> {
+    match (pair, projection) {
+        (
+            _,
+            Frame::Application(..) | Frame::Project(_) | Frame::NatElim { .. } | Frame::Case { .. },
+        ) => panic!("projected with a frame that is not a projection out of a pair"),
+        (Value::Neutral { head, spine }, projection) => {
+            extend_neutral(head, spine, [projection], fuel)
+        }
+        (Value::Pair(first, _), Frame::First) => force_with_fuel(first, fuel),
+        (Value::Pair(_, second), Frame::Second) => force_with_fuel(second, fuel),
+        (Value::Thunk(thunk), projection) => {
+            project_with_fuel(&*thunk.force_with_fuel(fuel)?, projection, fuel)
+        }
+        (_, projection) => Ok(stuck(pair, projection)),
+    }
+}
+
+/// Projects the field `name` out of `record`, which is stuck if `record`
+/// is not a record with that field.
+pub fn project_field<'a>(record: &Value<'a>, name: &'a str) -> ValueRef<'a> {
+    unmetered(|fuel| project_field_with_fuel(record, name, fuel))
+}
+
+fn project_field_with_fuel<'a>(
+    record: &Value<'a>,
+    name: &'a str,
+    fuel: &Fuel,
+) -> Result<ValueRef<'a>, Interrupted> {
+    match record {
+        Value::Neutral { head, spine } => extend_neutral(head, spine, [Frame::Project(name)], fuel),
+        Value::Record(fields) => match fields.iter().find(|&&(field, _)| field == name) {
+            Some((_, value)) => force_with_fuel(value, fuel),
+            None => Ok(stuck(record, Frame::Project(name))),
+        },
+        Value::Thunk(thunk) => project_field_with_fuel(&*thunk.force_with_fuel(fuel)?, name, fuel),
+        _ => Ok(stuck(record, Frame::Project(name))),
+    }
+}
+
+/// The types of the fields of the record type of `fields` in `environment`,
+/// each evaluated with the fields before it bound to what `field` gives for
+/// their positions and names.
+pub(crate) fn field_types<'a>(
+    fields: &'a [(&'a str, TermRef<'a>)],
+    environment: &Environment<'a>,
+    field: impl FnMut(usize, &'a str) -> ValueRef<'a>,
+) -> Vec<ValueRef<'a>> {
+    unmetered(|fuel| field_types_with_fuel(fields, environment, field, fuel))
+}
+
+fn field_types_with_fuel<'a>(
+    fields: &'a [(&'a str, TermRef<'a>)],
+    environment: &Environment<'a>,
+    mut field: impl FnMut(usize, &'a str) -> ValueRef<'a>,
+    fuel: &Fuel,
+) -> Result<Vec<ValueRef<'a>>, Interrupted> {
+    let mut environment = environment.clone();
+    (fields.iter().enumerate())
+        .map(|(position, &(name, ty))| {
+            let ty = ty.evaluate_rc_with_fuel(&mut environment.clone(), fuel)?;
+            environment.extend(field(position, name));
+            Ok(ty)
+        })
+        .collect()
+}
+
+/// The successor of `value`, which is a literal if `value` is a literal
+/// below `u64::MAX`.
+fn successor<'a>(value: ValueRef<'a>) -> ValueRef<'a> {
+    match &*value {
+        Value::Natural(value) if *value < u64::MAX => Rc::new(Value::Natural(value + 1)),
+        _ => Rc::new(Value::Suc(value)),
+    }
+}
+
+/// Primitive recursion on `scrutinee`, which blocks if it is neutral and is
+/// stuck if it is not a natural number.
+///
+/// The recursion runs bottom up in a loop, applying `suc` once for each
+/// successor, so large literals do not need a deep stack.
+pub fn nat_elim<'a>(
+    scrutinee: &Value<'a>,
+    motive: ValueRef<'a>,
+    zero: ValueRef<'a>,
+    suc: ValueRef<'a>,
+) -> ValueRef<'a> {
+    unmetered(|fuel| nat_elim_with_fuel(scrutinee, motive, zero, suc, fuel))
+}
+
+fn nat_elim_with_fuel<'a>(
+    scrutinee: &Value<'a>,
+    motive: ValueRef<'a>,
+    zero: ValueRef<'a>,
+    suc: ValueRef<'a>,
+    fuel: &Fuel,
+) -> Result<ValueRef<'a>, Interrupted> {
+    let mut predecessors = Vec::new();
+    let mut scrutinee = scrutinee;
+    let mut forced;
+    loop {
+        match scrutinee {
+            Value::Suc(predecessor) => {
+                predecessors.push(predecessor.clone());
+                scrutinee = predecessor;
+            }
+            Value::Thunk(thunk) => {
+                forced = thunk.force_with_fuel(fuel)?;
+                scrutinee = &forced;
+            }
+            _ => break,
+        }
+    }
+    let mut result = match scrutinee {
+        Value::Natural(value) => {
+            (0..*value).try_fold(force_with_fuel(&zero, fuel)?, |result, predecessor| {
+                apply_suc(&suc, Rc::new(Value::Natural(predecessor)), result, fuel)
+            })?
+        }
+        Value::Neutral { head, spine } => extend_neutral(
             head,
-            spine: function_spine,
-        } => {
-            let mut new_spine = function_spine.clone();
-            new_spine.extend(spine.into_iter());
-            Rc::new(Value::Neutral {
-                head: head.clone(),
-                spine: new_spine,
-            })
-        }
-        Value::Lambda(Closure { term, environment }) => {
-            if let Some(argument) = spine.pop_front() {
+            spine,
+            [Frame::NatElim {
+                motive,
+                zero,
+                suc: suc.clone(),
+            }],
+            fuel,
+        )?,
+        _ => stuck(
+            scrutinee,
+            Frame::NatElim {
+                motive,
+                zero,
+                suc: suc.clone(),
+            },
+        ),
+    };
+    for predecessor in predecessors.into_iter().rev() {
+        result = apply_suc(&suc, predecessor, result, fuel)?;
+    }
+    Ok(result)
+}
+
+/// Case analysis on `scrutinee`, which selects a branch if it is a
+/// constructor, blocks if it is any other neutral, and is stuck if it is not
+/// of a datatype.
+pub fn case<'a>(
+    scrutinee: &Value<'a>,
+    branches: &'a [Branch<'a>],
+    environment: Rc<Environment<'a>>,
+) -> ValueRef<'a> {
+    unmetered(|fuel| case_with_fuel(scrutinee, branches, environment, fuel))
+}
+
+fn case_with_fuel<'a>(
+    scrutinee: &Value<'a>,
+    branches: &'a [Branch<'a>],
+    environment: Rc<Environment<'a>>,
+    fuel: &Fuel,
+) -> Result<ValueRef<'a>, Interrupted> {
+    match scrutinee {
+        Value::Neutral { head, spine } => extend_neutral(
+            head,
+            spine,
+            [Frame::Case {
+                branches,
+                environment,
+            }],
+            fuel,
+        ),
+        Value::Thunk(thunk) => {
+            case_with_fuel(&*thunk.force_with_fuel(fuel)?, branches, environment, fuel)
+        }
+        _ => Ok(stuck(
+            scrutinee,
+            Frame::Case {
+                branches,
+                environment,
+            },
+        )),
+    }
+}
+
+/// Applies the successor case of a recursion to a predecessor and the
+/// result for it, in one run of the evaluator.
+fn apply_suc<'a>(
+    suc: &ValueRef<'a>,
+    predecessor: ValueRef<'a>,
+    result: ValueRef<'a>,
+    fuel: &Fuel,
+) -> Result<ValueRef<'a>, Interrupted> {
+    let mut spine = Spine::new();
+    spine.push_front(Frame::Application(Plicity::Explicit, result));
+    spine.push_front(Frame::Application(Plicity::Explicit, predecessor));
+    apply_spine_with_fuel(suc, spine, fuel)
+}
+
+pub fn apply_spine<'a>(function: &ValueRef<'a>, spine: Spine<'a>) -> ValueRef<'a> {
+    unmetered(|fuel| apply_spine_with_fuel(function, spine, fuel))
+}
+
+/// [`apply_spine`], which takes a step of `fuel` for each lambda that is
+/// applied, and then whatever evaluating their bodies takes.
+pub fn apply_spine_with_fuel<'a>(
+    function: &ValueRef<'a>,
+    mut spine: Spine<'a>,
+    fuel: &Fuel,
+) -> Result<ValueRef<'a>, Interrupted> {
+    let mut function = function.clone();
+    while let Some(frame) = spine.pop_front() {
+        function = match (&*function, frame) {
+            (
+                Value::Neutral {
+                    head,
+                    spine: function_spine,
+                },
+                frame,
+            ) => {
+                return extend_neutral(
+                    head,
+                    function_spine,
+                    std::iter::once(frame).chain(spine.into_frames()),
+                    fuel,
+                )
+            }
+            (Value::Lambda(_, Closure { term, environment }), Frame::Application(_, argument)) => {
+                fuel.consume()?;
                 let mut environment = environment.clone();
                 environment.extend(argument);
-                term.evaluate_with_spine_rc(spine, &mut environment)
-            } else {
-                function.clone()
+                return term.machine_rc(spine, &mut environment, fuel);
             }
-        }
+            (Value::Pair(first, _), Frame::First) => force_with_fuel(first, fuel)?,
+            (Value::Pair(_, second), Frame::Second) => force_with_fuel(second, fuel)?,
+            (Value::Record(_), Frame::Project(name)) => {
+                project_field_with_fuel(&function, name, fuel)?
+            }
+            (Value::Thunk(thunk), frame) => {
+                spine.push_front(frame);
+                thunk.force_with_fuel(fuel)?
+            }
+            (Value::Natural(_) | Value::Suc(_), Frame::NatElim { motive, zero, suc }) => {
+                nat_elim_with_fuel(&function, motive, zero, suc, fuel)?
+            }
+            (_, frame) => {
+                return Ok(Rc::new(Value::Neutral {
+                    head: Head::Stuck(function.clone()),
+                    spine: Vec::from_iter(std::iter::once(frame).chain(spine.into_frames())),
+                }))
+            }
+        };
+    }
+    Ok(function)
+}
+
+/// What to do with the value of the term currently being evaluated. The
+/// evaluator keeps these on an explicit stack rather than recursing, so the
+/// depth of the terms it can handle is bounded by the heap.
+enum Continuation<'a> {
+    /// The value is the argument of an application: push it onto `spine`
+    /// and evaluate `function`, in the environment as it was when the
+    /// argument was entered.
+    Argument {
+        plicity: Plicity,
+        function: TermRef<'a>,
+        spine: Spine<'a>,
+        environment_length: usize,
+    },
+    /// The value is bound by a `let`: extend the environment, as it was when
+    /// the bound term was entered, with it and evaluate `body` against `spine`.
+    Let {
+        body: TermRef<'a>,
+        spine: Spine<'a>,
+        environment_length: usize,
+    },
+    /// The value is the domain of a dependent function type whose codomain
+    /// is `codomain`, in the environment as it was when the domain was
+    /// entered.
+    Pi {
+        plicity: Plicity,
+        codomain: TermRef<'a>,
+        spine: Spine<'a>,
+        environment_length: usize,
+    },
+    /// The value is the domain of a dependent pair type whose codomain is
+    /// `codomain`, in the environment as it was when the domain was entered.
+    Sigma {
+        codomain: TermRef<'a>,
+        spine: Spine<'a>,
+        environment_length: usize,
+    },
+    /// The value is the first component of a pair whose second component is
+    /// `second`, in the environment as it was when the first was entered.
+    PairFirst {
+        second: TermRef<'a>,
+        spine: Spine<'a>,
+        environment_length: usize,
+    },
+    /// The value is the second component of a pair whose first component
+    /// is `first`.
+    PairSecond {
+        first: ValueRef<'a>,
+        spine: Spine<'a>,
+    },
+    /// The value is the predecessor of a natural number.
+    Suc { spine: Spine<'a> },
+    /// The value is that of a thunk, `Value::Thunk`, which should memoise it
+    /// before it is applied to `spine`.
+    Update {
+        thunk: ValueRef<'a>,
+        spine: Spine<'a>,
+    },
+    /// The value is the body of a closure that was entered from `Environment`,
+    /// which should be reinstated.
+    Restore(Environment<'a>),
+}
+
+/// Replaces the current environment with `new_environment`, remembering the
+/// current one if any pending continuation still refers to it.
+fn switch_environment<'a>(
+    environment: &mut Environment<'a>,
+    new_environment: Environment<'a>,
+    continuations: &mut Vec<Continuation<'a>>,
+) {
+    let environment = std::mem::replace(environment, new_environment);
+    // Only the continuations pushed since the last `Restore` refer to the
+    // current environment.
+    if !matches!(continuations.last(), Some(Continuation::Restore(_))) {
+        continuations.push(Continuation::Restore(environment));
     }
 }
 
@@ -150,65 +1278,1096 @@ impl<'a> Term<'a> {
 
     pub fn evaluate_with_spine_rc(
         &self,
-        mut spine: Spine<'a>,
+        spine: Spine<'a>,
         environment: &mut Environment<'a>,
     ) -> ValueRef<'a> {
+        unmetered(|fuel| self.machine_rc(spine, environment, fuel))
+    }
+
+    /// [`Term::evaluate_rc`], which is [`Interrupted`] once it has taken more
+    /// beta steps than `fuel` allows or `fuel` is cancelled. If it is,
+    /// `environment` is put back as it was, thunks that were being forced are
+    /// suspended again so they can still be forced later, and the values
+    /// built so far are dropped.
+    pub fn evaluate_rc_with_fuel(
+        &self,
+        environment: &mut Environment<'a>,
+        fuel: &Fuel,
+    ) -> Result<ValueRef<'a>, Interrupted> {
+        self.machine_rc(Spine::new(), environment, fuel)
+    }
+
+    /// Evaluates the term to weak head normal form, without reading it back:
+    /// the value is not a thunk, and if it is a neutral, its head is neither
+    /// a global nor a solved meta. Its subterms are left as they are.
+    pub fn whnf_rc(&self, environment: &mut Environment<'a>) -> ValueRef<'a> {
+        unmetered(|fuel| self.whnf_rc_with_fuel(environment, fuel))
+    }
+
+    /// [`Term::whnf_rc`], metered by `fuel`.
+    pub fn whnf_rc_with_fuel(
+        &self,
+        environment: &mut Environment<'a>,
+        fuel: &Fuel,
+    ) -> Result<ValueRef<'a>, Interrupted> {
+        let value = self.evaluate_rc_with_fuel(environment, fuel)?;
+        unfold_with_fuel(&value, fuel)
+    }
+
+    fn machine_rc(
+        &self,
+        spine: Spine<'a>,
+        environment: &mut Environment<'a>,
+        fuel: &Fuel,
+    ) -> Result<ValueRef<'a>, Interrupted> {
+        let environment_length = environment.values.len();
+        let mut continuations = Vec::new();
+        let result = self.run_machine_rc(spine, environment, &mut continuations, fuel);
+        if result.is_err() {
+            // Put back what the interrupted run changed: the environment it
+            // was entered with, and the thunks it was forcing.
+            while let Some(continuation) = continuations.pop() {
+                match continuation {
+                    Continuation::Restore(saved_environment) => *environment = saved_environment,
+                    Continuation::Update { thunk, .. } => {
+                        if let Value::Thunk(thunk) = &*thunk {
+                            thunk.abandon_forcing();
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            environment.truncate(environment_length);
+        }
+        result
+    }
+
+    fn run_machine_rc(
+        &self,
+        mut spine: Spine<'a>,
+        environment: &mut Environment<'a>,
+        continuations: &mut Vec<Continuation<'a>>,
+        fuel: &Fuel,
+    ) -> Result<ValueRef<'a>, Interrupted> {
         let mut head = self;
         loop {
-            match head {
+            let mut value = match head {
                 Term::Variable(index) => {
-                    let head = &environment[*index];
-                    return apply_spine(head, spine);
+                    let function = environment[*index].clone();
+                    if let Value::Thunk(thunk) = &*function {
+                        match thunk.start_forcing(fuel) {
+                            Ok(value) => value,
+                            Err((term, thunk_environment)) => {
+                                switch_environment(environment, thunk_environment, continuations);
+                                continuations.push(Continuation::Update {
+                                    thunk: function.clone(),
+                                    spine: std::mem::take(&mut spine),
+                                });
+                                head = term;
+                                continue;
+                            }
+                        }
+                    } else {
+                        function
+                    }
                 }
-                Term::Lambda(body) => {
-                    if let Some(argument) = spine.pop_front() {
+                Term::Lambda(plicity, _, body) => {
+                    if let Some(argument) = spine.pop_argument() {
+                        fuel.consume()?;
                         environment.extend(argument);
                         head = body;
+                        continue;
                     } else {
-                        return Rc::new(Value::Lambda(Closure {
-                            term: body,
-                            environment: environment.clone(),
-                        }));
+                        Rc::new(Value::Lambda(
+                            *plicity,
+                            Closure {
+                                term: body,
+                                environment: environment.clone(),
+                            },
+                        ))
                     }
                 }
-                Term::Application(function, argument) => {
-                    let argument =
-                        environment.local(|environment| argument.evaluate_rc(environment));
-                    spine.push_front(argument);
-                    head = function;
+                Term::Application(plicity, function, argument) => match environment.strategy {
+                    Strategy::CallByValue => {
+                        continuations.push(Continuation::Argument {
+                            plicity: *plicity,
+                            function,
+                            spine: std::mem::take(&mut spine),
+                            environment_length: environment.values.len(),
+                        });
+                        head = argument;
+                        continue;
+                    }
+                    Strategy::CallByNeed => {
+                        spine.push_front(Frame::Application(*plicity, environment.delay(argument)));
+                        head = function;
+                        continue;
+                    }
+                },
+                Term::Let(value, body) => match environment.strategy {
+                    Strategy::CallByValue => {
+                        continuations.push(Continuation::Let {
+                            body,
+                            spine: std::mem::take(&mut spine),
+                            environment_length: environment.values.len(),
+                        });
+                        head = value;
+                        continue;
+                    }
+                    Strategy::CallByNeed => {
+                        environment.extend(environment.delay(value));
+                        head = body;
+                        continue;
+                    }
+                },
+                Term::Pi(plicity, domain, codomain) => match environment.strategy {
+                    Strategy::CallByValue => {
+                        continuations.push(Continuation::Pi {
+                            plicity: *plicity,
+                            codomain,
+                            spine: std::mem::take(&mut spine),
+                            environment_length: environment.values.len(),
+                        });
+                        head = domain;
+                        continue;
+                    }
+                    Strategy::CallByNeed => Rc::new(Value::Pi(
+                        *plicity,
+                        environment.delay(domain),
+                        Closure::new(codomain, environment.clone()),
+                    )),
+                },
+                Term::Sigma(domain, codomain) => match environment.strategy {
+                    Strategy::CallByValue => {
+                        continuations.push(Continuation::Sigma {
+                            codomain,
+                            spine: std::mem::take(&mut spine),
+                            environment_length: environment.values.len(),
+                        });
+                        head = domain;
+                        continue;
+                    }
+                    Strategy::CallByNeed => Rc::new(Value::Sigma(
+                        environment.delay(domain),
+                        Closure::new(codomain, environment.clone()),
+                    )),
+                },
+                Term::Pair(first, second) => match environment.strategy {
+                    Strategy::CallByValue => {
+                        continuations.push(Continuation::PairFirst {
+                            second,
+                            spine: std::mem::take(&mut spine),
+                            environment_length: environment.values.len(),
+                        });
+                        head = first;
+                        continue;
+                    }
+                    Strategy::CallByNeed => Rc::new(Value::Pair(
+                        environment.delay(first),
+                        environment.delay(second),
+                    )),
+                },
+                Term::RecordType(fields) => Rc::new(Value::RecordType(fields, environment.clone())),
+                Term::Record(fields) => Rc::new(Value::Record(
+                    (fields.iter())
+                        .map(|&(name, field)| Ok((name, environment.argument(field, fuel)?)))
+                        .collect::<Result<_, _>>()?,
+                )),
+                Term::Project(record, name) => {
+                    spine.push_front(Frame::Project(name));
+                    head = record;
+                    continue;
+                }
+                Term::Fst(pair) => {
+                    spine.push_front(Frame::First);
+                    head = pair;
+                    continue;
+                }
+                Term::Snd(pair) => {
+                    spine.push_front(Frame::Second);
+                    head = pair;
+                    continue;
+                }
+                Term::Type => Rc::new(Value::Type),
+                Term::Nat => Rc::new(Value::Nat),
+                Term::Zero => Rc::new(Value::Natural(0)),
+                Term::Natural(value) => Rc::new(Value::Natural(*value)),
+                Term::IntegerType(ty) => Rc::new(Value::IntegerType(*ty)),
+                Term::Integer(value) => Rc::new(Value::Integer(*value)),
+                Term::Primitive(primitive) => Value::primitive(*primitive),
+                Term::Global(global) => environment.global(*global),
+                Term::Meta(meta) => environment.meta(*meta),
+                Term::Hole(hole) => Value::hole(*hole),
+                Term::TypeConstructor(type_constructor) => Rc::new(Value::Neutral {
+                    head: Head::TypeConstructor(*type_constructor),
+                    spine: Vec::new(),
+                }),
+                Term::Constructor(constructor) => Rc::new(Value::Neutral {
+                    head: Head::Constructor(*constructor),
+                    spine: Vec::new(),
+                }),
+                Term::Case(scrutinee, branches) => {
+                    spine.push_front(Frame::Case {
+                        branches,
+                        environment: Rc::new(environment.clone()),
+                    });
+                    head = scrutinee;
+                    continue;
+                }
+                Term::Suc(predecessor) => {
+                    continuations.push(Continuation::Suc {
+                        spine: std::mem::take(&mut spine),
+                    });
+                    head = predecessor;
+                    continue;
+                }
+                Term::NatElim(motive, zero, suc, scrutinee) => {
+                    spine.push_front(Frame::NatElim {
+                        motive: environment.argument(motive, fuel)?,
+                        zero: environment.argument(zero, fuel)?,
+                        suc: environment.argument(suc, fuel)?,
+                    });
+                    head = scrutinee;
+                    continue;
+                }
+            };
+            // `value` is in weak head normal form; apply it to the pending
+            // spine and pass the result on.
+            loop {
+                if !spine.is_empty() {
+                    if let Value::Lambda(_, closure) = &*value {
+                        if let Some(argument) = spine.pop_argument() {
+                            fuel.consume()?;
+                            let mut closure_environment = closure.environment.clone();
+                            closure_environment.extend(argument);
+                            switch_environment(environment, closure_environment, continuations);
+                            head = closure.term;
+                            break;
+                        }
+                    }
+                    if let Value::Pair(..) = &*value {
+                        if matches!(spine.iter().next(), Some(Frame::First | Frame::Second)) {
+                            let projection = spine.pop_front().unwrap();
+                            value = project_with_fuel(&value, projection, fuel)?;
+                            continue;
+                        }
+                    }
+                    value = apply_spine_with_fuel(&value, std::mem::take(&mut spine), fuel)?;
+                }
+                match continuations.pop() {
+                    None => return Ok(value),
+                    Some(Continuation::Restore(saved_environment)) => {
+                        *environment = saved_environment;
+                    }
+                    Some(Continuation::Update {
+                        thunk,
+                        spine: thunk_spine,
+                    }) => {
+                        if let Value::Thunk(thunk) = &*thunk {
+                            thunk.finish_forcing(value.clone());
+                        }
+                        spine = thunk_spine;
+                    }
+                    Some(Continuation::Argument {
+                        plicity,
+                        function,
+                        spine: function_spine,
+                        environment_length,
+                    }) => {
+                        environment.values.truncate(environment_length);
+                        spine = function_spine;
+                        spine.push_front(Frame::Application(plicity, value));
+                        head = function;
+                        break;
+                    }
+                    Some(Continuation::Let {
+                        body,
+                        spine: body_spine,
+                        environment_length,
+                    }) => {
+                        environment.values.truncate(environment_length);
+                        environment.extend(value);
+                        spine = body_spine;
+                        head = body;
+                        break;
+                    }
+                    Some(Continuation::Pi {
+                        plicity,
+                        codomain,
+                        spine: pi_spine,
+                        environment_length,
+                    }) => {
+                        environment.values.truncate(environment_length);
+                        value = Rc::new(Value::Pi(
+                            plicity,
+                            value,
+                            Closure::new(codomain, environment.clone()),
+                        ));
+                        spine = pi_spine;
+                    }
+                    Some(Continuation::Sigma {
+                        codomain,
+                        spine: sigma_spine,
+                        environment_length,
+                    }) => {
+                        environment.values.truncate(environment_length);
+                        value = Rc::new(Value::Sigma(
+                            value,
+                            Closure::new(codomain, environment.clone()),
+                        ));
+                        spine = sigma_spine;
+                    }
+                    Some(Continuation::PairFirst {
+                        second,
+                        spine: pair_spine,
+                        environment_length,
+                    }) => {
+                        environment.values.truncate(environment_length);
+                        continuations.push(Continuation::PairSecond {
+                            first: value,
+                            spine: pair_spine,
+                        });
+                        head = second;
+                        break;
+                    }
+                    Some(Continuation::Suc { spine: suc_spine }) => {
+                        value = successor(value);
+                        spine = suc_spine;
+                    }
+                    Some(Continuation::PairSecond {
+                        first,
+                        spine: pair_spine,
+                    }) => {
+                        value = Rc::new(Value::Pair(first, value));
+                        spine = pair_spine;
+                    }
                 }
             }
         }
     }
 }
 
-impl<'a> Value<'a> {
-    pub fn quote(&self, level: Level, syntax_builder: &'a syntax::Builder) -> syntax::TermRef<'a> {
-        match self {
-            Value::Neutral { head, spine } => head.quote(spine, level, syntax_builder),
-            Value::Lambda(Closure { term, environment }) => syntax_builder.lambda({
-                let mut environment = environment.clone();
-                environment.extend(Value::variable(level));
-                term.evaluate_rc(&mut environment)
-                    .quote(level + 1, syntax_builder)
-            }),
+/// A unit of pending readback work. Quoting uses these on an explicit stack
+/// instead of recursing, so arbitrarily deep values can be read back.
+enum Task<'a> {
+    Quote(ValueRef<'a>, Level),
+    Lambda(Plicity),
+    Application(Plicity),
+    Pair,
+    First,
+    Second,
+    RecordType(&'a [(&'a str, TermRef<'a>)]),
+    Record(Vec<&'a str>),
+    Project(&'a str),
+    Pi(Plicity),
+    Sigma,
+    Suc,
+    NatElim,
+    Case(&'a [Branch<'a>]),
+}
+
+/// Options for [`Value::quote_with`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct QuoteOptions {
+    /// Read neutrals headed by a global back as the global applied to its
+    /// spine, rather than unfolding the global.
+    pub keep_globals: bool,
+    /// Leave out the implicit arguments of neutrals, for printing. The
+    /// binders of implicit lambdas and function types are kept, so the
+    /// result is only meant to be shown, not evaluated.
+    pub hide_implicits: bool,
+    /// Eta-contract lambdas while reading them back, so that `\x. f x`
+    /// becomes `f` when `x` does not occur in `f`, giving beta-eta-short
+    /// forms.
+    pub eta_contract: bool,
+}
+
+struct Readback<'a, 'b> {
+    tasks: Vec<Task<'a>>,
+    results: Vec<syntax::TermRef<'b>>,
+    syntax_builder: &'b syntax::Builder,
+    options: QuoteOptions,
+}
+
+impl<'a, 'b> Readback<'a, 'b> {
+    fn new(syntax_builder: &'b syntax::Builder, options: QuoteOptions) -> Self {
+        Readback {
+            tasks: Vec::new(),
+            results: Vec::new(),
+            syntax_builder,
+            options,
+        }
+    }
+
+    fn value(&mut self, value: &Value<'a>, level: Level, fuel: &Fuel) -> Result<(), Interrupted> {
+        match value {
+            Value::Neutral { head, spine } => self.neutral(head, spine, level, fuel)?,
+            Value::Lambda(plicity, closure) => {
+                let body = closure.apply_with_fuel(Value::variable(level), fuel)?;
+                self.tasks.push(Task::Lambda(*plicity));
+                self.tasks.push(Task::Quote(body, level + 1));
+            }
+            Value::Pair(first, second) => {
+                self.tasks.push(Task::Pair);
+                self.tasks.push(Task::Quote(second.clone(), level));
+                self.tasks.push(Task::Quote(first.clone(), level));
+            }
+            Value::Pi(_, domain, codomain) | Value::Sigma(domain, codomain) => {
+                let codomain = codomain.apply_with_fuel(Value::variable(level), fuel)?;
+                self.tasks.push(match value {
+                    Value::Pi(plicity, ..) => Task::Pi(*plicity),
+                    _ => Task::Sigma,
+                });
+                self.tasks.push(Task::Quote(codomain, level + 1));
+                self.tasks.push(Task::Quote(domain.clone(), level));
+            }
+            Value::RecordType(fields, environment) => {
+                let types = field_types_with_fuel(
+                    fields,
+                    environment,
+                    |position, _| Value::variable(level + position),
+                    fuel,
+                )?;
+                self.tasks.push(Task::RecordType(fields));
+                for (position, ty) in types.into_iter().enumerate().rev() {
+                    self.tasks.push(Task::Quote(ty, level + position));
+                }
+            }
+            Value::Record(fields) => {
+                self.tasks
+                    .push(Task::Record(fields.iter().map(|&(name, _)| name).collect()));
+                for (_, field) in fields.iter().rev() {
+                    self.tasks.push(Task::Quote(field.clone(), level));
+                }
+            }
+            Value::Type => self.results.push(self.syntax_builder.universe()),
+            Value::Nat => self.results.push(self.syntax_builder.nat()),
+            Value::IntegerType(ty) => self.results.push(self.syntax_builder.integer_type(*ty)),
+            Value::Integer(value) => self.results.push(self.syntax_builder.integer(*value)),
+            Value::Natural(value) => self.results.push(self.syntax_builder.natural(*value)),
+            Value::Suc(predecessor) => {
+                self.tasks.push(Task::Suc);
+                self.tasks.push(Task::Quote(predecessor.clone(), level));
+            }
+            Value::Thunk(thunk) => self.value(&*thunk.force_with_fuel(fuel)?, level, fuel)?,
+        }
+        Ok(())
+    }
+
+    /// Pushes the quoted head, followed by tasks that eliminate it with each
+    /// frame of the spine in turn. A stuck head is quoted by a task of its
+    /// own, which runs before the eliminations.
+    fn neutral(
+        &mut self,
+        head: &Head<'a>,
+        spine: &[Frame<'a>],
+        level: Level,
+        fuel: &Fuel,
+    ) -> Result<(), Interrupted> {
+        match head {
+            Head::Variable(var_level) => self
+                .results
+                .push(self.syntax_builder.variable(var_level.to_index(level))),
+            Head::Primitive(primitive) => {
+                self.results.push(self.syntax_builder.primitive(*primitive))
+            }
+            Head::Stuck(_) => {}
+            Head::Global { global, .. } if self.options.keep_globals => self
+                .results
+                .push(self.syntax_builder.global(global.index, global.name)),
+            Head::Global { unfolding, .. } => {
+                let unfolded = apply_spine_with_fuel(
+                    &force_with_fuel(unfolding, fuel)?,
+                    Spine::from_frames(spine.iter().cloned()),
+                    fuel,
+                )?;
+                self.tasks.push(Task::Quote(unfolded, level));
+                return Ok(());
+            }
+            Head::Meta { meta, metas } => match metas.solution(*meta) {
+                Some(solution) => {
+                    let spine = Spine::from_frames(spine.iter().cloned());
+                    let solved = apply_spine_with_fuel(&solution, spine, fuel)?;
+                    self.tasks.push(Task::Quote(solved, level));
+                    return Ok(());
+                }
+                None => self.results.push(self.syntax_builder.meta(*meta)),
+            },
+            Head::Hole(hole) => self
+                .results
+                .push(self.syntax_builder.hole(hole.name, hole.scope)),
+            Head::TypeConstructor(type_constructor) => self.results.push(
+                self.syntax_builder
+                    .type_constructor(type_constructor.index, type_constructor.name),
+            ),
+            Head::Constructor(constructor) => self
+                .results
+                .push(self.syntax_builder.constructor(*constructor)),
+        }
+        for frame in spine.iter().rev() {
+            match frame {
+                Frame::Application(Plicity::Implicit, _) if self.options.hide_implicits => {}
+                Frame::Application(plicity, argument) => {
+                    self.tasks.push(Task::Application(*plicity));
+                    self.tasks.push(Task::Quote(argument.clone(), level));
+                }
+                Frame::First => self.tasks.push(Task::First),
+                Frame::Second => self.tasks.push(Task::Second),
+                Frame::Project(name) => self.tasks.push(Task::Project(name)),
+                Frame::NatElim { motive, zero, suc } => {
+                    self.tasks.push(Task::NatElim);
+                    self.tasks.push(Task::Quote(suc.clone(), level));
+                    self.tasks.push(Task::Quote(zero.clone(), level));
+                    self.tasks.push(Task::Quote(motive.clone(), level));
+                }
+                Frame::Case {
+                    branches,
+                    environment,
+                } => {
+                    self.tasks.push(Task::Case(branches));
+                    for branch in branches.iter().rev() {
+                        let body = branch_body_with_fuel(branch, environment, level, fuel)?;
+                        self.tasks.push(Task::Quote(body, level + branch.arity));
+                    }
+                }
+            }
+        }
+        if let Head::Stuck(value) = head {
+            self.tasks.push(Task::Quote(value.clone(), level));
         }
+        Ok(())
     }
+
+    /// Runs the pending tasks, each of which takes a step of `fuel`.
+    fn run(mut self, fuel: &Fuel) -> Result<syntax::TermRef<'b>, Interrupted> {
+        while let Some(task) = self.tasks.pop() {
+            fuel.consume()?;
+            match task {
+                Task::Quote(value, level) => self.value(&value, level, fuel)?,
+                Task::Lambda(plicity) => {
+                    let body = self.results.pop().unwrap();
+                    self.results.push(match body {
+                        syntax::Term::Application(
+                            argument_plicity,
+                            function,
+                            syntax::Term::Variable(Index(0)),
+                        ) if self.options.eta_contract
+                            && *argument_plicity == plicity
+                            && !function.occurs(Index(0)) =>
+                        {
+                            self.syntax_builder.strengthen(function, 0)
+                        }
+                        _ => self.syntax_builder.lambda_with(plicity, None, body),
+                    });
+                }
+                Task::Application(plicity) => {
+                    let argument = self.results.pop().unwrap();
+                    let function = self.results.pop().unwrap();
+                    self.results.push(
+                        self.syntax_builder
+                            .application_with(plicity, function, argument),
+                    );
+                }
+                Task::Pair => {
+                    let second = self.results.pop().unwrap();
+                    let first = self.results.pop().unwrap();
+                    self.results.push(self.syntax_builder.pair(first, second));
+                }
+                Task::First => {
+                    let pair = self.results.pop().unwrap();
+                    self.results.push(self.syntax_builder.fst(pair));
+                }
+                Task::Second => {
+                    let pair = self.results.pop().unwrap();
+                    self.results.push(self.syntax_builder.snd(pair));
+                }
+                Task::RecordType(fields) => {
+                    let types = self.results.split_off(self.results.len() - fields.len());
+                    let fields: Vec<_> = (fields.iter().zip(types))
+                        .map(|(&(name, _), ty)| (name, ty))
+                        .collect();
+                    self.results.push(self.syntax_builder.record_type(&fields));
+                }
+                Task::Record(names) => {
+                    let fields = self.results.split_off(self.results.len() - names.len());
+                    let fields: Vec<_> = names.into_iter().zip(fields).collect();
+                    self.results.push(self.syntax_builder.record(&fields));
+                }
+                Task::Project(name) => {
+                    let record = self.results.pop().unwrap();
+                    self.results.push(self.syntax_builder.project(record, name));
+                }
+                Task::Pi(plicity) => {
+                    let codomain = self.results.pop().unwrap();
+                    let domain = self.results.pop().unwrap();
+                    self.results
+                        .push(self.syntax_builder.pi_with(plicity, domain, codomain));
+                }
+                Task::Sigma => {
+                    let codomain = self.results.pop().unwrap();
+                    let domain = self.results.pop().unwrap();
+                    self.results
+                        .push(self.syntax_builder.sigma(domain, codomain));
+                }
+                // The predecessor of a successor is only a literal here if it
+                // became one after the successor was built, such as an
+                // unfolded global.
+                Task::Suc => {
+                    let result = match self.results.pop().unwrap() {
+                        Term::Natural(value) if *value < u64::MAX => {
+                            self.syntax_builder.natural(value + 1)
+                        }
+                        predecessor => self.syntax_builder.suc(predecessor),
+                    };
+                    self.results.push(result);
+                }
+                Task::NatElim => {
+                    let suc = self.results.pop().unwrap();
+                    let zero = self.results.pop().unwrap();
+                    let motive = self.results.pop().unwrap();
+                    let scrutinee = self.results.pop().unwrap();
+                    self.results
+                        .push(self.syntax_builder.nat_elim(motive, zero, suc, scrutinee));
+                }
+                Task::Case(branches) => {
+                    let bodies = self.results.split_off(self.results.len() - branches.len());
+                    let scrutinee = self.results.pop().unwrap();
+                    let branches: Vec<_> = (branches.iter().zip(bodies))
+                        .map(|(branch, body)| {
+                            self.syntax_builder
+                                .branch(branch.constructor, branch.arity, body)
+                        })
+                        .collect();
+                    self.results
+                        .push(self.syntax_builder.case(scrutinee, &branches));
+                }
+            }
+        }
+        let result = self.results.pop().unwrap();
+        assert!(self.results.is_empty());
+        Ok(result)
+    }
+}
+
+/// The body of a branch of a stuck case analysis, with the fields bound to
+/// fresh variables from `level` on.
+fn branch_body<'a>(
+    branch: &Branch<'a>,
+    environment: &Environment<'a>,
+    level: Level,
+) -> ValueRef<'a> {
+    unmetered(|fuel| branch_body_with_fuel(branch, environment, level, fuel))
 }
 
-impl Head {
-    pub fn quote<'a>(
+fn branch_body_with_fuel<'a>(
+    branch: &Branch<'a>,
+    environment: &Environment<'a>,
+    level: Level,
+    fuel: &Fuel,
+) -> Result<ValueRef<'a>, Interrupted> {
+    let mut environment = environment.clone();
+    for field in 0..branch.arity {
+        environment.extend(Value::variable(level + field));
+    }
+    branch.body.evaluate_rc_with_fuel(&mut environment, fuel)
+}
+
+impl<'a> Value<'a> {
+    pub fn quote<'b>(
         &self,
-        spine: &Vec<ValueRef<'a>>,
         level: Level,
-        syntax_builder: &'a syntax::Builder,
-    ) -> syntax::TermRef<'a> {
-        let mut result = match self {
-            Head::Variable(var_level) => syntax_builder.variable(var_level.to_index(level)),
-        };
-        for arg in spine.iter() {
-            result = syntax_builder.application(result, arg.quote(level, syntax_builder));
+        syntax_builder: &'b syntax::Builder,
+    ) -> syntax::TermRef<'b> {
+        self.quote_with(level, syntax_builder, QuoteOptions::default())
+    }
+
+    pub fn quote_with<'b>(
+        &self,
+        level: Level,
+        syntax_builder: &'b syntax::Builder,
+        options: QuoteOptions,
+    ) -> syntax::TermRef<'b> {
+        unmetered(|fuel| self.quote_with_fuel(level, syntax_builder, options, fuel))
+    }
+
+    /// [`Value::quote_with`], which is [`Interrupted`] once the steps it
+    /// takes, evaluating under binders and reading back, run past `fuel` or
+    /// `fuel` is cancelled.
+    pub fn quote_with_fuel<'b>(
+        &self,
+        level: Level,
+        syntax_builder: &'b syntax::Builder,
+        options: QuoteOptions,
+        fuel: &Fuel,
+    ) -> Result<syntax::TermRef<'b>, Interrupted> {
+        let mut readback = Readback::new(syntax_builder, options);
+        readback.value(self, level, fuel)?;
+        readback.run(fuel)
+    }
+}
+
+impl<'a> Head<'a> {
+    pub fn quote<'b>(
+        &self,
+        spine: &[Frame<'a>],
+        level: Level,
+        syntax_builder: &'b syntax::Builder,
+    ) -> syntax::TermRef<'b> {
+        unmetered(|fuel| {
+            let mut readback = Readback::new(syntax_builder, QuoteOptions::default());
+            readback.neutral(self, spine, level, fuel)?;
+            readback.run(fuel)
+        })
+    }
+}
+
+impl<'a> Value<'a> {
+    /// Whether two values at `level` are equal up to beta and eta, for both
+    /// functions and pairs.
+    ///
+    /// Neutrals are compared head first and frame by frame, and lambdas are
+    /// only opened when the other side has been reached too, so the
+    /// comparison stops at the first mismatch without building normal forms.
+    ///
+    /// A global is only unfolded when the other side is not the same global,
+    /// or when the spines it is applied to on both sides differ.
+    pub fn conv(level: Level, value1: &Value<'a>, value2: &Value<'a>) -> bool {
+        let mut pairs = Vec::new();
+        conv_step(level, value1, value2, &mut pairs) && conv_pairs(pairs)
+    }
+}
+
+pub(crate) type ConvPairs<'a> = Vec<(Level, ValueRef<'a>, ValueRef<'a>)>;
+
+fn conv_pairs(mut pairs: ConvPairs<'_>) -> bool {
+    while let Some((level, value1, value2)) = pairs.pop() {
+        if !conv_step(level, &value1, &value2, &mut pairs) {
+            return false;
         }
-        result
+    }
+    true
+}
+
+/// Compares the heads of two neutrals and pushes the pairs of arguments
+/// their spines eliminate them with, along with the values in stuck heads.
+fn conv_neutrals<'a>(
+    level: Level,
+    (head1, spine1): (&Head<'a>, &[Frame<'a>]),
+    (head2, spine2): (&Head<'a>, &[Frame<'a>]),
+    pairs: &mut ConvPairs<'a>,
+) -> bool {
+    match (head1, head2) {
+        (Head::Stuck(value1), Head::Stuck(value2)) => {
+            pairs.push((level, value1.clone(), value2.clone()))
+        }
+        _ if head1 != head2 => return false,
+        _ => {}
+    }
+    if spine1.len() != spine2.len() {
+        return false;
+    }
+    for frames in spine1.iter().zip(spine2).rev() {
+        match frames {
+            (Frame::Application(_, argument1), Frame::Application(_, argument2)) => {
+                pairs.push((level, argument1.clone(), argument2.clone()))
+            }
+            (Frame::First, Frame::First) | (Frame::Second, Frame::Second) => {}
+            (Frame::Project(name1), Frame::Project(name2)) if name1 == name2 => {}
+            (
+                Frame::NatElim {
+                    motive: motive1,
+                    zero: zero1,
+                    suc: suc1,
+                },
+                Frame::NatElim {
+                    motive: motive2,
+                    zero: zero2,
+                    suc: suc2,
+                },
+            ) => pairs.extend([
+                (level, suc1.clone(), suc2.clone()),
+                (level, zero1.clone(), zero2.clone()),
+                (level, motive1.clone(), motive2.clone()),
+            ]),
+            (
+                Frame::Case {
+                    branches: branches1,
+                    environment: environment1,
+                },
+                Frame::Case {
+                    branches: branches2,
+                    environment: environment2,
+                },
+            ) => {
+                if branches1.len() != branches2.len() {
+                    return false;
+                }
+                for (branch1, branch2) in branches1.iter().zip(*branches2).rev() {
+                    if !branch1.constructor.same(&branch2.constructor)
+                        || branch1.arity != branch2.arity
+                    {
+                        return false;
+                    }
+                    pairs.push((
+                        level + branch1.arity,
+                        branch_body(branch1, environment1, level),
+                        branch_body(branch2, environment2, level),
+                    ));
+                }
+            }
+            _ => return false,
+        }
+    }
+    true
+}
+
+pub(crate) fn is_global(value: &Value<'_>) -> bool {
+    matches!(
+        value,
+        Value::Neutral {
+            head: Head::Global { .. },
+            ..
+        }
+    )
+}
+
+/// Whether `value1` and `value2` are the same global applied to spines that
+/// are convertible, which makes them equal without unfolding the global.
+pub(crate) fn same_global<'a>(level: Level, value1: &Value<'a>, value2: &Value<'a>) -> bool {
+    match (value1, value2) {
+        (
+            Value::Neutral {
+                head: head1 @ Head::Global { .. },
+                spine: spine1,
+            },
+            Value::Neutral {
+                head: head2,
+                spine: spine2,
+            },
+        ) if head1 == head2 => {
+            // The spines are compared on their own, so that a mismatch can
+            // still be retried on the unfoldings.
+            let mut pairs = Vec::new();
+            conv_neutrals(level, (head1, spine1), (head2, spine2), &mut pairs) && conv_pairs(pairs)
+        }
+        _ => false,
+    }
+}
+
+/// Unfolds `value` if it is a neutral headed by a global.
+pub(crate) fn unfold_global<'a>(value: &Value<'a>) -> Option<ValueRef<'a>> {
+    unmetered(|fuel| unfold_global_with_fuel(value, fuel))
+}
+
+fn unfold_global_with_fuel<'a>(
+    value: &Value<'a>,
+    fuel: &Fuel,
+) -> Result<Option<ValueRef<'a>>, Interrupted> {
+    match value {
+        Value::Neutral {
+            head: Head::Global { unfolding, .. },
+            spine,
+        } => {
+            let spine = Spine::from_frames(spine.iter().cloned());
+            apply_spine_with_fuel(&force_with_fuel(unfolding, fuel)?, spine, fuel).map(Some)
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Compares the outermost layer of two values, pushing the pairs of
+/// subvalues that remain to be compared. Unsolved metas are compared like
+/// variables.
+pub(crate) fn conv_step<'a>(
+    level: Level,
+    value1: &Value<'a>,
+    value2: &Value<'a>,
+    pairs: &mut ConvPairs<'a>,
+) -> bool {
+    if let Some(value1) = force_meta(value1) {
+        return conv_step(level, &value1, value2, pairs);
+    }
+    if let Some(value2) = force_meta(value2) {
+        return conv_step(level, value1, &value2, pairs);
+    }
+    match (value1, value2) {
+        (Value::Thunk(thunk), _) => conv_step(level, &thunk.force(), value2, pairs),
+        (_, Value::Thunk(thunk)) => conv_step(level, value1, &thunk.force(), pairs),
+        _ if same_global(level, value1, value2) => true,
+        (Value::Neutral { .. }, _) | (_, Value::Neutral { .. })
+            if is_global(value1) || is_global(value2) =>
+        {
+            let unfolded1 = unfold_global(value1);
+            let unfolded2 = unfold_global(value2);
+            conv_step(
+                level,
+                unfolded1.as_deref().unwrap_or(value1),
+                unfolded2.as_deref().unwrap_or(value2),
+                pairs,
+            )
+        }
+        (
+            Value::Neutral {
+                head: head1,
+                spine: spine1,
+            },
+            Value::Neutral {
+                head: head2,
+                spine: spine2,
+            },
+        ) => conv_neutrals(level, (head1, spine1), (head2, spine2), pairs),
+        // Eta: a lambda is compared with a lambda or a neutral by applying
+        // both sides to a fresh variable.
+        (Value::Lambda(plicity, _), Value::Lambda(..) | Value::Neutral { .. })
+        | (Value::Neutral { .. }, Value::Lambda(plicity, _)) => {
+            let variable = Value::variable(level);
+            pairs.push((
+                level + 1,
+                apply_with(value1, *plicity, variable.clone()),
+                apply_with(value2, *plicity, variable),
+            ));
+            true
+        }
+        (Value::Pair(first1, second1), Value::Pair(first2, second2)) => {
+            pairs.push((level, second1.clone(), second2.clone()));
+            pairs.push((level, first1.clone(), first2.clone()));
+            true
+        }
+        // Eta: a pair is compared with a neutral by projecting both sides.
+        (Value::Pair(..), Value::Neutral { .. }) | (Value::Neutral { .. }, Value::Pair(..)) => {
+            pairs.push((
+                level,
+                project(value1, Frame::Second),
+                project(value2, Frame::Second),
+            ));
+            pairs.push((
+                level,
+                project(value1, Frame::First),
+                project(value2, Frame::First),
+            ));
+            true
+        }
+        (Value::Record(fields1), Value::Record(fields2)) => {
+            if !same_names(fields1, fields2) {
+                return false;
+            }
+            for ((_, field1), (_, field2)) in fields1.iter().zip(fields2).rev() {
+                pairs.push((level, field1.clone(), field2.clone()));
+            }
+            true
+        }
+        // Eta: a record is compared with a neutral by projecting each field
+        // out of both sides.
+        (Value::Record(fields), Value::Neutral { .. })
+        | (Value::Neutral { .. }, Value::Record(fields)) => {
+            for &(name, _) in fields.iter().rev() {
+                pairs.push((
+                    level,
+                    project_field(value1, name),
+                    project_field(value2, name),
+                ));
+            }
+            true
+        }
+        (Value::RecordType(fields1, environment1), Value::RecordType(fields2, environment2)) => {
+            if !same_names(fields1, fields2) {
+                return false;
+            }
+            let variable = |position, _| Value::variable(level + position);
+            let types1 = field_types(fields1, environment1, variable);
+            let types2 = field_types(fields2, environment2, variable);
+            for (position, (type1, type2)) in types1.into_iter().zip(types2).enumerate().rev() {
+                pairs.push((level + position, type1, type2));
+            }
+            true
+        }
+        (Value::Pi(plicity1, _, _), Value::Pi(plicity2, _, _)) if plicity1 != plicity2 => false,
+        (Value::Pi(_, domain1, codomain1), Value::Pi(_, domain2, codomain2))
+        | (Value::Sigma(domain1, codomain1), Value::Sigma(domain2, codomain2)) => {
+            let variable = Value::variable(level);
+            pairs.push((
+                level + 1,
+                codomain1.apply(variable.clone()),
+                codomain2.apply(variable),
+            ));
+            pairs.push((level, domain1.clone(), domain2.clone()));
+            true
+        }
+        (Value::Type, Value::Type) | (Value::Nat, Value::Nat) => true,
+        (Value::Natural(value1), Value::Natural(value2)) => value1 == value2,
+        (Value::IntegerType(ty1), Value::IntegerType(ty2)) => ty1 == ty2,
+        (Value::Integer(value1), Value::Integer(value2)) => value1 == value2,
+        (Value::Suc(predecessor1), Value::Suc(predecessor2)) => {
+            pairs.push((level, predecessor1.clone(), predecessor2.clone()));
+            true
+        }
+        // A successor whose predecessor turns out to be a literal, such as
+        // `suc n2` for a global `n2`, is compared with a literal by its
+        // predecessor.
+        (Value::Natural(value), Value::Suc(predecessor))
+        | (Value::Suc(predecessor), Value::Natural(value)) => {
+            if *value == 0 {
+                return false;
+            }
+            pairs.push((
+                level,
+                Rc::new(Value::Natural(value - 1)),
+                predecessor.clone(),
+            ));
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Whether two lists of fields have the same names in the same order.
+pub(crate) fn same_names<A, B>(fields1: &[(&str, A)], fields2: &[(&str, B)]) -> bool {
+    fields1.len() == fields2.len()
+        && (fields1.iter().zip(fields2)).all(|((name1, _), (name2, _))| name1 == name2)
+}
+
+/// The reference-counted backend. Values are freed as soon as the last
+/// reference to them goes away.
+pub struct Backend;
+
+impl Normalizer for Backend {
+    fn normalize<'out>(
+        &self,
+        term: TermRef<'_>,
+        syntax_builder: &'out syntax::Builder,
+    ) -> TermRef<'out> {
+        term.evaluate_rc(&mut Environment::new())
+            .quote(Level(0), syntax_builder)
+    }
+}
+
+/// The reference-counted backend under [`Strategy::CallByNeed`].
+pub struct LazyBackend;
+
+impl Normalizer for LazyBackend {
+    fn normalize<'out>(
+        &self,
+        term: TermRef<'_>,
+        syntax_builder: &'out syntax::Builder,
+    ) -> TermRef<'out> {
+        term.evaluate_rc(&mut Environment::new().with_strategy(Strategy::CallByNeed))
+            .quote(Level(0), syntax_builder)
+    }
+}
+
+/// The reference-counted backend with the definitions of a signature in scope.
+/// Globals are unfolded in the normal forms it produces.
+pub struct SignatureBackend<'s> {
+    pub signature: &'s Signature<'s>,
+}
+
+impl Normalizer for SignatureBackend<'_> {
+    fn normalize<'out>(
+        &self,
+        term: TermRef<'_>,
+        syntax_builder: &'out syntax::Builder,
+    ) -> TermRef<'out> {
+        term.evaluate_rc(&mut Environment::new().with_signature(self.signature))
+            .quote(Level(0), syntax_builder)
     }
 }
@@ -0,0 +1,159 @@
+use std::{
+    cell::Cell,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Instant,
+};
+
+/// A budget of steps for evaluation and readback, shared by everything that
+/// one run of them does. A beta step, that is the instantiation of a
+/// closure, and a readback step each use up one unit of fuel.
+///
+/// Evaluation only diverges by taking infinitely many beta steps, so any
+/// finite budget makes it return. A run can also be stopped from outside by
+/// a [`CancellationToken`] or a deadline, which are checked every
+/// [`CHECK_INTERVAL`] steps.
+#[derive(Debug)]
+pub struct Fuel {
+    remaining: Cell<u64>,
+    cancellation: Option<CancellationToken>,
+    deadline: Option<Instant>,
+    until_check: Cell<u32>,
+}
+
+/// The number of steps between two checks of the cancellation token and the
+/// deadline of a [`Fuel`].
+pub const CHECK_INTERVAL: u32 = 1024;
+
+/// Why evaluation or readback stopped before it finished.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Interrupted {
+    /// It used up its fuel.
+    OutOfFuel,
+    /// Its [`CancellationToken`] was cancelled.
+    Cancelled,
+    /// It was still running at its deadline.
+    TimedOut,
+}
+
+impl std::fmt::Display for Interrupted {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Interrupted::OutOfFuel => write!(f, "ran out of fuel"),
+            Interrupted::Cancelled => write!(f, "cancelled"),
+            Interrupted::TimedOut => write!(f, "timed out"),
+        }
+    }
+}
+
+impl std::error::Error for Interrupted {}
+
+/// A flag that stops the runs whose [`Fuel`] holds a clone of it once it is
+/// cancelled, from any thread.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+impl Fuel {
+    /// A budget of `steps` steps. A budget of `u64::MAX` steps never runs
+    /// out.
+    pub fn new(steps: u64) -> Self {
+        Fuel {
+            remaining: Cell::new(steps),
+            cancellation: None,
+            deadline: None,
+            until_check: Cell::new(0),
+        }
+    }
+
+    /// A budget that never runs out.
+    pub fn unlimited() -> Self {
+        Self::new(u64::MAX)
+    }
+
+    /// Stops the runs using this budget once `token` is cancelled.
+    pub fn with_cancellation(self, token: CancellationToken) -> Self {
+        Fuel {
+            cancellation: Some(token),
+            ..self
+        }
+    }
+
+    /// Stops the runs using this budget once `deadline` has passed.
+    pub fn with_deadline(self, deadline: Instant) -> Self {
+        Fuel {
+            deadline: Some(deadline),
+            ..self
+        }
+    }
+
+    /// The number of steps left.
+    pub fn remaining(&self) -> u64 {
+        self.remaining.get()
+    }
+
+    /// Whether a run using this budget could be interrupted, in which case it
+    /// has to leave shared state as it found it.
+    pub(crate) fn is_interruptible(&self) -> bool {
+        self.remaining.get() != u64::MAX || self.cancellation.is_some() || self.deadline.is_some()
+    }
+
+    /// Uses up one step.
+    #[inline]
+    pub(crate) fn consume(&self) -> Result<(), Interrupted> {
+        match self.remaining.get() {
+            0 => return Err(Interrupted::OutOfFuel),
+            u64::MAX => {}
+            remaining => self.remaining.set(remaining - 1),
+        }
+        if self.cancellation.is_none() && self.deadline.is_none() {
+            return Ok(());
+        }
+        match self.until_check.get() {
+            0 => {
+                self.until_check.set(CHECK_INTERVAL);
+                self.check()
+            }
+            until_check => {
+                self.until_check.set(until_check - 1);
+                Ok(())
+            }
+        }
+    }
+
+    fn check(&self) -> Result<(), Interrupted> {
+        if (self.cancellation.as_ref()).is_some_and(CancellationToken::is_cancelled) {
+            return Err(Interrupted::Cancelled);
+        }
+        if self
+            .deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            return Err(Interrupted::TimedOut);
+        }
+        Ok(())
+    }
+}
+
+/// Runs `run` with fuel that never runs out, for the entry points that are
+/// not metered.
+pub(crate) fn unmetered<T>(run: impl FnOnce(&Fuel) -> Result<T, Interrupted>) -> T {
+    run(&Fuel::unlimited()).expect("unlimited fuel ran out")
+}
@@ -1,6 +1,16 @@
+pub mod bohm_tree;
+pub mod check;
 pub mod domain;
 pub mod domain_rc;
+pub mod fuel;
 pub mod index;
+pub mod meta;
+pub mod normalizer;
+pub mod parse;
+pub mod pretty;
+pub mod primitive;
+pub mod reference;
+pub mod signature;
 pub mod syntax;
 
 use mimalloc::MiMalloc;
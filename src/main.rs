@@ -1,15 +1,17 @@
 use rixty::{
-    domain_rc,
-    index::{Index, Level},
+    domain, domain_rc,
+    index::Index,
+    normalizer::Normalizer,
     syntax::{self, TermRef},
 };
 
+#[allow(dead_code)]
 fn to_nat<'a>(term: TermRef<'a>) -> usize {
     match term {
-        syntax::Term::Lambda(syntax::Term::Lambda(body)) => {
+        syntax::Term::Lambda(_, _, syntax::Term::Lambda(_, _, body)) => {
             let mut result = 0;
             let mut body = *body;
-            while let syntax::Term::Application(syntax::Term::Variable(Index(1)), arg) = body {
+            while let syntax::Term::Application(_, syntax::Term::Variable(Index(1)), arg) = body {
                 result += 1;
                 body = arg;
             }
@@ -22,6 +24,12 @@ fn to_nat<'a>(term: TermRef<'a>) -> usize {
     }
 }
 
+fn normalize<N: Normalizer>(normalizer: &N, term: TermRef<'_>) {
+    let result_builder = syntax::Builder::new();
+    normalizer.normalize(term, &result_builder);
+}
+
+#[allow(non_snake_case, unused_variables)]
 fn main() {
     let b = syntax::Builder::new();
     let v0 = b.v(0);
@@ -62,8 +70,13 @@ fn main() {
     let tree_4M = b.application(full_tree, n21);
     let tree_8M = b.application(full_tree, n22);
 
-    let result_builder = syntax::Builder::new();
-    tree_2M
-        .evaluate_rc(&mut domain_rc::Environment::new())
-        .quote(Level(0), &result_builder);
+    match std::env::args().nth(1).as_deref() {
+        None | Some("rc") => normalize(&domain_rc::Backend, tree_2M),
+        Some("lazy") => normalize(&domain_rc::LazyBackend, tree_2M),
+        Some("arena") => normalize(&domain::Backend, tree_2M),
+        Some(backend) => {
+            eprintln!("Unknown backend {backend}, expected one of: rc, lazy, arena");
+            std::process::exit(1)
+        }
+    }
 }
@@ -0,0 +1,367 @@
+use crate::{
+    domain_rc::{self, ConvPairs, Environment, Frame, Head, QuoteOptions, Value, ValueRef},
+    index::{Index, Level},
+    signature::{Definition, Signature},
+    syntax::{self, Meta, Term, TermRef},
+};
+use rclite::Rc;
+use std::cell::{Cell, RefCell};
+
+/// The metavariables of a problem, and the solutions unification has found
+/// for them.
+///
+/// A solution is kept as a closed term, a lambda for each argument the meta
+/// was applied to when it was solved, rather than as a value: the values of
+/// metas refer back to the metacontext, so storing values here would make
+/// cycles of reference counts. Solutions are normal forms, so evaluating one
+/// again whenever it is needed is cheap.
+///
+/// Cloning a metacontext gives another handle to the same metas.
+#[derive(Clone)]
+pub struct MetaContext<'a> {
+    state: Rc<State<'a>>,
+}
+
+struct State<'a> {
+    solutions: RefCell<Vec<Option<TermRef<'a>>>>,
+    /// The definitions that globals in solutions refer to.
+    definitions: Cell<&'a [Definition<'a>]>,
+    /// Where solutions are built.
+    syntax_builder: &'a syntax::Builder,
+}
+
+/// Why two values could not be unified. Variables are identified by their
+/// levels.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UnifyError {
+    /// The values differ.
+    Mismatch,
+    /// A meta would have to be solved with a value that contains it.
+    Occurs { meta: Meta },
+    /// A meta would have to be solved with a value that mentions a variable
+    /// it is not applied to.
+    Scope { meta: Meta, variable: Level },
+    /// A meta is applied to something other than distinct variables, so the
+    /// problem is outside the pattern fragment and has no most general
+    /// solution.
+    NotPattern { meta: Meta },
+}
+
+impl std::fmt::Display for UnifyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UnifyError::Mismatch => write!(f, "the values differ"),
+            UnifyError::Occurs { meta } => write!(f, "{meta} occurs in its own solution"),
+            UnifyError::Scope { meta, variable } => write!(
+                f,
+                "the solution of {meta} would mention x{}, which it is not applied to",
+                variable.to_int()
+            ),
+            UnifyError::NotPattern { meta } => {
+                write!(f, "{meta} is not applied to distinct variables")
+            }
+        }
+    }
+}
+
+impl std::error::Error for UnifyError {}
+
+impl<'a> MetaContext<'a> {
+    /// An empty metacontext. Solutions are built in `syntax_builder`.
+    pub fn new(syntax_builder: &'a syntax::Builder) -> Self {
+        Self::with_definitions(&[], syntax_builder)
+    }
+
+    /// An empty metacontext whose solutions can refer to the definitions of
+    /// `signature`.
+    pub fn with_signature(
+        signature: &'a Signature<'a>,
+        syntax_builder: &'a syntax::Builder,
+    ) -> Self {
+        Self::with_definitions(signature.definitions(), syntax_builder)
+    }
+
+    fn with_definitions(
+        definitions: &'a [Definition<'a>],
+        syntax_builder: &'a syntax::Builder,
+    ) -> Self {
+        MetaContext {
+            state: Rc::new(State {
+                solutions: RefCell::new(Vec::new()),
+                definitions: Cell::new(definitions),
+                syntax_builder,
+            }),
+        }
+    }
+
+    /// Replaces the definitions that globals in solutions refer to, such as
+    /// with a signature that has grown since.
+    pub(crate) fn set_definitions(&self, definitions: &'a [Definition<'a>]) {
+        self.state.definitions.set(definitions)
+    }
+
+    /// Adds an unsolved meta.
+    pub fn fresh(&self) -> Meta {
+        let mut solutions = self.state.solutions.borrow_mut();
+        solutions.push(None);
+        Meta(solutions.len() - 1)
+    }
+
+    /// The metas that have not been solved yet, in the order they were
+    /// created.
+    pub fn unsolved(&self) -> Vec<Meta> {
+        let solutions = self.state.solutions.borrow();
+        (0..solutions.len())
+            .filter(|&meta| solutions[meta].is_none())
+            .map(Meta)
+            .collect()
+    }
+
+    /// The solutions of the metas as they stand, in the order the metas were
+    /// created.
+    pub(crate) fn solutions(&self) -> Vec<Option<TermRef<'a>>> {
+        self.state.solutions.borrow().clone()
+    }
+
+    /// The value of the solution of `meta`, if it has one.
+    pub fn solution(&self, meta: Meta) -> Option<ValueRef<'a>> {
+        let solution = self.state.solutions.borrow()[meta.0]?;
+        Some(solution.evaluate_rc(&mut self.environment()))
+    }
+
+    /// The value of `meta`: its solution, or a neutral if it has none yet.
+    pub fn value(&self, meta: Meta) -> ValueRef<'a> {
+        self.solution(meta).unwrap_or_else(|| {
+            Rc::new(Value::Neutral {
+                head: Head::Meta {
+                    meta,
+                    metas: self.clone(),
+                },
+                spine: Vec::new(),
+            })
+        })
+    }
+
+    /// An empty environment for evaluating solutions.
+    fn environment(&self) -> Environment<'a> {
+        Environment::new()
+            .with_definitions(self.state.definitions.get())
+            .with_metas(self)
+    }
+
+    /// Unifies two values at `level`, solving metas so that they become equal
+    /// up to beta and eta.
+    ///
+    /// A meta is only solved when it is applied to distinct variables, the
+    /// pattern fragment, where a solution is unique if there is one. The
+    /// solution abstracts over those variables, so the other side may mention
+    /// no other variables, nor the meta itself. Metas solved before a failure
+    /// stay solved.
+    pub fn unify(
+        &self,
+        level: Level,
+        value1: &Value<'a>,
+        value2: &Value<'a>,
+    ) -> Result<(), UnifyError> {
+        let mut pairs = Vec::new();
+        self.unify_step(level, value1, value2, &mut pairs)?;
+        while let Some((level, value1, value2)) = pairs.pop() {
+            self.unify_step(level, &value1, &value2, &mut pairs)?;
+        }
+        Ok(())
+    }
+
+    /// Unifies the outermost layer of two values, pushing the pairs of
+    /// subvalues that remain to be unified.
+    fn unify_step(
+        &self,
+        level: Level,
+        value1: &Value<'a>,
+        value2: &Value<'a>,
+        pairs: &mut ConvPairs<'a>,
+    ) -> Result<(), UnifyError> {
+        match (value1, value2) {
+            (Value::Thunk(thunk), _) => {
+                return self.unify_step(level, &thunk.force(), value2, pairs)
+            }
+            (_, Value::Thunk(thunk)) => {
+                return self.unify_step(level, value1, &thunk.force(), pairs)
+            }
+            _ => {}
+        }
+        if let Some(value1) = domain_rc::force_meta(value1) {
+            return self.unify_step(level, &value1, value2, pairs);
+        }
+        if let Some(value2) = domain_rc::force_meta(value2) {
+            return self.unify_step(level, value1, &value2, pairs);
+        }
+        match (flex(value1), flex(value2)) {
+            // The same meta on both sides: compare the spines, below.
+            (Some((meta1, _)), Some((meta2, _))) if meta1 == meta2 => {}
+            (Some((meta, spine)), flex2) => {
+                return match (self.solve(level, meta, spine, value2), flex2) {
+                    (Err(UnifyError::NotPattern { .. }), Some((meta2, spine2))) => {
+                        self.solve(level, meta2, spine2, value1)
+                    }
+                    (result, _) => result,
+                };
+            }
+            (None, Some((meta, spine))) => return self.solve(level, meta, spine, value1),
+            (None, None) => {}
+        }
+        if domain_rc::is_global(value1) || domain_rc::is_global(value2) {
+            if domain_rc::same_global(level, value1, value2) {
+                return Ok(());
+            }
+            let unfolded1 = domain_rc::unfold_global(value1);
+            let unfolded2 = domain_rc::unfold_global(value2);
+            return self.unify_step(
+                level,
+                unfolded1.as_deref().unwrap_or(value1),
+                unfolded2.as_deref().unwrap_or(value2),
+                pairs,
+            );
+        }
+        if domain_rc::conv_step(level, value1, value2, pairs) {
+            Ok(())
+        } else {
+            Err(UnifyError::Mismatch)
+        }
+    }
+
+    /// Solves `meta` applied to `spine` to be `rhs`, at `level`.
+    fn solve(
+        &self,
+        level: Level,
+        meta: Meta,
+        spine: &[Frame<'a>],
+        rhs: &Value<'a>,
+    ) -> Result<(), UnifyError> {
+        // The position of each variable in the spine, which must consist of
+        // distinct variables.
+        let mut positions = vec![None; level.to_int()];
+        for (position, frame) in spine.iter().enumerate() {
+            let variable = match frame {
+                Frame::Application(_, argument) => match &*domain_rc::unfold(argument) {
+                    Value::Neutral {
+                        head: Head::Variable(variable),
+                        spine,
+                    } if spine.is_empty() => Some(*variable),
+                    _ => None,
+                },
+                _ => None,
+            };
+            match variable {
+                Some(variable) if positions[variable.to_int()].is_none() => {
+                    positions[variable.to_int()] = Some(position)
+                }
+                _ => return Err(UnifyError::NotPattern { meta }),
+            }
+        }
+        let syntax_builder = self.state.syntax_builder;
+        let rhs = rhs.quote_with(
+            level,
+            syntax_builder,
+            QuoteOptions {
+                keep_globals: true,
+                ..QuoteOptions::default()
+            },
+        );
+        let renaming = Renaming {
+            meta,
+            level,
+            positions: &positions,
+            arity: spine.len(),
+            syntax_builder,
+        };
+        let body = renaming.rename(rhs)?;
+        let solution = spine
+            .iter()
+            .fold(body, |body, _| syntax_builder.lambda(body));
+        self.state.solutions.borrow_mut()[meta.0] = Some(solution);
+        Ok(())
+    }
+}
+
+/// The meta at the head of `value` and its spine, if `value` is a neutral
+/// headed by an unsolved meta.
+fn flex<'v, 'a>(value: &'v Value<'a>) -> Option<(Meta, &'v [Frame<'a>])> {
+    match value {
+        Value::Neutral {
+            head: Head::Meta { meta, .. },
+            spine,
+        } => Some((*meta, spine)),
+        _ => None,
+    }
+}
+
+/// Turns the right-hand side of a pattern problem, read back at `level`,
+/// into the body of its solution: a term under one binder for each argument
+/// of the meta, which are the only variables it may mention.
+struct Renaming<'r, 'a> {
+    meta: Meta,
+    level: Level,
+    /// For each variable below `level`, its position among the arguments of
+    /// the meta, if it is one of them.
+    positions: &'r [Option<usize>],
+    arity: usize,
+    syntax_builder: &'a syntax::Builder,
+}
+
+impl<'a> Renaming<'_, 'a> {
+    /// Renames `term`, the whole right-hand side.
+    ///
+    /// The pending work is kept on an explicit stack, so that solutions of
+    /// any depth can be renamed.
+    fn rename(&self, term: TermRef<'a>) -> Result<TermRef<'a>, UnifyError> {
+        let b = self.syntax_builder;
+        let mut tasks = vec![Rename::Enter(term, 0)];
+        let mut results = Vec::new();
+        while let Some(task) = tasks.pop() {
+            match task {
+                Rename::Enter(term, depth) => match term {
+                    Term::Variable(Index(index)) if *index < depth => results.push(term),
+                    Term::Variable(Index(index)) => {
+                        let variable = Level(self.level.to_int() + depth - index - 1);
+                        match self.positions[variable.to_int()] {
+                            Some(position) => results.push(b.v(depth + self.arity - position - 1)),
+                            None => {
+                                return Err(UnifyError::Scope {
+                                    meta: self.meta,
+                                    variable,
+                                })
+                            }
+                        }
+                    }
+                    Term::Meta(meta) if *meta == self.meta => {
+                        return Err(UnifyError::Occurs { meta: *meta })
+                    }
+                    _ => {
+                        let subterms = syntax::subterms(term);
+                        tasks.push(Rename::Rebuild(term, subterms.len()));
+                        tasks.extend(
+                            subterms
+                                .into_iter()
+                                .rev()
+                                .map(|(subterm, binders)| Rename::Enter(subterm, depth + binders)),
+                        );
+                    }
+                },
+                Rename::Rebuild(term, count) => {
+                    let subterms = results.split_off(results.len() - count);
+                    results.push(syntax::rebuild(term, &subterms, b));
+                }
+            }
+        }
+        Ok(results.pop().unwrap())
+    }
+}
+
+/// A unit of pending work for [`Renaming::rename`].
+enum Rename<'a> {
+    /// Rename a term under so many binders of the right-hand side, pushing
+    /// the result.
+    Enter(TermRef<'a>, usize),
+    /// Rebuild a term from its last so many subterms, as renamed.
+    Rebuild(TermRef<'a>, usize),
+}
@@ -0,0 +1,17 @@
+use crate::syntax::{self, TermRef};
+
+/// A strategy for computing the normal form of a closed term.
+///
+/// The term need not be well-typed: an elimination of the wrong kind of
+/// value, such as `Type Type`, is stuck and stays in the normal form as it is
+/// written.
+///
+/// Each backend implements this on a small handle type, so drivers such as the
+/// binary and the benchmarks can be written once and run against any of them.
+pub trait Normalizer {
+    fn normalize<'out>(
+        &self,
+        term: TermRef<'_>,
+        syntax_builder: &'out syntax::Builder,
+    ) -> TermRef<'out>;
+}
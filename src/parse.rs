@@ -0,0 +1,859 @@
+use crate::{
+    index::Index,
+    primitive::{Integer, IntegerType, Primitive},
+    signature::{ConstructorDeclaration, Datatype, Definition, Name, Signature},
+    syntax::{self, Branch, Plicity, TermRef},
+};
+
+/// Errors produced while parsing the surface syntax. Positions are byte
+/// offsets into the input.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    UnexpectedCharacter {
+        character: char,
+        position: usize,
+    },
+    UnexpectedToken {
+        found: String,
+        expected: &'static str,
+        position: usize,
+    },
+    UnexpectedEnd {
+        expected: &'static str,
+    },
+    UnboundVariable {
+        name: String,
+        position: usize,
+    },
+    LiteralOutOfRange {
+        literal: String,
+        position: usize,
+    },
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::UnexpectedCharacter {
+                character,
+                position,
+            } => write!(f, "unexpected character {character:?} at {position}"),
+            Error::UnexpectedToken {
+                found,
+                expected,
+                position,
+            } => write!(f, "expected {expected} at {position}, found {found}"),
+            Error::UnexpectedEnd { expected } => {
+                write!(f, "expected {expected}, found end of input")
+            }
+            Error::UnboundVariable { name, position } => {
+                write!(f, "unbound variable {name} at {position}")
+            }
+            Error::LiteralOutOfRange { literal, position } => {
+                write!(f, "literal {literal} at {position} is out of range")
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Token<'i> {
+    Identifier(&'i str),
+    /// A hole `?name`, without the `?`.
+    Hole(&'i str),
+    Natural(u64),
+    Integer(Integer),
+    IntegerType(IntegerType),
+    Primitive(Primitive),
+    Let,
+    In,
+    Type,
+    Fst,
+    Snd,
+    Nat,
+    Zero,
+    Suc,
+    NatElim,
+    Data,
+    Case,
+    Sig,
+    Struct,
+    Equals,
+    FatArrow,
+    Bar,
+    Lambda,
+    Dot,
+    Colon,
+    Comma,
+    Semicolon,
+    Arrow,
+    Star,
+    LeftParen,
+    RightParen,
+    LeftBrace,
+    RightBrace,
+}
+
+impl std::fmt::Display for Token<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Identifier(name) => write!(f, "{name}"),
+            Token::Hole(name) => write!(f, "?{name}"),
+            Token::Natural(value) => write!(f, "{value}"),
+            Token::Integer(value) => write!(f, "{value}"),
+            Token::IntegerType(ty) => write!(f, "{}", ty.name()),
+            Token::Primitive(primitive) => write!(f, "{primitive}"),
+            Token::Let => write!(f, "let"),
+            Token::In => write!(f, "in"),
+            Token::Type => write!(f, "Type"),
+            Token::Fst => write!(f, "fst"),
+            Token::Snd => write!(f, "snd"),
+            Token::Nat => write!(f, "Nat"),
+            Token::Zero => write!(f, "zero"),
+            Token::Suc => write!(f, "suc"),
+            Token::NatElim => write!(f, "natElim"),
+            Token::Data => write!(f, "data"),
+            Token::Case => write!(f, "case"),
+            Token::Sig => write!(f, "sig"),
+            Token::Struct => write!(f, "struct"),
+            Token::Equals => write!(f, "="),
+            Token::FatArrow => write!(f, "=>"),
+            Token::Bar => write!(f, "|"),
+            Token::Lambda => write!(f, "\\"),
+            Token::Dot => write!(f, "."),
+            Token::Colon => write!(f, ":"),
+            Token::Comma => write!(f, ","),
+            Token::Semicolon => write!(f, ";"),
+            Token::Arrow => write!(f, "->"),
+            Token::Star => write!(f, "*"),
+            Token::LeftParen => write!(f, "("),
+            Token::RightParen => write!(f, ")"),
+            Token::LeftBrace => write!(f, "{{"),
+            Token::RightBrace => write!(f, "}}"),
+        }
+    }
+}
+
+fn is_identifier_character(character: char) -> bool {
+    character.is_alphanumeric() || character == '_' || character == '\''
+}
+
+/// Lexes `word` as a literal: a natural number such as `42`, or a machine
+/// integer such as `42u64` or `-42i64`. Returns `None` if it is not one.
+fn literal<'i>(word: &'i str, position: usize) -> Option<Result<Token<'i>, Error>> {
+    let (digits, ty) = IntegerType::ALL
+        .into_iter()
+        .find_map(|ty| Some((word.strip_suffix(ty.suffix())?, Some(ty))))
+        .unwrap_or((word, None));
+    let magnitude = digits.strip_prefix('-').unwrap_or(digits);
+    if magnitude.is_empty() || !magnitude.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+    let token = match ty {
+        None => digits.parse().map(Token::Natural).ok(),
+        Some(IntegerType::I64) => digits
+            .parse()
+            .map(|value| Token::Integer(Integer::I64(value)))
+            .ok(),
+        Some(IntegerType::U64) => digits
+            .parse()
+            .map(|value| Token::Integer(Integer::U64(value)))
+            .ok(),
+    };
+    Some(token.ok_or_else(|| Error::LiteralOutOfRange {
+        literal: word.to_owned(),
+        position,
+    }))
+}
+
+fn lex(input: &str) -> Result<Vec<(Token<'_>, usize)>, Error> {
+    let mut tokens = Vec::new();
+    let mut characters = input.char_indices().peekable();
+    while let Some((position, character)) = characters.next() {
+        let token = match character {
+            _ if character.is_whitespace() => continue,
+            '\\' | 'λ' => Token::Lambda,
+            '.' => Token::Dot,
+            ':' => Token::Colon,
+            ',' => Token::Comma,
+            ';' => Token::Semicolon,
+            '*' => Token::Star,
+            '=' if characters.next_if(|&(_, next)| next == '>').is_some() => Token::FatArrow,
+            '=' => Token::Equals,
+            '|' => Token::Bar,
+            '-' if characters.next_if(|&(_, next)| next == '>').is_some() => Token::Arrow,
+            '(' => Token::LeftParen,
+            ')' => Token::RightParen,
+            '{' => Token::LeftBrace,
+            '}' => Token::RightBrace,
+            '?' => {
+                let start = position + 1;
+                let mut end = start;
+                while let Some(&(next_position, next)) = characters.peek() {
+                    if !is_identifier_character(next) {
+                        break;
+                    }
+                    end = next_position + next.len_utf8();
+                    characters.next();
+                }
+                if start == end {
+                    return Err(Error::UnexpectedCharacter {
+                        character,
+                        position,
+                    });
+                }
+                Token::Hole(&input[start..end])
+            }
+            _ if is_identifier_character(character)
+                || (character == '-'
+                    && characters
+                        .peek()
+                        .is_some_and(|&(_, next)| next.is_ascii_digit())) =>
+            {
+                let mut end = position + character.len_utf8();
+                while let Some(&(next_position, next)) = characters.peek() {
+                    if !is_identifier_character(next) {
+                        break;
+                    }
+                    end = next_position + next.len_utf8();
+                    characters.next();
+                }
+                match &input[position..end] {
+                    "let" => Token::Let,
+                    "in" => Token::In,
+                    "Type" => Token::Type,
+                    "fst" => Token::Fst,
+                    "snd" => Token::Snd,
+                    "Nat" => Token::Nat,
+                    "zero" => Token::Zero,
+                    "suc" => Token::Suc,
+                    "natElim" => Token::NatElim,
+                    "data" => Token::Data,
+                    "case" => Token::Case,
+                    "sig" => Token::Sig,
+                    "struct" => Token::Struct,
+                    "I64" => Token::IntegerType(IntegerType::I64),
+                    "U64" => Token::IntegerType(IntegerType::U64),
+                    word => match literal(word, position) {
+                        Some(token) => token?,
+                        None if character == '-' => {
+                            return Err(Error::UnexpectedCharacter {
+                                character,
+                                position,
+                            })
+                        }
+                        None => match Primitive::from_name(word) {
+                            Some(primitive) => Token::Primitive(primitive),
+                            None => Token::Identifier(word),
+                        },
+                    },
+                }
+            }
+            _ => {
+                return Err(Error::UnexpectedCharacter {
+                    character,
+                    position,
+                })
+            }
+        };
+        tokens.push((token, position));
+    }
+    Ok(tokens)
+}
+
+/// The binders of a group or lambda, outermost first, with their types if
+/// they are given.
+type Domains<'a> = Vec<(Plicity, Option<TermRef<'a>>)>;
+
+/// Builds a pair type; the groups binding it are checked to be explicit.
+fn sigma<'a>(
+    builder: &'a syntax::Builder,
+    _: Plicity,
+    domain: TermRef<'a>,
+    codomain: TermRef<'a>,
+) -> TermRef<'a> {
+    builder.sigma(domain, codomain)
+}
+
+struct Parser<'i, 'a> {
+    tokens: Vec<(Token<'i>, usize)>,
+    position: usize,
+    scope: Vec<&'i str>,
+    /// The globals that names not bound in `scope` can refer to.
+    signature: Signature<'a>,
+    builder: &'a syntax::Builder,
+}
+
+impl<'i, 'a> Parser<'i, 'a> {
+    fn peek(&self) -> Option<Token<'i>> {
+        self.tokens.get(self.position).map(|&(token, _)| token)
+    }
+
+    fn unexpected(&self, expected: &'static str) -> Error {
+        match self.tokens.get(self.position) {
+            Some((token, position)) => Error::UnexpectedToken {
+                found: token.to_string(),
+                expected,
+                position: *position,
+            },
+            None => Error::UnexpectedEnd { expected },
+        }
+    }
+
+    fn expect(&mut self, expected_token: Token<'i>, expected: &'static str) -> Result<(), Error> {
+        if self.peek() == Some(expected_token) {
+            self.position += 1;
+            Ok(())
+        } else {
+            Err(self.unexpected(expected))
+        }
+    }
+
+    fn identifier(&mut self) -> Result<(&'i str, usize), Error> {
+        match self.tokens.get(self.position) {
+            Some(&(Token::Identifier(name), position)) => {
+                self.position += 1;
+                Ok((name, position))
+            }
+            _ => Err(self.unexpected("an identifier")),
+        }
+    }
+
+    fn variable(&self, name: &str, position: usize) -> Result<TermRef<'a>, Error> {
+        if let Some(index) = self.scope.iter().rev().position(|&bound| bound == name) {
+            return Ok(self.builder.variable(Index(index)));
+        }
+        match self.signature.lookup(name) {
+            Some(Name::Global(global)) => Ok(self.builder.global(global.index, global.name)),
+            Some(Name::TypeConstructor(type_constructor)) => Ok(self
+                .builder
+                .type_constructor(type_constructor.index, type_constructor.name)),
+            Some(Name::Constructor(constructor)) => Ok(self.builder.constructor(constructor)),
+            None => Err(Error::UnboundVariable {
+                name: name.to_owned(),
+                position,
+            }),
+        }
+    }
+
+    /// definition ::= identifier [':' term] '=' term ';'
+    fn definition(&mut self) -> Result<Definition<'a>, Error> {
+        let (name, _) = self.identifier()?;
+        let ty = if self.peek() == Some(Token::Colon) {
+            self.position += 1;
+            Some(self.term()?)
+        } else {
+            None
+        };
+        self.expect(Token::Equals, "=")?;
+        let term = self.term()?;
+        self.expect(Token::Semicolon, ";")?;
+        Ok(Definition {
+            name: self.builder.copy_str(name),
+            ty,
+            term,
+        })
+    }
+
+    /// datatype ::= 'data' identifier group* '=' [constructor ('|' constructor)*] ';'
+    /// constructor ::= identifier atom*
+    ///
+    /// Declares the datatype before its constructors are parsed, so that
+    /// their fields can refer to it. The fields are in scope of the
+    /// parameters and of the fields before them, which cannot be named.
+    fn datatype(&mut self) -> Result<(), Error> {
+        self.expect(Token::Data, "data")?;
+        let (name, _) = self.identifier()?;
+        let scope_before = self.scope.len();
+        let mut parameters = Vec::new();
+        while self.peek() == Some(Token::LeftParen) {
+            self.group(&mut parameters)?;
+        }
+        self.expect(Token::Equals, "=")?;
+        let parameters: Vec<_> = parameters.into_iter().flat_map(|(_, ty)| ty).collect();
+        let type_constructor = self.signature.declare(Datatype {
+            name: self.builder.copy_str(name),
+            parameters: self.builder.copy_slice(&parameters),
+            constructors: &[],
+        });
+        let mut constructors = Vec::new();
+        while self.peek() != Some(Token::Semicolon) {
+            if !constructors.is_empty() {
+                self.expect(Token::Bar, "| or ;")?;
+            }
+            let (name, _) = self.identifier()?;
+            let mut fields = Vec::new();
+            while self.at_atom() {
+                fields.push(self.atom()?);
+                self.scope.push("");
+            }
+            self.scope.truncate(scope_before + parameters.len());
+            constructors.push(ConstructorDeclaration {
+                name: self.builder.copy_str(name),
+                fields: self.builder.copy_slice(&fields),
+            });
+        }
+        self.position += 1;
+        self.scope.truncate(scope_before);
+        let constructors = self.builder.copy_slice(&constructors);
+        self.signature
+            .set_constructors(type_constructor, constructors);
+        Ok(())
+    }
+
+    /// term ::= '\' binder+ '.' term | 'let' identifier '=' term 'in' term
+    ///        | group+ '->' term | product ['->' term]
+    ///
+    /// Only explicit groups can bind the components of a product.
+    fn term(&mut self) -> Result<TermRef<'a>, Error> {
+        match self.peek() {
+            Some(Token::Lambda) => return self.lambda(),
+            Some(Token::Let) => return self.let_in(),
+            _ => {}
+        }
+        let domain = if self.at_group() {
+            let scope_before = self.scope.len();
+            let domains = self.groups()?;
+            if self.peek() == Some(Token::Arrow) {
+                self.position += 1;
+                let codomain = self.term()?;
+                return Ok(self.close(scope_before, domains, codomain, syntax::Builder::pi_with));
+            }
+            if Self::any_implicit(&domains) {
+                return Err(self.unexpected("->"));
+            }
+            self.expect(Token::Star, "-> or *")?;
+            let codomain = self.product()?;
+            self.close(scope_before, domains, codomain, sigma)
+        } else {
+            self.product()?
+        };
+        if self.peek() != Some(Token::Arrow) {
+            return Ok(domain);
+        }
+        self.position += 1;
+        // The codomain of an arrow binds a variable that cannot be named.
+        self.scope.push("");
+        let codomain = self.term()?;
+        self.scope.pop();
+        Ok(self.builder.pi(domain, codomain))
+    }
+
+    /// product ::= group+ '*' product | spine ['*' product]
+    fn product(&mut self) -> Result<TermRef<'a>, Error> {
+        if self.at_group() {
+            let scope_before = self.scope.len();
+            let domains = self.groups()?;
+            if Self::any_implicit(&domains) {
+                return Err(self.unexpected("->"));
+            }
+            self.expect(Token::Star, "*")?;
+            let codomain = self.product()?;
+            return Ok(self.close(scope_before, domains, codomain, sigma));
+        }
+        let domain = self.spine()?;
+        if self.peek() != Some(Token::Star) {
+            return Ok(domain);
+        }
+        self.position += 1;
+        self.scope.push("");
+        let codomain = self.product()?;
+        self.scope.pop();
+        Ok(self.builder.sigma(domain, codomain))
+    }
+
+    /// spine ::= (atom | ('fst' | 'snd' | 'suc') atom | 'natElim' atom atom atom atom
+    ///            | 'case' atom branches)
+    ///           (atom | '{' term '}')* ['\' ...]
+    fn spine(&mut self) -> Result<TermRef<'a>, Error> {
+        let keyword = self.peek();
+        if matches!(
+            keyword,
+            Some(Token::Fst | Token::Snd | Token::Suc | Token::NatElim | Token::Case)
+        ) {
+            self.position += 1;
+        }
+        let mut result = match keyword {
+            Some(Token::Fst) => self.builder.fst(self.atom()?),
+            Some(Token::Snd) => self.builder.snd(self.atom()?),
+            Some(Token::Suc) => self.builder.suc(self.atom()?),
+            Some(Token::NatElim) => {
+                let motive = self.atom()?;
+                let zero = self.atom()?;
+                let suc = self.atom()?;
+                let scrutinee = self.atom()?;
+                self.builder.nat_elim(motive, zero, suc, scrutinee)
+            }
+            Some(Token::Case) => {
+                let scrutinee = self.atom()?;
+                let branches = self.branches()?;
+                self.builder.case(scrutinee, &branches)
+            }
+            _ => self.atom()?,
+        };
+        loop {
+            match self.peek() {
+                _ if self.at_atom() => {
+                    let argument = self.atom()?;
+                    result = self.builder.application(result, argument);
+                }
+                Some(Token::LeftBrace) => {
+                    self.position += 1;
+                    let argument = self.term()?;
+                    self.expect(Token::RightBrace, "}")?;
+                    result = self
+                        .builder
+                        .application_with(Plicity::Implicit, result, argument);
+                }
+                Some(Token::Lambda) => {
+                    let argument = self.lambda()?;
+                    return Ok(self.builder.application(result, argument));
+                }
+                _ => return Ok(result),
+            }
+        }
+    }
+
+    /// branches ::= '{' [branch (';' branch)*] '}'
+    /// branch ::= identifier identifier* '=>' term
+    ///
+    /// The first identifier of a branch names a constructor, and the others
+    /// bind its fields.
+    fn branches(&mut self) -> Result<Vec<Branch<'a>>, Error> {
+        self.expect(Token::LeftBrace, "{")?;
+        let mut branches = Vec::new();
+        while self.peek() != Some(Token::RightBrace) {
+            if !branches.is_empty() {
+                self.expect(Token::Semicolon, "; or }")?;
+            }
+            let (name, position) = self.identifier()?;
+            let Some(Name::Constructor(constructor)) = self.signature.lookup(name) else {
+                return Err(Error::UnexpectedToken {
+                    found: name.to_owned(),
+                    expected: "a constructor",
+                    position,
+                });
+            };
+            let scope_before = self.scope.len();
+            while let Some(Token::Identifier(name)) = self.peek() {
+                self.position += 1;
+                self.scope.push(name);
+            }
+            let arity = self.scope.len() - scope_before;
+            self.expect(Token::FatArrow, "=>")?;
+            let body = self.term()?;
+            self.scope.truncate(scope_before);
+            branches.push(self.builder.branch(constructor, arity, body));
+        }
+        self.position += 1;
+        Ok(branches)
+    }
+
+    /// Whether the next token starts an atom.
+    fn at_atom(&self) -> bool {
+        matches!(
+            self.peek(),
+            Some(
+                Token::Identifier(_)
+                    | Token::Hole(_)
+                    | Token::Natural(_)
+                    | Token::Integer(_)
+                    | Token::IntegerType(_)
+                    | Token::Primitive(_)
+                    | Token::Type
+                    | Token::Nat
+                    | Token::Zero
+                    | Token::Sig
+                    | Token::Struct
+                    | Token::LeftParen
+            )
+        )
+    }
+
+    /// Whether the next tokens start a group of binders `(x y : A)` or
+    /// `{x y : A}`.
+    fn at_group(&self) -> bool {
+        let mut tokens = self.tokens[self.position..].iter().map(|&(token, _)| token);
+        if !matches!(tokens.next(), Some(Token::LeftParen | Token::LeftBrace)) {
+            return false;
+        }
+        let mut names = 0;
+        for token in tokens {
+            match token {
+                Token::Identifier(_) => names += 1,
+                Token::Colon => return names > 0,
+                _ => return false,
+            }
+        }
+        false
+    }
+
+    /// group ::= '(' identifier+ ':' term ')' | '{' identifier+ ':' term '}'
+    ///
+    /// Binds each name in turn, pushing its type onto `domains`. The type is
+    /// parsed again for every name, since each one is in scope of the names
+    /// before it. Braces bind implicit arguments.
+    fn group(&mut self, domains: &mut Domains<'a>) -> Result<(), Error> {
+        let (plicity, closing, expected) = match self.peek() {
+            Some(Token::LeftBrace) => (Plicity::Implicit, Token::RightBrace, "}"),
+            _ => (Plicity::Explicit, Token::RightParen, ")"),
+        };
+        if plicity == Plicity::Explicit {
+            self.expect(Token::LeftParen, "(")?;
+        } else {
+            self.position += 1;
+        }
+        let mut names = Vec::new();
+        while let Some(Token::Identifier(_)) = self.peek() {
+            names.push(self.identifier()?.0);
+        }
+        self.expect(Token::Colon, ":")?;
+        let start = self.position;
+        for name in names {
+            self.position = start;
+            domains.push((plicity, Some(self.term()?)));
+            self.scope.push(name);
+        }
+        self.expect(closing, expected)
+    }
+
+    fn any_implicit(domains: &Domains<'a>) -> bool {
+        domains
+            .iter()
+            .any(|&(plicity, _)| plicity == Plicity::Implicit)
+    }
+
+    fn groups(&mut self) -> Result<Domains<'a>, Error> {
+        let mut domains = Vec::new();
+        while self.at_group() {
+            self.group(&mut domains)?;
+        }
+        Ok(domains)
+    }
+
+    /// Wraps `codomain` in a `binder` for each of `domains`, innermost last,
+    /// and takes their names out of scope.
+    fn close(
+        &mut self,
+        scope_before: usize,
+        domains: Domains<'a>,
+        mut codomain: TermRef<'a>,
+        binder: fn(&'a syntax::Builder, Plicity, TermRef<'a>, TermRef<'a>) -> TermRef<'a>,
+    ) -> TermRef<'a> {
+        for (plicity, domain) in domains.into_iter().rev() {
+            if let Some(domain) = domain {
+                codomain = binder(self.builder, plicity, domain, codomain);
+            }
+        }
+        self.scope.truncate(scope_before);
+        codomain
+    }
+
+    /// binder ::= identifier | '{' identifier+ '}' | group
+    fn lambda(&mut self) -> Result<TermRef<'a>, Error> {
+        self.expect(Token::Lambda, "a lambda")?;
+        let scope_before = self.scope.len();
+        let mut domains = Vec::new();
+        loop {
+            if self.peek() == Some(Token::LeftParen) || self.at_group() {
+                self.group(&mut domains)?;
+            } else if self.peek() == Some(Token::LeftBrace) {
+                self.position += 1;
+                loop {
+                    let (name, _) = self.identifier()?;
+                    self.scope.push(name);
+                    domains.push((Plicity::Implicit, None));
+                    if self.peek() == Some(Token::RightBrace) {
+                        break;
+                    }
+                }
+                self.position += 1;
+            } else {
+                let (name, _) = self.identifier()?;
+                self.scope.push(name);
+                domains.push((Plicity::Explicit, None));
+            }
+            if self.peek() == Some(Token::Dot) {
+                break;
+            }
+        }
+        self.expect(Token::Dot, ".")?;
+        let mut result = self.term()?;
+        for (plicity, domain) in domains.into_iter().rev() {
+            result = self.builder.lambda_with(plicity, domain, result);
+        }
+        self.scope.truncate(scope_before);
+        Ok(result)
+    }
+
+    fn let_in(&mut self) -> Result<TermRef<'a>, Error> {
+        self.expect(Token::Let, "let")?;
+        let (name, _) = self.identifier()?;
+        self.expect(Token::Equals, "=")?;
+        let value = self.term()?;
+        self.expect(Token::In, "in")?;
+        self.scope.push(name);
+        let body = self.term()?;
+        self.scope.pop();
+        Ok(self.builder.let_in(value, body))
+    }
+
+    /// atom ::= primary ('.' identifier)*
+    fn atom(&mut self) -> Result<TermRef<'a>, Error> {
+        let mut result = self.primary()?;
+        while self.peek() == Some(Token::Dot) {
+            self.position += 1;
+            let (name, _) = self.identifier()?;
+            result = self.builder.project(result, name);
+        }
+        Ok(result)
+    }
+
+    /// fields ::= '{' [field (';' field)*] '}'
+    ///
+    /// Parses the fields of a record type, `x : A`, when `separator` is a
+    /// colon, and of a record, `x = a`, when it is an equals sign. The fields
+    /// of a record type are in scope of the types after them.
+    fn fields(
+        &mut self,
+        separator: Token<'i>,
+        expected: &'static str,
+    ) -> Result<Vec<(&'i str, TermRef<'a>)>, Error> {
+        self.expect(Token::LeftBrace, "{")?;
+        let scope_before = self.scope.len();
+        let mut fields = Vec::new();
+        while self.peek() != Some(Token::RightBrace) {
+            if !fields.is_empty() {
+                self.expect(Token::Semicolon, "; or }")?;
+            }
+            let (name, _) = self.identifier()?;
+            self.expect(separator, expected)?;
+            fields.push((name, self.term()?));
+            if separator == Token::Colon {
+                self.scope.push(name);
+            }
+        }
+        self.position += 1;
+        self.scope.truncate(scope_before);
+        Ok(fields)
+    }
+
+    /// primary ::= identifier | '?' identifier | literal | primitive | 'Type' | 'Nat' | 'I64'
+    ///           | 'U64' | 'zero' | 'sig' fields | 'struct' fields | '(' term [',' term] ')'
+    fn primary(&mut self) -> Result<TermRef<'a>, Error> {
+        let constant = match self.peek() {
+            Some(Token::Hole(name)) => Some(self.builder.hole(name, &self.scope)),
+            Some(Token::Natural(value)) => Some(self.builder.natural(value)),
+            Some(Token::Integer(value)) => Some(self.builder.integer(value)),
+            Some(Token::IntegerType(ty)) => Some(self.builder.integer_type(ty)),
+            Some(Token::Primitive(primitive)) => Some(self.builder.primitive(primitive)),
+            Some(Token::Type) => Some(self.builder.universe()),
+            Some(Token::Nat) => Some(self.builder.nat()),
+            Some(Token::Zero) => Some(self.builder.zero()),
+            _ => None,
+        };
+        if let Some(constant) = constant {
+            self.position += 1;
+            return Ok(constant);
+        }
+        match self.peek() {
+            Some(Token::Identifier(_)) => {
+                let (name, position) = self.identifier()?;
+                self.variable(name, position)
+            }
+            Some(Token::LeftParen) => {
+                self.position += 1;
+                let mut result = self.term()?;
+                if self.peek() == Some(Token::Comma) {
+                    self.position += 1;
+                    result = self.builder.pair(result, self.term()?);
+                }
+                self.expect(Token::RightParen, ")")?;
+                Ok(result)
+            }
+            Some(Token::Sig) => {
+                self.position += 1;
+                let fields = self.fields(Token::Colon, ":")?;
+                Ok(self.builder.record_type(&fields))
+            }
+            Some(Token::Struct) => {
+                self.position += 1;
+                let fields = self.fields(Token::Equals, "=")?;
+                Ok(self.builder.record(&fields))
+            }
+            _ => Err(self.unexpected("a term")),
+        }
+    }
+}
+
+/// Parses a closed term such as `\f x. f (f x)`.
+pub fn parse<'a>(input: &str, builder: &'a syntax::Builder) -> Result<TermRef<'a>, Error> {
+    parse_open(input, &[], builder)
+}
+
+/// Parses a term whose free variables are `context`, listed from the
+/// outermost binder to the innermost, so the last name gets `Index(0)`.
+pub fn parse_open<'a>(
+    input: &str,
+    context: &[&str],
+    builder: &'a syntax::Builder,
+) -> Result<TermRef<'a>, Error> {
+    parse_term(input, context, Signature::new(), builder)
+}
+
+/// Parses a closed term that can refer to the definitions of `signature` by
+/// name. Local binders shadow globals.
+pub fn parse_in<'a>(
+    input: &str,
+    signature: &Signature<'a>,
+    builder: &'a syntax::Builder,
+) -> Result<TermRef<'a>, Error> {
+    parse_term(input, &[], signature.clone(), builder)
+}
+
+fn parse_term<'a>(
+    input: &str,
+    context: &[&str],
+    signature: Signature<'a>,
+    builder: &'a syntax::Builder,
+) -> Result<TermRef<'a>, Error> {
+    let mut parser = Parser {
+        tokens: lex(input)?,
+        position: 0,
+        scope: context.to_vec(),
+        signature,
+        builder,
+    };
+    let result = parser.term()?;
+    if parser.position < parser.tokens.len() {
+        return Err(parser.unexpected("end of input"));
+    }
+    Ok(result)
+}
+
+/// Parses a sequence of top-level definitions, `name = term;` or
+/// `name : type = term;`, and datatypes, `data name (x : A) = c B | d;`,
+/// each of which can refer to the ones before it.
+pub fn parse_signature<'a>(
+    input: &str,
+    builder: &'a syntax::Builder,
+) -> Result<Signature<'a>, Error> {
+    let mut parser = Parser {
+        tokens: lex(input)?,
+        position: 0,
+        scope: Vec::new(),
+        signature: Signature::new(),
+        builder,
+    };
+    while parser.position < parser.tokens.len() {
+        if parser.peek() == Some(Token::Data) {
+            parser.datatype()?;
+        } else {
+            let definition = parser.definition()?;
+            parser.signature.define(definition);
+        }
+    }
+    Ok(parser.signature)
+}
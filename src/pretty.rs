@@ -0,0 +1,464 @@
+use crate::{
+    index::Index,
+    syntax::{Branch, Plicity, Term, TermRef},
+};
+
+/// The line width used when none is given, e.g. by `format!("{term}")`.
+pub const DEFAULT_WIDTH: usize = 80;
+
+/// A document in the style of Wadler's "A prettier printer": a `Line` is laid
+/// out as a space if its enclosing `Group` fits on the line, and as a newline
+/// followed by the current indentation otherwise.
+enum Doc {
+    Text(String),
+    Line,
+    Concat(Vec<Doc>),
+    Nest(usize, Box<Doc>),
+    Group(Box<Doc>),
+}
+
+impl Doc {
+    fn text(text: impl Into<String>) -> Self {
+        Doc::Text(text.into())
+    }
+
+    fn nest(indent: usize, doc: Doc) -> Self {
+        Doc::Nest(indent, Box::new(doc))
+    }
+
+    fn group(doc: Doc) -> Self {
+        Doc::Group(Box::new(doc))
+    }
+
+    fn parens(doc: Doc) -> Self {
+        Doc::Concat(vec![Doc::text("("), doc, Doc::text(")")])
+    }
+
+    fn braces(doc: Doc) -> Self {
+        Doc::Concat(vec![Doc::text("{"), doc, Doc::text("}")])
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Flat,
+    Break,
+}
+
+/// Whether the documents on `stack` fit in `remaining` columns before the
+/// next line break. `rest` holds the commands that follow them.
+fn fits<'d>(
+    mut remaining: usize,
+    mut stack: Vec<(usize, Mode, &'d Doc)>,
+    rest: &[(usize, Mode, &'d Doc)],
+) -> bool {
+    let mut rest = rest.iter().rev();
+    loop {
+        let (indent, mode, doc) = match stack.pop() {
+            Some(command) => command,
+            None => match rest.next() {
+                Some(&command) => command,
+                None => return true,
+            },
+        };
+        match doc {
+            Doc::Text(text) => match remaining.checked_sub(text.chars().count()) {
+                Some(new_remaining) => remaining = new_remaining,
+                None => return false,
+            },
+            Doc::Line => match mode {
+                Mode::Flat => match remaining.checked_sub(1) {
+                    Some(new_remaining) => remaining = new_remaining,
+                    None => return false,
+                },
+                Mode::Break => return true,
+            },
+            Doc::Concat(docs) => stack.extend(docs.iter().rev().map(|doc| (indent, mode, doc))),
+            Doc::Nest(extra, doc) => stack.push((indent + extra, mode, doc)),
+            Doc::Group(doc) => stack.push((indent, mode, doc)),
+        }
+    }
+}
+
+fn layout(doc: &Doc, width: usize) -> String {
+    let mut output = String::new();
+    let mut column = 0;
+    let mut stack = vec![(0, Mode::Break, doc)];
+    while let Some((indent, mode, doc)) = stack.pop() {
+        match doc {
+            Doc::Text(text) => {
+                output.push_str(text);
+                column += text.chars().count();
+            }
+            Doc::Line => match mode {
+                Mode::Flat => {
+                    output.push(' ');
+                    column += 1;
+                }
+                Mode::Break => {
+                    output.push('\n');
+                    output.extend(std::iter::repeat_n(' ', indent));
+                    column = indent;
+                }
+            },
+            Doc::Concat(docs) => stack.extend(docs.iter().rev().map(|doc| (indent, mode, doc))),
+            Doc::Nest(extra, doc) => stack.push((indent + extra, mode, doc)),
+            Doc::Group(doc) => {
+                let flat = vec![(indent, Mode::Flat, &**doc)];
+                let mode = if fits(width.saturating_sub(column), flat, &stack) {
+                    Mode::Flat
+                } else {
+                    Mode::Break
+                };
+                stack.push((indent, mode, doc));
+            }
+        }
+    }
+    output
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Precedence {
+    Lambda,
+    Product,
+    Application,
+    Atom,
+}
+
+/// Turns terms into documents, inventing a name for each binder.
+struct Printer {
+    scope: Vec<String>,
+}
+
+/// The `n`th name in the sequence `a`, ..., `z`, `a1`, ..., `z1`, `a2`, ....
+fn candidate_name(n: usize) -> String {
+    let letter = char::from(b'a' + (n % 26) as u8);
+    match n / 26 {
+        0 => letter.to_string(),
+        suffix => format!("{letter}{suffix}"),
+    }
+}
+
+impl Printer {
+    /// The first candidate name that is not in scope, which also keeps
+    /// generated names apart from the names of record fields.
+    fn fresh_name(&self) -> String {
+        (0..)
+            .map(candidate_name)
+            .find(|name| !self.scope.contains(name))
+            .unwrap()
+    }
+
+    fn variable(&self, Index(index): Index) -> String {
+        match self.scope.len().checked_sub(index + 1) {
+            Some(level) => self.scope[level].clone(),
+            None => format!("#{}", index - self.scope.len()),
+        }
+    }
+
+    fn term(&mut self, term: TermRef<'_>, precedence: Precedence) -> Doc {
+        let doc = match term {
+            Term::Variable(index) => return Doc::text(self.variable(*index)),
+            Term::Lambda(..) if precedence > Precedence::Lambda => {
+                return Doc::parens(self.term(term, Precedence::Lambda))
+            }
+            Term::Lambda(..) => self.lambda(term),
+            Term::Application(..) if precedence > Precedence::Application => {
+                return Doc::parens(self.term(term, Precedence::Lambda))
+            }
+            Term::Application(..) => self.application(term),
+            Term::Let(..) if precedence > Precedence::Lambda => {
+                return Doc::parens(self.term(term, Precedence::Lambda))
+            }
+            Term::Let(value, body) => return self.let_in(value, body),
+            Term::Pi(..) if precedence > Precedence::Lambda => {
+                return Doc::parens(self.term(term, Precedence::Lambda))
+            }
+            Term::Pi(plicity, domain, codomain) => self.binder(
+                *plicity,
+                domain,
+                codomain,
+                " ->",
+                Precedence::Product,
+                Precedence::Lambda,
+            ),
+            Term::Sigma(..) if precedence > Precedence::Product => {
+                return Doc::parens(self.term(term, Precedence::Lambda))
+            }
+            Term::Sigma(domain, codomain) => self.binder(
+                Plicity::Explicit,
+                domain,
+                codomain,
+                " *",
+                Precedence::Application,
+                Precedence::Product,
+            ),
+            Term::Pair(first, second) => Doc::parens(Doc::Concat(vec![
+                self.term(first, Precedence::Lambda),
+                Doc::text(","),
+                Doc::Line,
+                self.term(second, Precedence::Lambda),
+            ])),
+            Term::Fst(_) | Term::Snd(_) | Term::Suc(_) | Term::NatElim(..) | Term::Case(..)
+                if precedence > Precedence::Application =>
+            {
+                return Doc::parens(self.term(term, Precedence::Lambda))
+            }
+            Term::Fst(pair) => self.keyword("fst", &[pair]),
+            Term::Snd(pair) => self.keyword("snd", &[pair]),
+            Term::Suc(predecessor) => self.keyword("suc", &[predecessor]),
+            Term::NatElim(motive, zero, suc, scrutinee) => {
+                self.keyword("natElim", &[motive, zero, suc, scrutinee])
+            }
+            Term::Case(scrutinee, branches) => self.case(scrutinee, branches),
+            Term::RecordType(fields) => self.record_type(fields),
+            Term::Record(fields) => self.record(fields),
+            Term::Project(record, name) => {
+                let record = self.term(record, Precedence::Atom);
+                return Doc::Concat(vec![record, Doc::text(format!(".{name}"))]);
+            }
+            Term::Type => return Doc::text("Type"),
+            Term::Nat => return Doc::text("Nat"),
+            Term::Zero => return Doc::text("zero"),
+            Term::Natural(value) => return Doc::text(value.to_string()),
+            Term::IntegerType(ty) => return Doc::text(ty.name()),
+            Term::Integer(value) => return Doc::text(value.to_string()),
+            Term::Primitive(primitive) => return Doc::text(primitive.to_string()),
+            Term::Global(global) => return Doc::text(global.name),
+            Term::Meta(meta) => return Doc::text(meta.to_string()),
+            Term::Hole(hole) => return Doc::text(format!("?{}", hole.name)),
+            Term::TypeConstructor(type_constructor) => return Doc::text(type_constructor.name),
+            Term::Constructor(constructor) => return Doc::text(constructor.name),
+        };
+        Doc::group(doc)
+    }
+
+    fn lambda(&mut self, mut term: TermRef<'_>) -> Doc {
+        let scope_before = self.scope.len();
+        let mut binders = vec![Doc::text("\\")];
+        while let Term::Lambda(plicity, domain, body) = term {
+            if binders.len() > 1 {
+                binders.push(Doc::text(" "));
+            }
+            let name = self.fresh_name();
+            binders.push(match (plicity, domain) {
+                (Plicity::Explicit, Some(domain)) => Doc::parens(Doc::Concat(vec![
+                    Doc::text(format!("{name} : ")),
+                    self.term(domain, Precedence::Lambda),
+                ])),
+                (Plicity::Implicit, Some(domain)) => Doc::braces(Doc::Concat(vec![
+                    Doc::text(format!("{name} : ")),
+                    self.term(domain, Precedence::Lambda),
+                ])),
+                (Plicity::Explicit, None) => Doc::text(name.clone()),
+                (Plicity::Implicit, None) => Doc::text(format!("{{{name}}}")),
+            });
+            self.scope.push(name);
+            term = body;
+        }
+        binders.push(Doc::text("."));
+        let body = self.term(term, Precedence::Lambda);
+        self.scope.truncate(scope_before);
+        Doc::Concat(vec![
+            Doc::Concat(binders),
+            Doc::nest(2, Doc::Concat(vec![Doc::Line, body])),
+        ])
+    }
+
+    /// Lets are laid out one per line when they do not all fit, with the
+    /// body at the same indentation as the bindings.
+    fn let_in(&mut self, value: TermRef<'_>, body: TermRef<'_>) -> Doc {
+        let value = self.term(value, Precedence::Lambda);
+        let name = self.fresh_name();
+        self.scope.push(name.clone());
+        let body = self.term(body, Precedence::Lambda);
+        self.scope.pop();
+        Doc::group(Doc::Concat(vec![
+            Doc::group(Doc::Concat(vec![
+                Doc::text(format!("let {name} =")),
+                Doc::nest(2, Doc::Concat(vec![Doc::Line, value])),
+                Doc::Line,
+                Doc::text("in"),
+            ])),
+            Doc::Line,
+            body,
+        ]))
+    }
+
+    /// Function and pair types whose codomain does not mention the bound
+    /// variable are printed as `A -> B` and `A * B`, and dependent ones name
+    /// it as in `(a : A) -> B`. Implicit function types always name it, as in
+    /// `{a : A} -> B`.
+    fn binder(
+        &mut self,
+        plicity: Plicity,
+        domain: TermRef<'_>,
+        codomain: TermRef<'_>,
+        separator: &str,
+        domain_precedence: Precedence,
+        codomain_precedence: Precedence,
+    ) -> Doc {
+        let domain = if plicity == Plicity::Implicit || codomain.occurs(Index(0)) {
+            let name = self.fresh_name();
+            let domain = Doc::Concat(vec![
+                Doc::text(format!("{name} : ")),
+                self.term(domain, Precedence::Lambda),
+            ]);
+            self.scope.push(name);
+            match plicity {
+                Plicity::Explicit => Doc::parens(domain),
+                Plicity::Implicit => Doc::braces(domain),
+            }
+        } else {
+            let domain = self.term(domain, domain_precedence);
+            self.scope.push(self.fresh_name());
+            domain
+        };
+        let codomain = self.term(codomain, codomain_precedence);
+        self.scope.pop();
+        Doc::Concat(vec![domain, Doc::text(separator), Doc::Line, codomain])
+    }
+
+    /// A built-in form such as `fst p`, whose arguments are atoms.
+    fn keyword(&mut self, keyword: &str, arguments: &[TermRef<'_>]) -> Doc {
+        let mut docs = Vec::new();
+        for argument in arguments {
+            docs.push(Doc::Line);
+            docs.push(self.term(argument, Precedence::Atom));
+        }
+        Doc::Concat(vec![Doc::text(keyword), Doc::nest(2, Doc::Concat(docs))])
+    }
+
+    /// Case analyses are laid out with one branch per line when they do not
+    /// fit on one, as in `case a { nil => b; cons c d => e }`.
+    fn case(&mut self, scrutinee: TermRef<'_>, branches: &[Branch<'_>]) -> Doc {
+        let scrutinee = self.term(scrutinee, Precedence::Atom);
+        if branches.is_empty() {
+            return Doc::Concat(vec![Doc::text("case "), scrutinee, Doc::text(" {}")]);
+        }
+        let mut docs = Vec::new();
+        for branch in branches {
+            if !docs.is_empty() {
+                docs.push(Doc::text(";"));
+            }
+            let scope_before = self.scope.len();
+            let mut pattern = branch.constructor.name.to_owned();
+            for _ in 0..branch.arity {
+                let name = self.fresh_name();
+                pattern.push(' ');
+                pattern.push_str(&name);
+                self.scope.push(name);
+            }
+            let body = self.term(branch.body, Precedence::Lambda);
+            self.scope.truncate(scope_before);
+            docs.push(Doc::Line);
+            docs.push(Doc::group(Doc::Concat(vec![
+                Doc::text(format!("{pattern} =>")),
+                Doc::nest(2, Doc::Concat(vec![Doc::Line, body])),
+            ])));
+        }
+        Doc::Concat(vec![
+            Doc::text("case "),
+            scrutinee,
+            Doc::text(" {"),
+            Doc::nest(2, Doc::Concat(docs)),
+            Doc::Line,
+            Doc::text("}"),
+        ])
+    }
+
+    /// Record types are printed as `sig { a : Type; b : a }`, where each
+    /// field is referred to by its name in the types after it. A field name
+    /// can hide a variable of the same name from the types after it.
+    fn record_type(&mut self, fields: &[(&str, TermRef<'_>)]) -> Doc {
+        let scope_before = self.scope.len();
+        let mut docs = Vec::new();
+        for &(name, ty) in fields {
+            let ty = self.term(ty, Precedence::Lambda);
+            docs.push(field(format!("{name} :"), ty));
+            self.scope.push(name.to_owned());
+        }
+        self.scope.truncate(scope_before);
+        fields_block("sig", docs)
+    }
+
+    /// Records are printed as `struct { a = b; c = d }`.
+    fn record(&mut self, fields: &[(&str, TermRef<'_>)]) -> Doc {
+        let docs = (fields.iter())
+            .map(|&(name, field)| {
+                let field = self.term(field, Precedence::Lambda);
+                self::field(format!("{name} ="), field)
+            })
+            .collect();
+        fields_block("struct", docs)
+    }
+
+    fn application(&mut self, mut term: TermRef<'_>) -> Doc {
+        let mut arguments = Vec::new();
+        while let Term::Application(plicity, function, argument) = term {
+            arguments.push((*plicity, *argument));
+            term = function;
+        }
+        let mut docs = vec![self.term(term, Precedence::Application)];
+        for (plicity, argument) in arguments.into_iter().rev() {
+            docs.push(Doc::Line);
+            docs.push(match plicity {
+                Plicity::Explicit => self.term(argument, Precedence::Atom),
+                Plicity::Implicit => Doc::braces(self.term(argument, Precedence::Lambda)),
+            });
+        }
+        let head = docs.remove(0);
+        Doc::Concat(vec![head, Doc::nest(2, Doc::Concat(docs))])
+    }
+}
+
+/// A field of a record or record type, indented under its name when it does
+/// not fit after it.
+fn field(name: String, body: Doc) -> Doc {
+    Doc::group(Doc::Concat(vec![
+        Doc::text(name),
+        Doc::nest(2, Doc::Concat(vec![Doc::Line, body])),
+    ]))
+}
+
+/// The fields of a record or record type after `keyword`, laid out one per
+/// line when they do not fit on one.
+fn fields_block(keyword: &str, fields: Vec<Doc>) -> Doc {
+    if fields.is_empty() {
+        return Doc::text(format!("{keyword} {{}}"));
+    }
+    let mut docs = Vec::new();
+    for field in fields {
+        if !docs.is_empty() {
+            docs.push(Doc::text(";"));
+        }
+        docs.push(Doc::Line);
+        docs.push(field);
+    }
+    Doc::Concat(vec![
+        Doc::text(format!("{keyword} {{")),
+        Doc::nest(2, Doc::Concat(docs)),
+        Doc::Line,
+        Doc::text("}"),
+    ])
+}
+
+/// Renders a term whose free variables are named by `context`, outermost
+/// first, wrapping lines that would exceed `width` columns.
+///
+/// Bound variables get fresh names that do not clash with `context`. Free
+/// variables outside `context` are printed as `#n`, counting outwards.
+pub fn render(term: TermRef<'_>, context: &[&str], width: usize) -> String {
+    let mut printer = Printer {
+        scope: context.iter().map(|&name| name.to_owned()).collect(),
+    };
+    let doc = printer.term(term, Precedence::Lambda);
+    layout(&doc, width)
+}
+
+/// Uses the formatter's width, as in `format!("{term:40}")`, if one is given.
+impl std::fmt::Display for Term<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&render(self, &[], f.width().unwrap_or(DEFAULT_WIDTH)))
+    }
+}
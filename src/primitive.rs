@@ -0,0 +1,173 @@
+/// The types of machine integers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IntegerType {
+    I64,
+    U64,
+}
+
+impl IntegerType {
+    pub const ALL: [IntegerType; 2] = [IntegerType::I64, IntegerType::U64];
+
+    /// The name of the type, such as `I64`.
+    pub fn name(self) -> &'static str {
+        match self {
+            IntegerType::I64 => "I64",
+            IntegerType::U64 => "U64",
+        }
+    }
+
+    /// The suffix of literals of the type, such as `i64` in `42i64`.
+    pub fn suffix(self) -> &'static str {
+        match self {
+            IntegerType::I64 => "i64",
+            IntegerType::U64 => "u64",
+        }
+    }
+}
+
+/// A machine integer literal.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Integer {
+    I64(i64),
+    U64(u64),
+}
+
+impl Integer {
+    pub fn ty(self) -> IntegerType {
+        match self {
+            Integer::I64(_) => IntegerType::I64,
+            Integer::U64(_) => IntegerType::U64,
+        }
+    }
+}
+
+impl std::fmt::Display for Integer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Integer::I64(value) => write!(f, "{value}"),
+            Integer::U64(value) => write!(f, "{value}"),
+        }?;
+        write!(f, "{}", self.ty().suffix())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Operation {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Eq,
+    Lt,
+    Le,
+}
+
+impl Operation {
+    pub const ALL: [Operation; 8] = [
+        Operation::Add,
+        Operation::Sub,
+        Operation::Mul,
+        Operation::Div,
+        Operation::Rem,
+        Operation::Eq,
+        Operation::Lt,
+        Operation::Le,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Operation::Add => "add",
+            Operation::Sub => "sub",
+            Operation::Mul => "mul",
+            Operation::Div => "div",
+            Operation::Rem => "rem",
+            Operation::Eq => "eq",
+            Operation::Lt => "lt",
+            Operation::Le => "le",
+        }
+    }
+
+    /// Whether the operation compares its arguments, giving a `Nat`, rather
+    /// than computing an integer of their type.
+    pub fn is_comparison(self) -> bool {
+        matches!(self, Operation::Eq | Operation::Lt | Operation::Le)
+    }
+}
+
+/// A primitive operation on machine integers of one type, written as the
+/// operation followed by the type, such as `addI64`.
+///
+/// Arithmetic wraps around on overflow. Division by zero gives zero, and the
+/// remainder of division by zero is the dividend, so every operation is total.
+/// Comparisons give the natural number 1 for true and 0 for false, which can
+/// be branched on with `natElim`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Primitive {
+    pub operation: Operation,
+    pub ty: IntegerType,
+}
+
+/// The result of a primitive operation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Output {
+    Integer(Integer),
+    Natural(u64),
+}
+
+macro_rules! compute {
+    ($operation:expr, $x:expr, $y:expr, $integer:path) => {{
+        let (x, y) = ($x, $y);
+        match $operation {
+            Operation::Add => Output::Integer($integer(x.wrapping_add(y))),
+            Operation::Sub => Output::Integer($integer(x.wrapping_sub(y))),
+            Operation::Mul => Output::Integer($integer(x.wrapping_mul(y))),
+            Operation::Div if y == 0 => Output::Integer($integer(0)),
+            Operation::Div => Output::Integer($integer(x.wrapping_div(y))),
+            Operation::Rem if y == 0 => Output::Integer($integer(x)),
+            Operation::Rem => Output::Integer($integer(x.wrapping_rem(y))),
+            Operation::Eq => Output::Natural(u64::from(x == y)),
+            Operation::Lt => Output::Natural(u64::from(x < y)),
+            Operation::Le => Output::Natural(u64::from(x <= y)),
+        }
+    }};
+}
+
+impl Primitive {
+    pub fn from_name(name: &str) -> Option<Self> {
+        let ty = IntegerType::ALL
+            .into_iter()
+            .find(|ty| name.ends_with(ty.name()))?;
+        let operation = &name[..name.len() - ty.name().len()];
+        let operation = Operation::ALL
+            .into_iter()
+            .find(|candidate| candidate.name() == operation)?;
+        Some(Primitive { operation, ty })
+    }
+
+    /// The number of arguments the operation computes with. Applied to fewer,
+    /// or to arguments that are not all literals, it is stuck.
+    pub fn arity(self) -> usize {
+        2
+    }
+
+    /// Computes the operation, or returns `None` if `arguments` are not
+    /// literals of its type.
+    pub fn apply(self, arguments: &[Integer]) -> Option<Output> {
+        match (self.ty, arguments) {
+            (IntegerType::I64, &[Integer::I64(x), Integer::I64(y)]) => {
+                Some(compute!(self.operation, x, y, Integer::I64))
+            }
+            (IntegerType::U64, &[Integer::U64(x), Integer::U64(y)]) => {
+                Some(compute!(self.operation, x, y, Integer::U64))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Primitive {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", self.operation.name(), self.ty.name())
+    }
+}
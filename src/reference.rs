@@ -0,0 +1,456 @@
+use crate::{
+    index::Index,
+    normalizer::Normalizer,
+    primitive::Output,
+    syntax::{self, Branch, Term, TermRef},
+};
+
+/// A normal-order normalizer that works directly on syntax by substitution.
+///
+/// It is far slower than the NbE backends, but simple enough to be obviously
+/// correct, which makes it the oracle for the differential tests. Globals,
+/// metas and holes are left as they are; [`Signature::inline`](crate::signature::Signature::inline)
+/// globals first.
+///
+/// Unlike the NbE backends, it recurses on the structure of terms, so it
+/// needs a stack as deep as the terms it is given: keep those shallow.
+pub struct Backend;
+
+impl Normalizer for Backend {
+    fn normalize<'out>(
+        &self,
+        term: TermRef<'_>,
+        syntax_builder: &'out syntax::Builder,
+    ) -> TermRef<'out> {
+        let term = copy(term, syntax_builder);
+        normalize(term, syntax_builder)
+    }
+}
+
+fn copy<'b>(term: TermRef<'_>, builder: &'b syntax::Builder) -> TermRef<'b> {
+    match term {
+        Term::Variable(index) => builder.variable(*index),
+        Term::Lambda(plicity, domain, body) => builder.lambda_with(
+            *plicity,
+            domain.map(|domain| copy(domain, builder)),
+            copy(body, builder),
+        ),
+        Term::Application(plicity, function, argument) => {
+            builder.application_with(*plicity, copy(function, builder), copy(argument, builder))
+        }
+        Term::Let(value, body) => builder.let_in(copy(value, builder), copy(body, builder)),
+        Term::Pi(plicity, domain, codomain) => {
+            builder.pi_with(*plicity, copy(domain, builder), copy(codomain, builder))
+        }
+        Term::Sigma(domain, codomain) => {
+            builder.sigma(copy(domain, builder), copy(codomain, builder))
+        }
+        Term::Pair(first, second) => builder.pair(copy(first, builder), copy(second, builder)),
+        Term::Fst(pair) => builder.fst(copy(pair, builder)),
+        Term::Snd(pair) => builder.snd(copy(pair, builder)),
+        Term::Type => builder.universe(),
+        Term::Nat => builder.nat(),
+        Term::Zero => builder.zero(),
+        Term::Suc(predecessor) => builder.suc(copy(predecessor, builder)),
+        Term::Natural(value) => builder.natural(*value),
+        Term::NatElim(motive, zero, suc, scrutinee) => builder.nat_elim(
+            copy(motive, builder),
+            copy(zero, builder),
+            copy(suc, builder),
+            copy(scrutinee, builder),
+        ),
+        Term::IntegerType(ty) => builder.integer_type(*ty),
+        Term::Integer(value) => builder.integer(*value),
+        Term::Primitive(primitive) => builder.primitive(*primitive),
+        Term::Global(global) => builder.global(global.index, global.name),
+        Term::Meta(meta) => builder.meta(*meta),
+        Term::Hole(hole) => builder.hole(hole.name, hole.scope),
+        Term::TypeConstructor(type_constructor) => {
+            builder.type_constructor(type_constructor.index, type_constructor.name)
+        }
+        Term::Constructor(constructor) => builder.constructor(*constructor),
+        Term::Case(scrutinee, branches) => {
+            case_with(copy(scrutinee, builder), branches, builder, |body, _| {
+                copy(body, builder)
+            })
+        }
+        Term::RecordType(fields) => {
+            builder.record_type(&fields_with(fields, |ty, _| copy(ty, builder)))
+        }
+        Term::Record(fields) => {
+            builder.record(&fields_with(fields, |field, _| copy(field, builder)))
+        }
+        Term::Project(record, name) => builder.project(copy(record, builder), name),
+    }
+}
+
+/// The fields of a record or record type with each replaced by `field`, which
+/// is given the number of fields before it.
+fn fields_with<'b, 't>(
+    fields: &[(&'t str, TermRef<'t>)],
+    mut field: impl FnMut(TermRef<'t>, usize) -> TermRef<'b>,
+) -> Vec<(&'t str, TermRef<'b>)> {
+    (fields.iter().enumerate())
+        .map(|(position, &(name, term))| (name, field(term, position)))
+        .collect()
+}
+
+/// Rebuilds a case analysis on `scrutinee` with the bodies of `branches`
+/// replaced by `body`, which is given the arity of each branch.
+fn case_with<'b, 't>(
+    scrutinee: TermRef<'b>,
+    branches: &[Branch<'t>],
+    builder: &'b syntax::Builder,
+    mut body: impl FnMut(TermRef<'t>, usize) -> TermRef<'b>,
+) -> TermRef<'b> {
+    let branches: Vec<_> = (branches.iter())
+        .map(|branch| {
+            builder.branch(
+                branch.constructor,
+                branch.arity,
+                body(branch.body, branch.arity),
+            )
+        })
+        .collect();
+    builder.case(scrutinee, &branches)
+}
+
+/// Adds `amount` to every variable of `term` that is free at depth `cutoff`.
+fn shift<'b>(
+    term: TermRef<'b>,
+    amount: usize,
+    cutoff: usize,
+    builder: &'b syntax::Builder,
+) -> TermRef<'b> {
+    if amount == 0 {
+        return term;
+    }
+    match term {
+        Term::Variable(Index(index)) if *index >= cutoff => builder.v(index + amount),
+        Term::Variable(_) => term,
+        Term::Lambda(plicity, domain, body) => builder.lambda_with(
+            *plicity,
+            domain.map(|domain| shift(domain, amount, cutoff, builder)),
+            shift(body, amount, cutoff + 1, builder),
+        ),
+        Term::Application(plicity, function, argument) => builder.application_with(
+            *plicity,
+            shift(function, amount, cutoff, builder),
+            shift(argument, amount, cutoff, builder),
+        ),
+        Term::Let(value, body) => builder.let_in(
+            shift(value, amount, cutoff, builder),
+            shift(body, amount, cutoff + 1, builder),
+        ),
+        Term::Pi(plicity, domain, codomain) => builder.pi_with(
+            *plicity,
+            shift(domain, amount, cutoff, builder),
+            shift(codomain, amount, cutoff + 1, builder),
+        ),
+        Term::Sigma(domain, codomain) => builder.sigma(
+            shift(domain, amount, cutoff, builder),
+            shift(codomain, amount, cutoff + 1, builder),
+        ),
+        Term::Pair(first, second) => builder.pair(
+            shift(first, amount, cutoff, builder),
+            shift(second, amount, cutoff, builder),
+        ),
+        Term::Fst(pair) => builder.fst(shift(pair, amount, cutoff, builder)),
+        Term::Snd(pair) => builder.snd(shift(pair, amount, cutoff, builder)),
+        Term::Type
+        | Term::Nat
+        | Term::Zero
+        | Term::Natural(_)
+        | Term::IntegerType(_)
+        | Term::Integer(_)
+        | Term::Primitive(_)
+        | Term::Global(_)
+        | Term::Meta(_)
+        | Term::Hole(_)
+        | Term::TypeConstructor(_)
+        | Term::Constructor(_) => term,
+        Term::Suc(predecessor) => builder.suc(shift(predecessor, amount, cutoff, builder)),
+        Term::NatElim(motive, zero, suc, scrutinee) => builder.nat_elim(
+            shift(motive, amount, cutoff, builder),
+            shift(zero, amount, cutoff, builder),
+            shift(suc, amount, cutoff, builder),
+            shift(scrutinee, amount, cutoff, builder),
+        ),
+        Term::Case(scrutinee, branches) => case_with(
+            shift(scrutinee, amount, cutoff, builder),
+            branches,
+            builder,
+            |body, arity| shift(body, amount, cutoff + arity, builder),
+        ),
+        Term::RecordType(fields) => builder.record_type(&fields_with(fields, |ty, position| {
+            shift(ty, amount, cutoff + position, builder)
+        })),
+        Term::Record(fields) => builder.record(&fields_with(fields, |field, _| {
+            shift(field, amount, cutoff, builder)
+        })),
+        Term::Project(record, name) => {
+            builder.project(shift(record, amount, cutoff, builder), name)
+        }
+    }
+}
+
+/// Substitutes `argument` for the variable bound `depth` binders above `body`,
+/// lowering the variables that were free above it by one.
+fn instantiate<'b>(
+    body: TermRef<'b>,
+    argument: TermRef<'b>,
+    depth: usize,
+    builder: &'b syntax::Builder,
+) -> TermRef<'b> {
+    match body {
+        Term::Variable(Index(index)) => match (*index).cmp(&depth) {
+            std::cmp::Ordering::Less => body,
+            std::cmp::Ordering::Equal => shift(argument, depth, 0, builder),
+            std::cmp::Ordering::Greater => builder.v(index - 1),
+        },
+        Term::Lambda(plicity, domain, inner) => builder.lambda_with(
+            *plicity,
+            domain.map(|domain| instantiate(domain, argument, depth, builder)),
+            instantiate(inner, argument, depth + 1, builder),
+        ),
+        Term::Application(plicity, function, inner_argument) => builder.application_with(
+            *plicity,
+            instantiate(function, argument, depth, builder),
+            instantiate(inner_argument, argument, depth, builder),
+        ),
+        Term::Let(value, body) => builder.let_in(
+            instantiate(value, argument, depth, builder),
+            instantiate(body, argument, depth + 1, builder),
+        ),
+        Term::Pi(plicity, domain, codomain) => builder.pi_with(
+            *plicity,
+            instantiate(domain, argument, depth, builder),
+            instantiate(codomain, argument, depth + 1, builder),
+        ),
+        Term::Sigma(domain, codomain) => builder.sigma(
+            instantiate(domain, argument, depth, builder),
+            instantiate(codomain, argument, depth + 1, builder),
+        ),
+        Term::Pair(first, second) => builder.pair(
+            instantiate(first, argument, depth, builder),
+            instantiate(second, argument, depth, builder),
+        ),
+        Term::Fst(pair) => builder.fst(instantiate(pair, argument, depth, builder)),
+        Term::Snd(pair) => builder.snd(instantiate(pair, argument, depth, builder)),
+        Term::Type
+        | Term::Nat
+        | Term::Zero
+        | Term::Natural(_)
+        | Term::IntegerType(_)
+        | Term::Integer(_)
+        | Term::Primitive(_)
+        | Term::Global(_)
+        | Term::Meta(_)
+        | Term::Hole(_)
+        | Term::TypeConstructor(_)
+        | Term::Constructor(_) => body,
+        Term::Suc(predecessor) => builder.suc(instantiate(predecessor, argument, depth, builder)),
+        Term::NatElim(motive, zero, suc, scrutinee) => builder.nat_elim(
+            instantiate(motive, argument, depth, builder),
+            instantiate(zero, argument, depth, builder),
+            instantiate(suc, argument, depth, builder),
+            instantiate(scrutinee, argument, depth, builder),
+        ),
+        Term::Case(scrutinee, branches) => case_with(
+            instantiate(scrutinee, argument, depth, builder),
+            branches,
+            builder,
+            |body, arity| instantiate(body, argument, depth + arity, builder),
+        ),
+        Term::RecordType(fields) => builder.record_type(&fields_with(fields, |ty, position| {
+            instantiate(ty, argument, depth + position, builder)
+        })),
+        Term::Record(fields) => builder.record(&fields_with(fields, |field, _| {
+            instantiate(field, argument, depth, builder)
+        })),
+        Term::Project(record, name) => {
+            builder.project(instantiate(record, argument, depth, builder), name)
+        }
+    }
+}
+
+fn weak_head_normalize<'b>(term: TermRef<'b>, builder: &'b syntax::Builder) -> TermRef<'b> {
+    match term {
+        Term::Application(plicity, function, argument) => {
+            match weak_head_normalize(function, builder) {
+                Term::Lambda(_, _, body) => {
+                    weak_head_normalize(instantiate(body, argument, 0, builder), builder)
+                }
+                function => {
+                    let term = builder.application_with(*plicity, function, argument);
+                    primitive_application(term, builder).unwrap_or(term)
+                }
+            }
+        }
+        Term::Let(value, body) => {
+            weak_head_normalize(instantiate(body, value, 0, builder), builder)
+        }
+        Term::Fst(pair) => match weak_head_normalize(pair, builder) {
+            Term::Pair(first, _) => weak_head_normalize(first, builder),
+            pair => builder.fst(pair),
+        },
+        Term::Snd(pair) => match weak_head_normalize(pair, builder) {
+            Term::Pair(_, second) => weak_head_normalize(second, builder),
+            pair => builder.snd(pair),
+        },
+        Term::Project(record, name) => {
+            let record = weak_head_normalize(record, builder);
+            match record {
+                Term::Record(fields) => match fields.iter().find(|&&(field, _)| field == *name) {
+                    Some((_, field)) => weak_head_normalize(field, builder),
+                    None => builder.project(record, name),
+                },
+                record => builder.project(record, name),
+            }
+        }
+        // Successors of literals are literals, as in the NbE backends, up to
+        // the largest literal.
+        Term::Zero => builder.natural(0),
+        Term::Suc(predecessor) => match weak_head_normalize(predecessor, builder) {
+            Term::Natural(value) if *value < u64::MAX => builder.natural(value + 1),
+            predecessor => builder.suc(predecessor),
+        },
+        Term::NatElim(motive, zero, suc, scrutinee) => {
+            let predecessor = match weak_head_normalize(scrutinee, builder) {
+                Term::Natural(0) => return weak_head_normalize(zero, builder),
+                Term::Natural(value) => builder.natural(value - 1),
+                Term::Suc(predecessor) => predecessor,
+                scrutinee => return builder.nat_elim(motive, zero, suc, scrutinee),
+            };
+            let recursion = builder.nat_elim(motive, zero, suc, predecessor);
+            weak_head_normalize(builder.apps(suc, &[predecessor, recursion]), builder)
+        }
+        Term::Case(scrutinee, branches) => {
+            let scrutinee = weak_head_normalize(scrutinee, builder);
+            match select(scrutinee, branches, builder) {
+                Some(body) => weak_head_normalize(body, builder),
+                None => builder.case(scrutinee, branches),
+            }
+        }
+        _ => term,
+    }
+}
+
+/// The body of the branch for the constructor that `scrutinee` applies, with
+/// its fields substituted for the last arguments.
+fn select<'b>(
+    scrutinee: TermRef<'b>,
+    branches: &[Branch<'b>],
+    builder: &'b syntax::Builder,
+) -> Option<TermRef<'b>> {
+    let mut arguments = Vec::new();
+    let mut head = scrutinee;
+    while let Term::Application(_, function, argument) = head {
+        arguments.push(*argument);
+        head = function;
+    }
+    let Term::Constructor(constructor) = head else {
+        return None;
+    };
+    let branch = (branches.iter()).find(|branch| branch.constructor.same(constructor))?;
+    let fields = arguments.get(..branch.arity)?;
+    // The first field is bound outermost, `arity - 1` binders above the body.
+    Some(
+        (fields.iter().rev().enumerate()).fold(branch.body, |body, (position, field)| {
+            instantiate(body, field, branch.arity - position - 1, builder)
+        }),
+    )
+}
+
+/// Computes `term` if it is a primitive applied to as many literals as it
+/// takes.
+fn primitive_application<'b>(
+    term: TermRef<'b>,
+    builder: &'b syntax::Builder,
+) -> Option<TermRef<'b>> {
+    let mut arguments = Vec::new();
+    let mut head = term;
+    while let Term::Application(_, function, argument) = head {
+        arguments.push(*argument);
+        head = function;
+    }
+    let Term::Primitive(primitive) = head else {
+        return None;
+    };
+    if arguments.len() != primitive.arity() {
+        return None;
+    }
+    let mut literals = Vec::new();
+    for argument in arguments.into_iter().rev() {
+        match weak_head_normalize(argument, builder) {
+            Term::Integer(value) => literals.push(*value),
+            _ => return None,
+        }
+    }
+    Some(match primitive.apply(&literals)? {
+        Output::Integer(value) => builder.integer(value),
+        Output::Natural(value) => builder.natural(value),
+    })
+}
+
+fn normalize<'b>(term: TermRef<'b>, builder: &'b syntax::Builder) -> TermRef<'b> {
+    match weak_head_normalize(term, builder) {
+        term @ Term::Variable(_) => term,
+        // Like the NbE backends, normal forms drop lambda annotations.
+        Term::Lambda(plicity, _, body) => {
+            builder.lambda_with(*plicity, None, normalize(body, builder))
+        }
+        Term::Application(plicity, function, argument) => builder.application_with(
+            *plicity,
+            normalize(function, builder),
+            normalize(argument, builder),
+        ),
+        Term::Pi(plicity, domain, codomain) => builder.pi_with(
+            *plicity,
+            normalize(domain, builder),
+            normalize(codomain, builder),
+        ),
+        Term::Sigma(domain, codomain) => {
+            builder.sigma(normalize(domain, builder), normalize(codomain, builder))
+        }
+        Term::Pair(first, second) => {
+            builder.pair(normalize(first, builder), normalize(second, builder))
+        }
+        Term::Fst(pair) => builder.fst(normalize(pair, builder)),
+        Term::Snd(pair) => builder.snd(normalize(pair, builder)),
+        Term::Suc(predecessor) => builder.suc(normalize(predecessor, builder)),
+        Term::NatElim(motive, zero, suc, scrutinee) => builder.nat_elim(
+            normalize(motive, builder),
+            normalize(zero, builder),
+            normalize(suc, builder),
+            normalize(scrutinee, builder),
+        ),
+        Term::Case(scrutinee, branches) => case_with(
+            normalize(scrutinee, builder),
+            branches,
+            builder,
+            |body, _| normalize(body, builder),
+        ),
+        Term::RecordType(fields) => {
+            builder.record_type(&fields_with(fields, |ty, _| normalize(ty, builder)))
+        }
+        Term::Record(fields) => {
+            builder.record(&fields_with(fields, |field, _| normalize(field, builder)))
+        }
+        Term::Project(record, name) => builder.project(normalize(record, builder), name),
+        term @ (Term::Type
+        | Term::Nat
+        | Term::Natural(_)
+        | Term::IntegerType(_)
+        | Term::Integer(_)
+        | Term::Primitive(_)
+        | Term::Global(_)
+        | Term::Meta(_)
+        | Term::Hole(_)
+        | Term::TypeConstructor(_)
+        | Term::Constructor(_)) => term,
+        Term::Let(..) | Term::Zero => {
+            unreachable!("weak head normal forms are not lets or zero")
+        }
+    }
+}
@@ -0,0 +1,193 @@
+use crate::syntax::{self, Constructor, Global, Term, TermRef, TypeConstructor, TypeRef};
+
+/// A named top-level definition. Its body is closed, except for references
+/// to the definitions before it.
+#[derive(Clone, Copy, Debug)]
+pub struct Definition<'a> {
+    pub name: &'a str,
+    /// The declared type, if any. The checker infers it otherwise.
+    pub ty: Option<TypeRef<'a>>,
+    pub term: TermRef<'a>,
+}
+
+/// A top-level inductive datatype, such as `data List (A : Type) = nil | cons
+/// A (List A)`: a type constructor, which takes the parameters, and the
+/// constructors of its values.
+#[derive(Clone, Copy, Debug)]
+pub struct Datatype<'a> {
+    pub name: &'a str,
+    /// The types of the parameters, each under binders for the ones before
+    /// it.
+    pub parameters: &'a [TypeRef<'a>],
+    pub constructors: &'a [ConstructorDeclaration<'a>],
+}
+
+/// A constructor of a [`Datatype`].
+#[derive(Clone, Copy, Debug)]
+pub struct ConstructorDeclaration<'a> {
+    pub name: &'a str,
+    /// The types of the fields, each under binders for the parameters and
+    /// the fields before it.
+    pub fields: &'a [TypeRef<'a>],
+}
+
+/// A definition or a datatype, in the order they were added to a signature.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Item<'a> {
+    Definition(Global<'a>),
+    Datatype(TypeConstructor<'a>),
+}
+
+/// What a name refers to in a signature.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Name<'a> {
+    Global(Global<'a>),
+    TypeConstructor(TypeConstructor<'a>),
+    Constructor(Constructor<'a>),
+}
+
+/// The top-level definitions and datatypes of a program, in order. Terms
+/// refer to definitions with [`Term::Global`] rather than inlining them, and
+/// the evaluator unfolds them only when it has to.
+#[derive(Clone, Debug, Default)]
+pub struct Signature<'a> {
+    definitions: Vec<Definition<'a>>,
+    datatypes: Vec<Datatype<'a>>,
+    items: Vec<Item<'a>>,
+}
+
+impl<'a> Signature<'a> {
+    pub fn new() -> Self {
+        Signature {
+            definitions: Vec::new(),
+            datatypes: Vec::new(),
+            items: Vec::new(),
+        }
+    }
+
+    /// Adds a definition after the existing items, shadowing any of the same
+    /// name, and returns a reference to it.
+    pub fn define(&mut self, definition: Definition<'a>) -> Global<'a> {
+        self.definitions.push(definition);
+        let global = Global {
+            index: self.definitions.len() - 1,
+            name: definition.name,
+        };
+        self.items.push(Item::Definition(global));
+        global
+    }
+
+    /// Adds a datatype after the existing items, shadowing any of the same
+    /// name, and returns its type constructor. Its constructors come after
+    /// its type constructor, so they shadow it.
+    pub fn declare(&mut self, datatype: Datatype<'a>) -> TypeConstructor<'a> {
+        self.datatypes.push(datatype);
+        let type_constructor = TypeConstructor {
+            index: self.datatypes.len() - 1,
+            name: datatype.name,
+        };
+        self.items.push(Item::Datatype(type_constructor));
+        type_constructor
+    }
+
+    /// Replaces the constructors of a declared datatype. Constructors whose
+    /// fields refer to their own datatype are parsed after it is declared,
+    /// and added with this.
+    pub fn set_constructors(
+        &mut self,
+        type_constructor: TypeConstructor<'_>,
+        constructors: &'a [ConstructorDeclaration<'a>],
+    ) {
+        self.datatypes[type_constructor.index].constructors = constructors;
+    }
+
+    /// What the latest item that declares `name` declares by it.
+    pub fn lookup(&self, name: &str) -> Option<Name<'a>> {
+        self.items.iter().rev().find_map(|item| match *item {
+            Item::Definition(global) => (global.name == name).then_some(Name::Global(global)),
+            Item::Datatype(type_constructor) => {
+                let datatype = &self.datatypes[type_constructor.index];
+                let constructor = (datatype.constructors.iter())
+                    .rposition(|constructor| constructor.name == name);
+                match constructor {
+                    Some(index) => Some(Name::Constructor(Constructor {
+                        datatype: type_constructor.index,
+                        index,
+                        name: datatype.constructors[index].name,
+                    })),
+                    None => (type_constructor.name == name)
+                        .then_some(Name::TypeConstructor(type_constructor)),
+                }
+            }
+        })
+    }
+
+    pub fn definition(&self, global: Global<'_>) -> &Definition<'a> {
+        &self.definitions[global.index]
+    }
+
+    pub fn definitions(&self) -> &[Definition<'a>] {
+        &self.definitions
+    }
+
+    pub fn datatype(&self, type_constructor: TypeConstructor<'_>) -> &Datatype<'a> {
+        &self.datatypes[type_constructor.index]
+    }
+
+    pub fn datatypes(&self) -> &[Datatype<'a>] {
+        &self.datatypes
+    }
+
+    /// The definitions and datatypes, in the order they were added.
+    pub fn items(&self) -> &[Item<'a>] {
+        &self.items
+    }
+
+    /// Replaces every global in `term` by its definition, recursively. Each
+    /// definition is inlined once and shared between its occurrences.
+    ///
+    /// The pending work is kept on an explicit stack, so terms and chains of
+    /// definitions of any depth can be inlined.
+    pub fn inline(&self, term: TermRef<'a>, builder: &'a syntax::Builder) -> TermRef<'a> {
+        let mut inlined = vec![None; self.definitions.len()];
+        let mut tasks = vec![Inline::Enter(term)];
+        let mut results = Vec::new();
+        while let Some(task) = tasks.pop() {
+            match task {
+                Inline::Enter(Term::Global(global)) => match inlined[global.index] {
+                    Some(term) => results.push(term),
+                    None => {
+                        tasks.push(Inline::Define(*global));
+                        tasks.push(Inline::Enter(self.definitions[global.index].term));
+                    }
+                },
+                Inline::Enter(term) => {
+                    let subterms = syntax::subterms(term);
+                    tasks.push(Inline::Rebuild(term, subterms.len()));
+                    tasks.extend(
+                        subterms
+                            .into_iter()
+                            .rev()
+                            .map(|(term, _)| Inline::Enter(term)),
+                    );
+                }
+                Inline::Define(global) => inlined[global.index] = results.last().copied(),
+                Inline::Rebuild(term, count) => {
+                    let subterms = results.split_off(results.len() - count);
+                    results.push(syntax::rebuild(term, &subterms, builder));
+                }
+            }
+        }
+        results.pop().unwrap()
+    }
+}
+
+/// A unit of pending work for [`Signature::inline`].
+enum Inline<'a> {
+    /// Inline a term, pushing the result.
+    Enter(TermRef<'a>),
+    /// Record the result on top as the inlined definition of the global.
+    Define(Global<'a>),
+    /// Rebuild a term from its last so many subterms, as inlined.
+    Rebuild(TermRef<'a>, usize),
+}
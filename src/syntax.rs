@@ -1,11 +1,142 @@
-use crate::index::Index;
+use crate::{
+    index::Index,
+    primitive::{Integer, IntegerType, Primitive},
+};
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 
 pub enum Term<'a> {
     Variable(Index),
-    Lambda(TermRef<'a>),
-    Application(TermRef<'a>, TermRef<'a>),
+    /// A lambda, optionally annotated with the type of its parameter.
+    Lambda(Plicity, Option<TypeRef<'a>>, TermRef<'a>),
+    Application(Plicity, TermRef<'a>, TermRef<'a>),
+    Let(TermRef<'a>, TermRef<'a>),
+    /// A dependent function type. The codomain is under a binder for the
+    /// argument.
+    Pi(Plicity, TypeRef<'a>, TypeRef<'a>),
+    /// A dependent pair type. The codomain is under a binder for the first
+    /// component.
+    Sigma(TypeRef<'a>, TypeRef<'a>),
+    Pair(TermRef<'a>, TermRef<'a>),
+    Fst(TermRef<'a>),
+    Snd(TermRef<'a>),
+    /// The universe of types, which is its own type.
+    Type,
+    /// The type of natural numbers.
+    Nat,
+    Zero,
+    Suc(TermRef<'a>),
+    /// A natural number literal, equal to that many successors of zero.
+    Natural(u64),
+    /// `NatElim(motive, zero, suc, scrutinee)` is primitive recursion on
+    /// `scrutinee`: `zero` when it is zero, and `suc n r` when it is the
+    /// successor of `n`, where `r` is the recursion on `n`. The motive is the
+    /// type of the result as a function of the scrutinee.
+    NatElim(TermRef<'a>, TermRef<'a>, TermRef<'a>, TermRef<'a>),
+    /// The type of machine integers of one width and signedness.
+    IntegerType(IntegerType),
+    /// A machine integer literal.
+    Integer(Integer),
+    /// A primitive operation on machine integers, applied like a function.
+    Primitive(Primitive),
+    /// A reference to a top-level definition of a
+    /// [`Signature`](crate::signature::Signature).
+    Global(Global<'a>),
+    /// A metavariable: a placeholder for a term that unification is to find,
+    /// closed except for the globals. See
+    /// [`MetaContext`](crate::meta::MetaContext).
+    Meta(Meta),
+    /// A hole left to be filled in, such as `?goal`. The checker reports
+    /// what it should be and elaborates it to a meta.
+    Hole(Hole<'a>),
+    /// The type constructor of a datatype of a
+    /// [`Signature`](crate::signature::Signature), applied to its parameters
+    /// like a function.
+    TypeConstructor(TypeConstructor<'a>),
+    /// A constructor of a datatype, applied to the parameters of the
+    /// datatype, implicitly, and then to its fields.
+    Constructor(Constructor<'a>),
+    /// `Case(scrutinee, branches)` is case analysis on a value of a datatype:
+    /// the branch for the constructor the scrutinee was built with, with its
+    /// fields bound.
+    Case(TermRef<'a>, &'a [Branch<'a>]),
+    /// A record type, with the name and type of each field. Each type is
+    /// under binders for the fields before it.
+    RecordType(&'a [(&'a str, TypeRef<'a>)]),
+    /// A record, with the name and value of each field.
+    Record(&'a [(&'a str, TermRef<'a>)]),
+    /// The field of a record with the given name.
+    Project(TermRef<'a>, &'a str),
+}
+
+/// Whether the argument of a function is written out, or left out for the
+/// checker to find by unification. Lambdas, applications and function types
+/// are each one or the other, and only match their own kind.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Plicity {
+    #[default]
+    Explicit,
+    Implicit,
+}
+
+/// A top-level definition, by its position in the signature. The name is only
+/// used for printing.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Global<'a> {
+    pub index: usize,
+    pub name: &'a str,
+}
+
+/// A metavariable, by its position in the metacontext. Printed as `?0`,
+/// `?1` and so on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Meta(pub usize);
+
+impl std::fmt::Display for Meta {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "?{}", self.0)
+    }
+}
+
+/// A named hole, with the names of the variables in scope at it, outermost
+/// first, for reporting its goal.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Hole<'a> {
+    pub name: &'a str,
+    pub scope: &'a [&'a str],
+}
+
+/// A datatype, by its position in the signature. The name is only used for
+/// printing.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TypeConstructor<'a> {
+    pub index: usize,
+    pub name: &'a str,
+}
+
+/// A constructor, by the position of its datatype in the signature and its
+/// own position among the constructors of that datatype.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Constructor<'a> {
+    pub datatype: usize,
+    pub index: usize,
+    pub name: &'a str,
+}
+
+impl Constructor<'_> {
+    /// Whether both are the same constructor, whatever their names.
+    pub fn same(&self, other: &Constructor<'_>) -> bool {
+        self.datatype == other.datatype && self.index == other.index
+    }
+}
+
+/// A branch of a case analysis. The body is under one binder for each of
+/// the `arity` fields of the constructor, the last field innermost.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Branch<'a> {
+    pub constructor: Constructor<'a>,
+    pub arity: usize,
+    pub body: TermRef<'a>,
 }
 
 pub type Type<'a> = Term<'a>;
@@ -16,6 +147,121 @@ pub struct Builder {
     arena: blink_alloc::Blink,
 }
 
+impl<'a> Term<'a> {
+    /// The immediate subterms, each with the number of variables it binds
+    /// that are in scope in it.
+    fn subterms(&self) -> Vec<(TermRef<'a>, usize)> {
+        match self {
+            Term::Lambda(_, domain, body) => {
+                (domain.iter().map(|&domain| (domain, 0)).chain([(*body, 1)])).collect()
+            }
+            Term::Application(_, function, argument) => vec![(function, 0), (argument, 0)],
+            Term::Let(value, body) => vec![(value, 0), (body, 1)],
+            Term::Pi(_, domain, codomain) | Term::Sigma(domain, codomain) => {
+                vec![(domain, 0), (codomain, 1)]
+            }
+            Term::Pair(first, second) => vec![(first, 0), (second, 0)],
+            Term::Fst(term) | Term::Snd(term) | Term::Suc(term) | Term::Project(term, _) => {
+                vec![(term, 0)]
+            }
+            Term::NatElim(motive, zero, suc, scrutinee) => {
+                vec![(motive, 0), (zero, 0), (suc, 0), (scrutinee, 0)]
+            }
+            Term::Case(scrutinee, branches) => ([(*scrutinee, 0)].into_iter())
+                .chain(branches.iter().map(|branch| (branch.body, branch.arity)))
+                .collect(),
+            Term::RecordType(fields) => (fields.iter().enumerate())
+                .map(|(position, &(_, ty))| (ty, position))
+                .collect(),
+            Term::Record(fields) => fields.iter().map(|&(_, field)| (field, 0)).collect(),
+            Term::Variable(_)
+            | Term::Type
+            | Term::Nat
+            | Term::Zero
+            | Term::Natural(_)
+            | Term::IntegerType(_)
+            | Term::Integer(_)
+            | Term::Primitive(_)
+            | Term::Global(_)
+            | Term::Meta(_)
+            | Term::Hole(_)
+            | Term::TypeConstructor(_)
+            | Term::Constructor(_) => Vec::new(),
+        }
+    }
+
+    /// Whether the variable `index` is free in the term.
+    pub fn occurs(&self, index: Index) -> bool {
+        let mut pending = vec![(self, index.to_int())];
+        while let Some((term, index)) = pending.pop() {
+            match term {
+                Term::Variable(Index(variable)) => {
+                    if *variable == index {
+                        return true;
+                    }
+                }
+                Term::Lambda(_, domain, body) => {
+                    pending.extend(domain.map(|domain| (domain, index)));
+                    pending.push((body, index + 1));
+                }
+                Term::Application(_, function, argument) => {
+                    pending.push((function, index));
+                    pending.push((argument, index));
+                }
+                Term::Let(value, body) => {
+                    pending.push((value, index));
+                    pending.push((body, index + 1));
+                }
+                Term::Pi(_, domain, codomain) | Term::Sigma(domain, codomain) => {
+                    pending.push((domain, index));
+                    pending.push((codomain, index + 1));
+                }
+                Term::Pair(first, second) => {
+                    pending.push((first, index));
+                    pending.push((second, index));
+                }
+                Term::Fst(term) | Term::Snd(term) | Term::Suc(term) | Term::Project(term, _) => {
+                    pending.push((term, index))
+                }
+                Term::RecordType(fields) => pending.extend(
+                    (fields.iter().enumerate()).map(|(position, &(_, ty))| (ty, index + position)),
+                ),
+                Term::Record(fields) => {
+                    pending.extend(fields.iter().map(|&(_, field)| (field, index)))
+                }
+                Term::NatElim(motive, zero, suc, scrutinee) => {
+                    pending.extend([motive, zero, suc, scrutinee].map(|term| (*term, index)))
+                }
+                Term::Case(scrutinee, branches) => {
+                    pending.push((scrutinee, index));
+                    pending.extend(
+                        (branches.iter()).map(|branch| (branch.body, index + branch.arity)),
+                    );
+                }
+                Term::Type
+                | Term::Nat
+                | Term::Zero
+                | Term::Natural(_)
+                | Term::IntegerType(_)
+                | Term::Integer(_)
+                | Term::Primitive(_)
+                | Term::Global(_)
+                | Term::Meta(_)
+                | Term::Hole(_)
+                | Term::TypeConstructor(_)
+                | Term::Constructor(_) => {}
+            }
+        }
+        false
+    }
+}
+
+impl Default for Builder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Builder {
     pub fn new() -> Self {
         Builder {
@@ -27,12 +273,290 @@ impl Builder {
     }
 
     pub fn lambda<'a>(&'a self, body: TermRef<'a>) -> TermRef<'a> {
-        self.arena.put_no_drop(Term::Lambda(body))
+        self.lambda_with(Plicity::Explicit, None, body)
+    }
+
+    pub fn annotated_lambda<'a>(&'a self, domain: TypeRef<'a>, body: TermRef<'a>) -> TermRef<'a> {
+        self.lambda_with(Plicity::Explicit, Some(domain), body)
+    }
+
+    pub fn lambda_with<'a>(
+        &'a self,
+        plicity: Plicity,
+        domain: Option<TypeRef<'a>>,
+        body: TermRef<'a>,
+    ) -> TermRef<'a> {
+        self.arena.put_no_drop(Term::Lambda(plicity, domain, body))
     }
 
     pub fn application<'a>(&'a self, function: TermRef<'a>, argument: TermRef<'a>) -> TermRef<'a> {
+        self.application_with(Plicity::Explicit, function, argument)
+    }
+
+    pub fn application_with<'a>(
+        &'a self,
+        plicity: Plicity,
+        function: TermRef<'a>,
+        argument: TermRef<'a>,
+    ) -> TermRef<'a> {
+        self.arena
+            .put_no_drop(Term::Application(plicity, function, argument))
+    }
+
+    pub fn let_in<'a>(&'a self, value: TermRef<'a>, body: TermRef<'a>) -> TermRef<'a> {
+        self.arena.put_no_drop(Term::Let(value, body))
+    }
+
+    pub fn pi<'a>(&'a self, domain: TypeRef<'a>, codomain: TypeRef<'a>) -> TypeRef<'a> {
+        self.pi_with(Plicity::Explicit, domain, codomain)
+    }
+
+    pub fn pi_with<'a>(
+        &'a self,
+        plicity: Plicity,
+        domain: TypeRef<'a>,
+        codomain: TypeRef<'a>,
+    ) -> TypeRef<'a> {
+        self.arena.put_no_drop(Term::Pi(plicity, domain, codomain))
+    }
+
+    pub fn sigma<'a>(&'a self, domain: TypeRef<'a>, codomain: TypeRef<'a>) -> TypeRef<'a> {
+        self.arena.put_no_drop(Term::Sigma(domain, codomain))
+    }
+
+    pub fn pair<'a>(&'a self, first: TermRef<'a>, second: TermRef<'a>) -> TermRef<'a> {
+        self.arena.put_no_drop(Term::Pair(first, second))
+    }
+
+    pub fn fst<'a>(&'a self, pair: TermRef<'a>) -> TermRef<'a> {
+        self.arena.put_no_drop(Term::Fst(pair))
+    }
+
+    pub fn snd<'a>(&'a self, pair: TermRef<'a>) -> TermRef<'a> {
+        self.arena.put_no_drop(Term::Snd(pair))
+    }
+
+    pub fn universe<'a>(&'a self) -> TypeRef<'a> {
+        self.arena.put_no_drop(Term::Type)
+    }
+
+    pub fn nat<'a>(&'a self) -> TypeRef<'a> {
+        self.arena.put_no_drop(Term::Nat)
+    }
+
+    pub fn zero<'a>(&'a self) -> TermRef<'a> {
+        self.arena.put_no_drop(Term::Zero)
+    }
+
+    pub fn suc<'a>(&'a self, predecessor: TermRef<'a>) -> TermRef<'a> {
+        self.arena.put_no_drop(Term::Suc(predecessor))
+    }
+
+    pub fn natural<'a>(&'a self, value: u64) -> TermRef<'a> {
+        self.arena.put_no_drop(Term::Natural(value))
+    }
+
+    pub fn nat_elim<'a>(
+        &'a self,
+        motive: TermRef<'a>,
+        zero: TermRef<'a>,
+        suc: TermRef<'a>,
+        scrutinee: TermRef<'a>,
+    ) -> TermRef<'a> {
+        self.arena
+            .put_no_drop(Term::NatElim(motive, zero, suc, scrutinee))
+    }
+
+    pub fn integer_type<'a>(&'a self, ty: IntegerType) -> TypeRef<'a> {
+        self.arena.put_no_drop(Term::IntegerType(ty))
+    }
+
+    pub fn integer<'a>(&'a self, value: Integer) -> TermRef<'a> {
+        self.arena.put_no_drop(Term::Integer(value))
+    }
+
+    pub fn primitive<'a>(&'a self, primitive: Primitive) -> TermRef<'a> {
+        self.arena.put_no_drop(Term::Primitive(primitive))
+    }
+
+    /// A reference to the global at `index`, with `name` copied into the
+    /// arena.
+    pub fn global<'a>(&'a self, index: usize, name: &str) -> TermRef<'a> {
+        let name = self.copy_str(name);
+        self.arena.put_no_drop(Term::Global(Global { index, name }))
+    }
+
+    pub fn meta<'a>(&'a self, meta: Meta) -> TermRef<'a> {
+        self.arena.put_no_drop(Term::Meta(meta))
+    }
+
+    /// A hole named `name`, with `name` and `scope` copied into the arena.
+    pub fn hole<'a>(&'a self, name: &str, scope: &[&str]) -> TermRef<'a> {
+        let name = self.copy_str(name);
+        let scope: Vec<&str> = scope.iter().map(|name| self.copy_str(name)).collect();
+        let scope = self.copy_slice(&scope);
+        self.arena.put_no_drop(Term::Hole(Hole { name, scope }))
+    }
+
+    /// A reference to the type constructor of the datatype at `index`, with
+    /// `name` copied into the arena.
+    pub fn type_constructor<'a>(&'a self, index: usize, name: &str) -> TypeRef<'a> {
+        let name = self.copy_str(name);
         self.arena
-            .put_no_drop(Term::Application(function, argument))
+            .put_no_drop(Term::TypeConstructor(TypeConstructor { index, name }))
+    }
+
+    /// A reference to `constructor`, with its name copied into the arena.
+    pub fn constructor<'a>(&'a self, constructor: Constructor<'_>) -> TermRef<'a> {
+        let constructor = self.copy_constructor(constructor);
+        self.arena.put_no_drop(Term::Constructor(constructor))
+    }
+
+    /// A case analysis, with `branches` copied into the arena.
+    pub fn case<'a>(&'a self, scrutinee: TermRef<'a>, branches: &[Branch<'a>]) -> TermRef<'a> {
+        let branches = self.copy_slice(branches);
+        self.arena.put_no_drop(Term::Case(scrutinee, branches))
+    }
+
+    /// A record type, with `fields` and their names copied into the arena.
+    pub fn record_type<'a>(&'a self, fields: &[(&str, TypeRef<'a>)]) -> TypeRef<'a> {
+        let fields = self.copy_fields(fields);
+        self.arena.put_no_drop(Term::RecordType(fields))
+    }
+
+    /// A record, with `fields` and their names copied into the arena.
+    pub fn record<'a>(&'a self, fields: &[(&str, TermRef<'a>)]) -> TermRef<'a> {
+        let fields = self.copy_fields(fields);
+        self.arena.put_no_drop(Term::Record(fields))
+    }
+
+    /// The field `name` of `record`, with `name` copied into the arena.
+    pub fn project<'a>(&'a self, record: TermRef<'a>, name: &str) -> TermRef<'a> {
+        let name = self.copy_str(name);
+        self.arena.put_no_drop(Term::Project(record, name))
+    }
+
+    fn copy_fields<'a>(&'a self, fields: &[(&str, TermRef<'a>)]) -> &'a [(&'a str, TermRef<'a>)] {
+        let fields: Vec<_> = (fields.iter())
+            .map(|&(name, field)| (self.copy_str(name), field))
+            .collect();
+        self.copy_slice(&fields)
+    }
+
+    /// `term` with the variable `Index(cutoff)`, which must not occur in it,
+    /// dropped from its context: the variables bound outside it are lowered
+    /// by one. The term is rebuilt with an explicit stack, so it can be
+    /// arbitrarily deep.
+    pub fn strengthen<'a>(&'a self, term: TermRef<'a>, cutoff: usize) -> TermRef<'a> {
+        enum Step<'a> {
+            Visit(TermRef<'a>, usize),
+            Rebuild(TermRef<'a>, usize),
+        }
+        let mut steps = vec![Step::Visit(term, cutoff)];
+        let mut results = Vec::new();
+        while let Some(step) = steps.pop() {
+            match step {
+                Step::Visit(Term::Variable(Index(index)), cutoff) => {
+                    assert!(
+                        *index != cutoff,
+                        "strengthened a term the variable occurs in"
+                    );
+                    let index = if *index > cutoff { index - 1 } else { *index };
+                    results.push(self.v(index));
+                }
+                Step::Visit(term, cutoff) => {
+                    let subterms = term.subterms();
+                    steps.push(Step::Rebuild(term, subterms.len()));
+                    for (subterm, binders) in subterms.into_iter().rev() {
+                        steps.push(Step::Visit(subterm, cutoff + binders));
+                    }
+                }
+                Step::Rebuild(term, count) => {
+                    let subterms = results.split_off(results.len() - count);
+                    results.push(self.with_subterms(term, &subterms));
+                }
+            }
+        }
+        results.pop().unwrap()
+    }
+
+    /// `term` with its immediate subterms, in the order of
+    /// [`Term::subterms`], replaced by `subterms`.
+    fn with_subterms<'a>(&'a self, term: TermRef<'a>, subterms: &[TermRef<'a>]) -> TermRef<'a> {
+        match (term, subterms) {
+            (Term::Lambda(plicity, None, _), &[body]) => self.lambda_with(*plicity, None, body),
+            (Term::Lambda(plicity, Some(_), _), &[domain, body]) => {
+                self.lambda_with(*plicity, Some(domain), body)
+            }
+            (Term::Application(plicity, ..), &[function, argument]) => {
+                self.application_with(*plicity, function, argument)
+            }
+            (Term::Let(..), &[value, body]) => self.let_in(value, body),
+            (Term::Pi(plicity, ..), &[domain, codomain]) => {
+                self.pi_with(*plicity, domain, codomain)
+            }
+            (Term::Sigma(..), &[domain, codomain]) => self.sigma(domain, codomain),
+            (Term::Pair(..), &[first, second]) => self.pair(first, second),
+            (Term::Fst(_), &[pair]) => self.fst(pair),
+            (Term::Snd(_), &[pair]) => self.snd(pair),
+            (Term::Suc(_), &[predecessor]) => self.suc(predecessor),
+            (Term::NatElim(..), &[motive, zero, suc, scrutinee]) => {
+                self.nat_elim(motive, zero, suc, scrutinee)
+            }
+            (Term::Case(_, branches), [scrutinee, bodies @ ..]) => {
+                let branches: Vec<_> = (branches.iter().zip(bodies))
+                    .map(|(branch, &body)| Branch { body, ..*branch })
+                    .collect();
+                self.case(scrutinee, &branches)
+            }
+            (Term::RecordType(fields), types) => {
+                let fields: Vec<_> = (fields.iter().zip(types))
+                    .map(|(&(name, _), &ty)| (name, ty))
+                    .collect();
+                self.record_type(&fields)
+            }
+            (Term::Record(fields), values) => {
+                let fields: Vec<_> = (fields.iter().zip(values))
+                    .map(|(&(name, _), &value)| (name, value))
+                    .collect();
+                self.record(&fields)
+            }
+            (Term::Project(_, name), &[record]) => self.project(record, name),
+            (term, []) => term,
+            _ => panic!("rebuilt a term with the wrong number of subterms"),
+        }
+    }
+
+    /// A branch for `constructor`, with its name copied into the arena.
+    pub fn branch<'a>(
+        &'a self,
+        constructor: Constructor<'_>,
+        arity: usize,
+        body: TermRef<'a>,
+    ) -> Branch<'a> {
+        Branch {
+            constructor: self.copy_constructor(constructor),
+            arity,
+            body,
+        }
+    }
+
+    fn copy_constructor<'a>(&'a self, constructor: Constructor<'_>) -> Constructor<'a> {
+        Constructor {
+            datatype: constructor.datatype,
+            index: constructor.index,
+            name: self.copy_str(constructor.name),
+        }
+    }
+
+    /// Copies `string` into the arena, e.g. for the name of a definition.
+    pub fn copy_str<'a>(&'a self, string: &str) -> &'a str {
+        self.arena.copy_str(string)
+    }
+
+    /// Copies `slice` into the arena.
+    pub fn copy_slice<'a, T: Copy>(&'a self, slice: &[T]) -> &'a [T] {
+        self.arena.copy_slice(slice)
     }
 
     pub fn v<'a>(&'a self, index: usize) -> TermRef<'a> {
@@ -47,3 +571,93 @@ impl Builder {
         self.lambda(body)
     }
 }
+
+/// The immediate subterms of `term`, in the order [`rebuild`] takes them,
+/// each with the number of binders of `term` it is under.
+pub(crate) fn subterms<'a>(term: TermRef<'a>) -> Vec<(TermRef<'a>, usize)> {
+    match term {
+        Term::Variable(_)
+        | Term::Type
+        | Term::Nat
+        | Term::Zero
+        | Term::Natural(_)
+        | Term::IntegerType(_)
+        | Term::Integer(_)
+        | Term::Primitive(_)
+        | Term::Global(_)
+        | Term::Meta(_)
+        | Term::Hole(_)
+        | Term::TypeConstructor(_)
+        | Term::Constructor(_) => vec![],
+        Term::Lambda(_, None, body) => vec![(body, 1)],
+        Term::Lambda(_, Some(domain), body) => vec![(domain, 0), (body, 1)],
+        Term::Application(_, function, argument) => vec![(function, 0), (argument, 0)],
+        Term::Let(value, body) => vec![(value, 0), (body, 1)],
+        Term::Pi(_, domain, codomain) | Term::Sigma(domain, codomain) => {
+            vec![(domain, 0), (codomain, 1)]
+        }
+        Term::Pair(first, second) => vec![(first, 0), (second, 0)],
+        Term::Fst(pair) | Term::Snd(pair) => vec![(pair, 0)],
+        Term::Suc(predecessor) => vec![(predecessor, 0)],
+        Term::NatElim(motive, zero, suc, scrutinee) => {
+            vec![(motive, 0), (zero, 0), (suc, 0), (scrutinee, 0)]
+        }
+        Term::Case(scrutinee, branches) => std::iter::once((*scrutinee, 0))
+            .chain(branches.iter().map(|branch| (branch.body, branch.arity)))
+            .collect(),
+        // Each field type is under binders for the fields before it.
+        Term::RecordType(fields) => (fields.iter().enumerate())
+            .map(|(position, &(_, ty))| (ty, position))
+            .collect(),
+        Term::Record(fields) => fields.iter().map(|&(_, field)| (field, 0)).collect(),
+        Term::Project(record, _) => vec![(record, 0)],
+    }
+}
+
+/// `term` with its immediate subterms replaced by `subterms`.
+pub(crate) fn rebuild<'a>(
+    term: TermRef<'a>,
+    subterms: &[TermRef<'a>],
+    b: &'a Builder,
+) -> TermRef<'a> {
+    match (term, subterms) {
+        (_, []) => term,
+        (Term::Lambda(plicity, None, _), &[body]) => b.lambda_with(*plicity, None, body),
+        (Term::Lambda(plicity, Some(_), _), &[domain, body]) => {
+            b.lambda_with(*plicity, Some(domain), body)
+        }
+        (Term::Application(plicity, ..), &[function, argument]) => {
+            b.application_with(*plicity, function, argument)
+        }
+        (Term::Let(..), &[value, body]) => b.let_in(value, body),
+        (Term::Pi(plicity, ..), &[domain, codomain]) => b.pi_with(*plicity, domain, codomain),
+        (Term::Sigma(..), &[domain, codomain]) => b.sigma(domain, codomain),
+        (Term::Pair(..), &[first, second]) => b.pair(first, second),
+        (Term::Fst(_), &[pair]) => b.fst(pair),
+        (Term::Snd(_), &[pair]) => b.snd(pair),
+        (Term::Suc(_), &[predecessor]) => b.suc(predecessor),
+        (Term::NatElim(..), &[motive, zero, suc, scrutinee]) => {
+            b.nat_elim(motive, zero, suc, scrutinee)
+        }
+        (Term::Case(_, branches), [scrutinee, bodies @ ..]) => {
+            let branches: Vec<_> = (branches.iter().zip(bodies))
+                .map(|(branch, body)| b.branch(branch.constructor, branch.arity, body))
+                .collect();
+            b.case(scrutinee, &branches)
+        }
+        (Term::RecordType(fields), types) => {
+            let fields: Vec<_> = (fields.iter().zip(types))
+                .map(|(&(name, _), &ty)| (name, ty))
+                .collect();
+            b.record_type(&fields)
+        }
+        (Term::Record(fields), values) => {
+            let fields: Vec<_> = (fields.iter().zip(values))
+                .map(|(&(name, _), &field)| (name, field))
+                .collect();
+            b.record(&fields)
+        }
+        (Term::Project(_, name), &[record]) => b.project(record, name),
+        _ => unreachable!("subterms do not match the term they came from"),
+    }
+}
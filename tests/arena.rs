@@ -0,0 +1,78 @@
+mod common;
+
+use common::{to_nat, tree_leaves, Workloads};
+use rixty::{domain, index::Level, normalizer::Normalizer, syntax};
+
+#[test]
+fn closed_terms() {
+    let b = syntax::Builder::new();
+    let id = b.l(b.v(0));
+    let result_builder = syntax::Builder::new();
+    for term in [
+        id,
+        b.application(id, id),
+        // (\x. x x) (\y. y)
+        b.application(b.l(b.application(b.v(0), b.v(0))), id),
+    ] {
+        assert_eq!(domain::Backend.normalize(term, &result_builder), id);
+    }
+}
+
+#[test]
+fn church_numerals() {
+    let b = syntax::Builder::new();
+    let w = Workloads::new(&b);
+    let result_builder = syntax::Builder::new();
+    for (term, expected) in [
+        (w.n2, 2),
+        (w.n10, 10),
+        (w.n10b, 10),
+        (w.n20, 20),
+        (w.n21, 21),
+        (w.n100, 100),
+        (w.n1k, 1000),
+    ] {
+        let normal_form = domain::Backend.normalize(term, &result_builder);
+        assert_eq!(to_nat(normal_form), Some(expected));
+    }
+}
+
+#[test]
+fn trees() {
+    let b = syntax::Builder::new();
+    let w = Workloads::new(&b);
+    let result_builder = syntax::Builder::new();
+    let tree_1k = domain::Backend.normalize(w.tree_1k, &result_builder);
+    let tree_2k = domain::Backend.normalize(w.tree_2k, &result_builder);
+    assert_eq!(tree_leaves(tree_1k), Some(1024));
+    assert_eq!(tree_leaves(tree_2k), Some(2048));
+}
+
+#[test]
+fn open_terms() {
+    // mul 2 x, with x free, unfolds as far as the neutral argument allows.
+    let b = syntax::Builder::new();
+    let w = Workloads::new(&b);
+    let term = b.apps(w.mul, &[w.n2, b.v(0)]);
+
+    let builder = domain::Builder::new();
+    let result_builder = syntax::Builder::new();
+    let normal_form = builder.normalize(term, Level(1), &result_builder);
+
+    // \f y. x f (x f y)
+    let x_f_y = b.apps(b.v(2), &[b.v(1), b.v(0)]);
+    let expected = b.l(b.l(b.apps(b.v(2), &[b.v(1), x_f_y])));
+    assert_eq!(normal_form, expected);
+}
+
+#[test]
+fn quote_from_evaluate() {
+    let b = syntax::Builder::new();
+    let w = Workloads::new(&b);
+
+    let builder = domain::Builder::new();
+    let result_builder = syntax::Builder::new();
+    let value = w.n10.evaluate(&mut domain::Environment::new(), &builder);
+    let normal_form = value.quote(Level(0), &builder, &result_builder);
+    assert_eq!(to_nat(normal_form), Some(10));
+}
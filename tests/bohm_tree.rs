@@ -0,0 +1,88 @@
+mod common;
+
+use common::Workloads;
+use rixty::{
+    bohm_tree::{Body, BohmTree},
+    domain_rc::{Environment, Strategy},
+    fuel::{Fuel, Interrupted},
+    index::Level,
+    parse::{parse, parse_in, parse_signature},
+    syntax,
+};
+
+const FIX: &str = r"(\f. (\x. f (x x)) (\x. f (x x)))";
+
+#[test]
+fn infinite_trees_are_expanded_on_demand() {
+    let b = syntax::Builder::new();
+    let term = parse(&format!(r"{FIX} (\s c. c s)"), &b).unwrap();
+    let value = term.evaluate_rc(&mut Environment::new().with_strategy(Strategy::CallByNeed));
+    let tree = BohmTree::new(value, Level(0));
+    assert_eq!(tree.render(0), "?_");
+    assert_eq!(tree.render(1), r"\a. a ?_");
+    assert_eq!(tree.render(3), r"\a. a (\b. b (\c. c ?_))");
+
+    let node = tree.expand();
+    assert_eq!(node.binders.len(), 1);
+    assert_eq!(node.level, Level(1));
+    let Body::Application { head, arguments } = &node.body else {
+        panic!("not headed by a variable");
+    };
+    assert_eq!(head.variable(), Some(Level(0)));
+    assert_eq!(arguments.len(), 1);
+    assert_eq!(arguments[0].1.expand().level, Level(2));
+}
+
+#[test]
+fn streams_of_constructors() {
+    let b = syntax::Builder::new();
+    let signature = parse_signature(r"data Stream = scons Nat Stream;", &b).unwrap();
+    let term = parse_in(&format!(r"{FIX} (\s. scons (suc 0) s)"), &signature, &b).unwrap();
+    let mut environment = Environment::new()
+        .with_signature(&signature)
+        .with_strategy(Strategy::CallByNeed);
+    let tree = BohmTree::new(term.evaluate_rc(&mut environment), Level(0));
+    assert_eq!(tree.render(1), r"scons ?_ ?_");
+    assert_eq!(tree.render(3), r"scons 1 (scons 1 (scons ?_ ?_))");
+}
+
+#[test]
+fn unsolvable_subtrees_only_diverge_when_expanded() {
+    let b = syntax::Builder::new();
+    let term = parse(r"\f. f ((\x. x x) (\x. x x)) f", &b).unwrap();
+    let value = term.evaluate_rc(&mut Environment::new().with_strategy(Strategy::CallByNeed));
+    let tree = BohmTree::new(value, Level(0));
+    assert_eq!(tree.render(1), r"\a. a ?_ ?_");
+
+    let Body::Application { arguments, .. } = &tree.expand().body else {
+        panic!("not headed by a variable");
+    };
+    let fuel = Fuel::new(1000);
+    assert_eq!(
+        arguments[0].1.expand_with_fuel(&fuel).err(),
+        Some(Interrupted::OutOfFuel)
+    );
+    // Subtrees are rendered on their own, with the binders above them free.
+    assert_eq!(arguments[1].1.render(1), "#0");
+    let fuel = Fuel::new(1000);
+    let result_builder = syntax::Builder::new();
+    assert_eq!(
+        tree.truncate_with_fuel(2, &result_builder, &fuel).err(),
+        Some(Interrupted::OutOfFuel)
+    );
+}
+
+#[test]
+fn deep_enough_trees_are_normal_forms() {
+    let b = syntax::Builder::new();
+    let w = Workloads::new(&b);
+    let result_builder = syntax::Builder::new();
+    for term in [w.n2, w.n10, w.mul, w.tree_1k] {
+        let value = term.evaluate_rc(&mut Environment::new());
+        let tree = BohmTree::new(value.clone(), Level(0));
+        assert_eq!(
+            tree.truncate(usize::MAX, &result_builder),
+            value.quote(Level(0), &result_builder)
+        );
+    }
+}
@@ -0,0 +1,568 @@
+use rixty::{
+    check::{Context, Error},
+    domain_rc::QuoteOptions,
+    index::Level,
+    parse::{parse, parse_in, parse_signature},
+    syntax::{self, TermRef},
+};
+
+/// Checks `term` against `ty` in the empty context, both given as source.
+fn check<'a>(term: &str, ty: &str, b: &'a syntax::Builder) -> Result<(), Error<'a>> {
+    let term = parse(term, b).unwrap();
+    let ty = parse(ty, b).unwrap();
+    let mut context = Context::new(b);
+    let ty = context.check(ty, &context.evaluate(b.universe()))?;
+    let ty = context.evaluate(ty);
+    context.check(term, &ty).map(|_| ())
+}
+
+/// Infers the type of `term` in the empty context and reads it back.
+fn infer<'a>(term: &str, b: &'a syntax::Builder) -> Result<TermRef<'a>, Error<'a>> {
+    let term = parse(term, b).unwrap();
+    let mut context = Context::new(b);
+    let (_, ty) = context.infer(term)?;
+    Ok(context.quote(&ty))
+}
+
+const CHURCH: &str = "(A : Type) -> (A -> A) -> A -> A";
+
+#[test]
+fn polymorphic_identity() {
+    let b = syntax::Builder::new();
+    assert_eq!(check(r"\A x. x", "(A : Type) -> A -> A", &b), Ok(()));
+    assert_eq!(
+        infer(r"\(A : Type) (x : A). x", &b).unwrap().to_string(),
+        "(a : Type) -> a -> a"
+    );
+    assert_eq!(
+        infer(r"(\(A : Type) (x : A). x) ((A : Type) -> A -> A)", &b)
+            .unwrap()
+            .to_string(),
+        "((a : Type) -> a -> a) -> (b : Type) -> b -> b"
+    );
+    assert_eq!(
+        infer("(A : Type) -> A -> A", &b).unwrap().to_string(),
+        "Type"
+    );
+}
+
+#[test]
+fn church_numerals() {
+    let b = syntax::Builder::new();
+    assert_eq!(check(r"\A f x. f (f x)", CHURCH, &b), Ok(()));
+    let mul = format!("({CHURCH}) -> ({CHURCH}) -> {CHURCH}");
+    assert_eq!(check(r"\m n A f x. m A (n A f) x", &mul, &b), Ok(()));
+    // Lets are transparent, so the inferred type mentions `Church` unfolded.
+    let program = format!(
+        r"let Church = {CHURCH} in
+          \(m n : Church) (A : Type) (f : A -> A) (x : A). m A (n A f) x"
+    );
+    assert_eq!(
+        format!("{:120}", infer(&program, &b).unwrap()),
+        "((a : Type) -> (a -> a) -> a -> a) -> ((b : Type) -> (b -> b) -> b -> b) -> \
+         (c : Type) -> (c -> c) -> c -> c"
+    );
+}
+
+#[test]
+fn dependent_types_compute() {
+    let b = syntax::Builder::new();
+    // The type of `x` is only `A` up to beta.
+    assert_eq!(
+        check(r"\A x. x", r"(A : Type) -> (\(B : Type). B) A -> A", &b),
+        Ok(())
+    );
+    assert_eq!(
+        check(
+            r"\F x. x",
+            r"(F : Type -> Type) -> F ((\(X : Type). X) Type) -> F Type",
+            &b
+        ),
+        Ok(())
+    );
+}
+
+#[test]
+fn errors() {
+    let b = syntax::Builder::new();
+    let error = check(r"\A x. A", "(A : Type) -> A -> A", &b).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "expected type x0, found Type",
+        "{error:?}"
+    );
+    let error = check(r"\A B x. x", "(A B : Type) -> A -> B", &b).unwrap_err();
+    assert_eq!(error.to_string(), "expected type x1, found x0");
+    let error = check(r"\A f. f f", "(A : Type) -> A -> A", &b).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "applied a term of type x0, which is not a function type"
+    );
+    assert_eq!(infer(r"\x. x", &b), Err(Error::CannotInfer));
+    let error = check(r"\(A : Type -> Type) x. x", "(A : Type) -> A -> A", &b).unwrap_err();
+    assert_eq!(error.to_string(), "expected type Type, found Type -> Type");
+}
+
+#[test]
+fn pairs() {
+    let b = syntax::Builder::new();
+    assert_eq!(
+        check(
+            r"\A B p. (snd p, fst p)",
+            "(A B : Type) -> A * B -> B * A",
+            &b
+        ),
+        Ok(())
+    );
+    // The type of the second component depends on the first.
+    let existential = "(A : Type) * (A -> A)";
+    assert_eq!(check(r"(Type, \x. x)", existential, &b), Ok(()));
+    assert_eq!(
+        check(
+            r"\p x. snd p (snd p x)",
+            &format!("(p : {existential}) -> fst p -> fst p"),
+            &b
+        ),
+        Ok(())
+    );
+    assert_eq!(
+        infer(&format!(r"\(p : {existential}). snd p"), &b)
+            .unwrap()
+            .to_string(),
+        "(a : (a : Type) * (a -> a)) -> fst a -> fst a"
+    );
+    let error = check(r"(Type, Type)", existential, &b).unwrap_err();
+    assert_eq!(error.to_string(), "expected type Type -> Type, found Type");
+    let error = check(r"\A x. fst x", "(A : Type) -> A -> A", &b).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "projected out of a term of type x0, which is not a pair type"
+    );
+    assert_eq!(infer(r"(Type, Type)", &b), Err(Error::CannotInfer));
+}
+
+#[test]
+fn naturals() {
+    let b = syntax::Builder::new();
+    let add = r"\m n. natElim (\_. Nat) n (\_ acc. suc acc) m";
+    assert_eq!(check(add, "Nat -> Nat -> Nat", &b), Ok(()));
+    // A vector type computed by recursion on its length.
+    let vec = r"(\(A : Type) (n : Nat). natElim (\_. Type) Type (\_ V. A * V) n)";
+    assert_eq!(
+        check(r"(0, (1, Type))", &format!("{vec} Nat 2"), &b),
+        Ok(())
+    );
+    assert_eq!(
+        check(
+            r"\n. natElim (\k. Nat) 0 (\k _. k) (suc n)",
+            r"Nat -> Nat",
+            &b
+        ),
+        Ok(())
+    );
+    let error = check(r"suc Type", "Nat", &b).unwrap_err();
+    assert_eq!(error.to_string(), "expected type Nat, found Type");
+    let error = check(
+        r"\n. natElim (\_. Nat) Type (\_ acc. acc) n",
+        "Nat -> Nat",
+        &b,
+    )
+    .unwrap_err();
+    assert_eq!(error.to_string(), "expected type Nat, found Type");
+}
+
+#[test]
+fn integers() {
+    let b = syntax::Builder::new();
+    assert_eq!(
+        infer(r"addI64", &b).unwrap().to_string(),
+        "I64 -> I64 -> I64"
+    );
+    assert_eq!(
+        infer(r"ltU64", &b).unwrap().to_string(),
+        "U64 -> U64 -> Nat"
+    );
+    assert_eq!(infer(r"mulU64 2u64", &b).unwrap().to_string(), "U64 -> U64");
+    assert_eq!(
+        check(
+            r"\x. natElim (\_. I64) x (\_ _. subI64 0i64 x) (ltI64 x 0i64)",
+            "I64 -> I64",
+            &b
+        ),
+        Ok(())
+    );
+    let error = check(r"addI64 1i64 1u64", "I64", &b).unwrap_err();
+    assert_eq!(error.to_string(), "expected type I64, found U64");
+    let error = check(r"eqU64 1u64 1u64", "U64", &b).unwrap_err();
+    assert_eq!(error.to_string(), "expected type U64, found Nat");
+}
+
+#[test]
+fn globals() {
+    let b = syntax::Builder::new();
+    let signature = parse_signature(
+        &format!(
+            r"Church : Type = {CHURCH};
+              two : Church = \A f x. f (f x);
+              mul : Church -> Church -> Church = \m n A f x. m A (n A f) x;
+              four = mul two two;"
+        ),
+        &b,
+    )
+    .unwrap();
+    let mut context = Context::with_signature(&signature, &b).unwrap();
+    // Types are shown by name, but unfolded to be checked against.
+    let four = parse_in("four", &signature, &b).unwrap();
+    let (_, ty) = context.infer(four).unwrap();
+    assert_eq!(context.quote(&ty).to_string(), "Church");
+    let term = parse_in(r"\(n : Church). n Nat (\k. suc k) 0", &signature, &b).unwrap();
+    let (_, ty) = context.infer(term).unwrap();
+    assert_eq!(context.quote(&ty).to_string(), "Church -> Nat");
+    let church = context.evaluate(parse_in("Church", &signature, &b).unwrap());
+    let term = parse_in(r"\A f x. f x", &signature, &b).unwrap();
+    assert!(context.check(term, &church).is_ok());
+    let term = parse_in(r"mul two", &signature, &b).unwrap();
+    assert_eq!(
+        context.check(term, &church).unwrap_err().to_string(),
+        "expected type Church, found Church -> Church"
+    );
+    let signature = parse_signature(r"n : Nat = Type;", &b).unwrap();
+    assert_eq!(
+        Context::with_signature(&signature, &b)
+            .err()
+            .unwrap()
+            .to_string(),
+        "expected type Nat, found Type"
+    );
+}
+
+#[test]
+fn metas_are_solved_by_checking() {
+    let b = syntax::Builder::new();
+    let mut context = Context::new(&b);
+    let meta = context.metas().fresh();
+    let ty = context.metas().value(meta);
+    let term = parse(r"\(A : Type) (x : A). x", &b).unwrap();
+    assert!(context.check(term, &ty).is_ok());
+    let solution = context.metas().value(meta);
+    assert_eq!(context.quote(&solution).to_string(), "(a : Type) -> a -> a");
+    // Now that it is solved, it is only equal to its solution.
+    let term = parse(r"\(A : Type) (x : A). A", &b).unwrap();
+    assert_eq!(
+        context.check(term, &ty).unwrap_err().to_string(),
+        "expected type x0, found Type"
+    );
+    let meta = context.metas().fresh();
+    let ty = b.pi(b.universe(), b.pi(b.meta(meta), b.v(1)));
+    let ty = context.evaluate(ty);
+    let term = parse(r"\(A : Type) (x : A -> A). x", &b).unwrap();
+    assert_eq!(
+        context.check(term, &ty).unwrap_err().to_string(),
+        "cannot unify type ?1 with x0 -> x0: \
+         the solution of ?1 would mention x0, which it is not applied to"
+    );
+}
+
+#[test]
+fn holes_report_their_goals() {
+    let b = syntax::Builder::new();
+    let term = parse(r"\A x. ?goal", &b).unwrap();
+    let ty = parse("(A : Type) -> A -> A", &b).unwrap();
+    let mut context = Context::new(&b);
+    let ty = context.evaluate(ty);
+    let term = context.check(term, &ty).unwrap();
+    assert_eq!(term.to_string(), r"\a b. ?0 a b");
+    let goals = context.goals();
+    assert_eq!(goals.len(), 1);
+    assert_eq!(goals[0].name, "goal");
+    assert_eq!(goals[0].to_string(), "?goal : A\n  A : Type\n  x : A");
+}
+
+#[test]
+fn checking_continues_past_holes() {
+    let b = syntax::Builder::new();
+    let term = parse(r"\(f : Nat -> Nat -> Nat). let y = ?v in f (suc y) ?w", &b).unwrap();
+    let mut context = Context::new(&b);
+    let (_, ty) = context.infer(term).unwrap();
+    assert_eq!(context.quote(&ty).to_string(), "(Nat -> Nat -> Nat) -> Nat");
+    // The type of `?v` was inferred as a meta, and solved by its use.
+    let goals: Vec<_> = context
+        .goals()
+        .iter()
+        .map(|goal| goal.to_string())
+        .collect();
+    assert_eq!(
+        goals,
+        [
+            "?v : Nat\n  f : Nat -> Nat -> Nat",
+            "?w : Nat\n  f : Nat -> Nat -> Nat\n  y : Nat",
+        ]
+    );
+    // Variables bound by arrows have no name in the source.
+    let term = parse(r"Nat -> ?t", &b).unwrap();
+    assert!(context.infer(term).is_ok());
+    assert_eq!(context.goals()[2].to_string(), "?t : Type\n  x0 : Nat");
+}
+
+#[test]
+fn implicit_arguments_are_inserted() {
+    let b = syntax::Builder::new();
+    let signature = parse_signature(
+        r"id : {A : Type} -> A -> A = \x. x;
+          const : {A B : Type} -> A -> B -> A = \{A} {B} x y. x;
+          Church : Type = {A : Type} -> (A -> A) -> A -> A;
+          two : Church = \f x. f (f x);
+          toNat : Church -> Nat = \n. n {Nat} (\k. suc k) 0;",
+        &b,
+    )
+    .unwrap();
+    let mut context = Context::with_signature(&signature, &b).unwrap();
+    // Implicit lambdas are inserted when checking against implicit function
+    // types, and implicit arguments are inserted when applying.
+    let definitions: Vec<_> = context
+        .definitions()
+        .iter()
+        .map(|definition| definition.term.to_string())
+        .collect();
+    assert_eq!(
+        definitions,
+        [
+            r"\{a} b. b",
+            r"\{a} {b} c d. c",
+            "{a : Type} -> (a -> a) -> a -> a",
+            r"\{a} b c. b (b c)",
+            r"\a. a {Nat} (\b. suc b) 0",
+        ]
+    );
+    let term = parse_in(r"toNat two", &signature, &b).unwrap();
+    let (term, ty) = context.infer(term).unwrap();
+    assert_eq!(context.quote(&ty).to_string(), "Nat");
+    assert_eq!(context.evaluate(term).quote(Level(0), &b).to_string(), "2");
+    // The metas are solved by unification.
+    let term = parse_in(r"const (id 2) Type", &signature, &b).unwrap();
+    let (term, ty) = context.infer(term).unwrap();
+    assert_eq!(context.quote(&ty).to_string(), "Nat");
+    let value = context.evaluate(term);
+    let keep_globals = QuoteOptions {
+        keep_globals: true,
+        ..QuoteOptions::default()
+    };
+    assert_eq!(
+        value.quote_with(Level(0), &b, keep_globals).to_string(),
+        "const {Nat} {Type} (id {Nat} 2) Type"
+    );
+    let term = parse_in(r"\(x : Nat). const x", &signature, &b).unwrap();
+    let (term, _) = context.infer(term).unwrap();
+    let value = context.evaluate(term);
+    assert_eq!(
+        value.quote_with(Level(0), &b, keep_globals).to_string(),
+        r"\a. const {Nat} {?5 a} a"
+    );
+    // Readback can leave the implicit arguments out.
+    let hide_implicits = QuoteOptions {
+        keep_globals: true,
+        hide_implicits: true,
+        eta_contract: false,
+    };
+    assert_eq!(
+        value.quote_with(Level(0), &b, hide_implicits).to_string(),
+        r"\a. const a"
+    );
+    // Explicit implicit arguments are checked against the domain.
+    let term = parse_in(r"id {Nat} Type", &signature, &b).unwrap();
+    assert_eq!(
+        context.infer(term).err().unwrap().to_string(),
+        "expected type Nat, found Type"
+    );
+    let term = parse_in(r"toNat {Nat}", &signature, &b).unwrap();
+    assert_eq!(
+        context.infer(term).err().unwrap().to_string(),
+        "applied a term of type Church -> Nat to an implicit argument, \
+         which is not an implicit function type"
+    );
+}
+
+#[test]
+fn goals_under_inserted_lambdas() {
+    let b = syntax::Builder::new();
+    let mut context = Context::new(&b);
+    let ty = context.evaluate(parse(r"{A : Type} -> A -> A", &b).unwrap());
+    let term = parse(r"\x. ?g", &b).unwrap();
+    let term = context.check(term, &ty).unwrap();
+    assert_eq!(term.to_string(), r"\{a} b. ?0 a b");
+    assert_eq!(
+        context.goals()[0].to_string(),
+        "?g : x0\n  x0 : Type\n  x : x0"
+    );
+}
+
+#[test]
+fn datatypes_and_case_analysis() {
+    let b = syntax::Builder::new();
+    let signature = parse_signature(
+        r"data Bool = true | false;
+          data List (A : Type) = nil | cons A (List A);
+          not : Bool -> Bool = \b. case b { true => false; false => true };
+          head : {A : Type} -> A -> List A -> A = \d xs. case xs { nil => d; cons x ys => x };",
+        &b,
+    )
+    .unwrap();
+    let mut context = Context::with_signature(&signature, &b).unwrap();
+    // Constructors take the parameters of their datatype implicitly.
+    let term = parse_in(r"cons 1 nil", &signature, &b).unwrap();
+    let (term, ty) = context.infer(term).unwrap();
+    assert_eq!(term.to_string(), "cons {?0} 1 (nil {?1})");
+    assert_eq!(
+        context.evaluate(term).quote(Level(0), &b).to_string(),
+        "cons {Nat} 1 (nil {Nat})"
+    );
+    assert_eq!(context.quote(&ty).to_string(), "List Nat");
+    let term = parse_in(r"head 0 (cons 1 nil)", &signature, &b).unwrap();
+    let (term, ty) = context.infer(term).unwrap();
+    assert_eq!(context.quote(&ty).to_string(), "Nat");
+    assert_eq!(context.evaluate(term).quote(Level(0), &b).to_string(), "1");
+    // The type of a case analysis is inferred from its branches.
+    let term = parse_in(
+        r"\(b : Bool). case b { true => 0; false => 1 }",
+        &signature,
+        &b,
+    )
+    .unwrap();
+    let (_, ty) = context.infer(term).unwrap();
+    assert_eq!(context.quote(&ty).to_string(), "Bool -> Nat");
+    // The fields of each branch are bound with the types of the fields of its
+    // constructor, at the parameters of the scrutinee.
+    let term = parse_in(
+        r"\(xs : List Nat). case xs { nil => 0; cons x ys => ?h }",
+        &signature,
+        &b,
+    )
+    .unwrap();
+    assert!(context.infer(term).is_ok());
+    assert_eq!(
+        context.goals()[0].to_string(),
+        "?h : Nat\n  xs : List Nat\n  x : Nat\n  ys : List Nat"
+    );
+    let errors = [
+        (
+            r"\(b : Bool). case b { true => 0 }",
+            "missing a branch for constructor false",
+        ),
+        (
+            r"\(b : Bool). case b { true => 0; true => 1; false => 2 }",
+            "unexpected branch for constructor true, which is covered already \
+             or is of another datatype",
+        ),
+        (
+            r"\(b : Bool). case b { nil => 0; true => 1; false => 2 }",
+            "unexpected branch for constructor nil, which is covered already \
+             or is of another datatype",
+        ),
+        (
+            r"\(b : Bool). case b { true x => 0; false => 1 }",
+            "constructor true has 0 fields, but its branch binds 1",
+        ),
+        (
+            r"case 0 { true => 0; false => 1 }",
+            "analysed a term of type Nat by cases, which is not a datatype",
+        ),
+        (
+            r"\(b : Bool). case b { true => 0; false => Type }",
+            "expected type Nat, found Type",
+        ),
+    ];
+    for (input, expected) in errors {
+        let term = parse_in(input, &signature, &b).unwrap();
+        assert_eq!(
+            context.infer(term).err().unwrap().to_string(),
+            expected,
+            "{input}"
+        );
+    }
+    // The fields of constructors must be types.
+    let signature = parse_signature(r"data Bad = bad 0;", &b).unwrap();
+    assert_eq!(
+        Context::with_signature(&signature, &b)
+            .err()
+            .unwrap()
+            .to_string(),
+        "expected type Type, found Nat"
+    );
+}
+
+#[test]
+fn records() {
+    let b = syntax::Builder::new();
+    let monoid = "sig { A : Type; unit : A; op : A -> A -> A }";
+    assert_eq!(
+        check(r"struct { A = Nat; unit = 0; op = \x y. x }", monoid, &b),
+        Ok(())
+    );
+    // The types of the fields after the first depend on the ones before.
+    assert_eq!(
+        infer(&format!(r"\(m : {monoid}). m.op m.unit"), &b)
+            .unwrap()
+            .to_string(),
+        format!("(a : {monoid}) -> a.A -> a.A")
+    );
+    // A record literal is given a record type of its fields' types.
+    assert_eq!(
+        infer(r"struct { x = 1; y = Nat }", &b).unwrap().to_string(),
+        "sig { x : Nat; y : Type }"
+    );
+    assert_eq!(
+        infer(r"\(A : Type) (a : A). struct { x = a; y = a }.y", &b)
+            .unwrap()
+            .to_string(),
+        "(a : Type) -> a -> a"
+    );
+    let errors = [
+        (
+            r"struct { A = Nat; unit = Type; op = \x y. x }",
+            monoid,
+            "expected type Nat, found Type",
+        ),
+        (
+            r"struct { A = Nat; op = \x y. x; unit = 0 }",
+            monoid,
+            "cannot infer the type of an unannotated lambda, a pair or a meta",
+        ),
+        (
+            r"\(m : sig { x : Nat }). m.y",
+            "sig { x : Nat } -> Nat",
+            "projected field y out of a term of type sig { x : Nat }, \
+             which is not a record type with that field",
+        ),
+        (
+            r"\(n : Nat). n.x",
+            "Nat -> Nat",
+            "projected field x out of a term of type Nat, \
+             which is not a record type with that field",
+        ),
+        ("sig { x : Nat; x : Nat }", "Type", "duplicate field x"),
+        (
+            "struct { x = 0; x = 1 }",
+            "sig { x : Nat }",
+            "duplicate field x",
+        ),
+    ];
+    for (term, ty, expected) in errors {
+        assert_eq!(
+            check(term, ty, &b).err().unwrap().to_string(),
+            expected,
+            "{term}"
+        );
+    }
+    // Records are compared field by field, and with neutrals by eta.
+    let point = "sig { x : Nat; y : Nat }";
+    assert_eq!(
+        check(
+            r"\r P p. p",
+            &format!(
+                "(r : {point}) -> (P : {point} -> Type) -> P r -> P struct {{ x = r.x; y = r.y }}"
+            ),
+            &b
+        ),
+        Ok(())
+    );
+}
@@ -0,0 +1,103 @@
+#![allow(dead_code)]
+
+use rixty::{
+    index::Index,
+    syntax::{self, Term, TermRef},
+};
+
+/// The Church-numeral and tree workloads from the benchmarks, at sizes that
+/// are quick to normalize in a debug build.
+pub struct Workloads<'a> {
+    pub n2: TermRef<'a>,
+    pub n5: TermRef<'a>,
+    pub mul: TermRef<'a>,
+    pub n10: TermRef<'a>,
+    pub n10b: TermRef<'a>,
+    pub n20: TermRef<'a>,
+    pub n21: TermRef<'a>,
+    pub n100: TermRef<'a>,
+    pub n1k: TermRef<'a>,
+    pub tree_1k: TermRef<'a>,
+    pub tree_2k: TermRef<'a>,
+}
+
+impl<'a> Workloads<'a> {
+    pub fn new(b: &'a syntax::Builder) -> Self {
+        let v0 = b.v(0);
+        let v1 = b.v(1);
+        let v2 = b.v(2);
+        let v3 = b.v(3);
+        let n2 = numeral(b, 2);
+        let n5 = numeral(b, 5);
+        let mul = b.l(b.l(b.l(b.l(b.apps(v3, &[b.application(v2, v1), v0])))));
+        let suc = |n| b.l(b.l(b.application(v1, b.apps(n, &[v1, v0]))));
+
+        let n10 = b.apps(mul, &[n2, n5]);
+        let n10b = b.apps(mul, &[n5, n2]);
+        let n20 = b.apps(mul, &[n2, n10]);
+        let n21 = suc(n20);
+        let n100 = b.apps(mul, &[n10, n10b]);
+        let n1k = b.apps(mul, &[n100, n10]);
+        let leaf = b.l(b.l(v1));
+        let node = b.l(b.l(b.l(b.l(b.apps(v0, &[b.apps(v3, &[v1, v0]), b.apps(v2, &[v1, v0])])))));
+        let full_tree = b.l(b.apps(v0, &[b.l(b.apps(node, &[v0, v0])), leaf]));
+        let tree_1k = b.application(full_tree, n10);
+        let tree_2k = b.application(full_tree, suc(n10));
+
+        Workloads {
+            n2,
+            n5,
+            mul,
+            n10,
+            n10b,
+            n20,
+            n21,
+            n100,
+            n1k,
+            tree_1k,
+            tree_2k,
+        }
+    }
+}
+
+/// Builds the Church numeral `\f x. f (f ... x)` directly.
+pub fn numeral<'a>(b: &'a syntax::Builder, n: usize) -> TermRef<'a> {
+    let body = (0..n).fold(b.v(0), |body, _| b.application(b.v(1), body));
+    b.l(b.l(body))
+}
+
+/// Reads a Church numeral `\f x. f (f ... x)` back into a number.
+pub fn to_nat(term: TermRef<'_>) -> Option<usize> {
+    match term {
+        Term::Lambda(_, _, Term::Lambda(_, _, body)) => {
+            let mut result = 0;
+            let mut body = *body;
+            while let Term::Application(_, Term::Variable(Index(1)), argument) = body {
+                result += 1;
+                body = argument;
+            }
+            match body {
+                Term::Variable(Index(0)) => Some(result),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Counts the leaves of a Church-encoded binary tree `\leaf node. ...`.
+pub fn tree_leaves(term: TermRef<'_>) -> Option<usize> {
+    fn go(term: TermRef<'_>) -> Option<usize> {
+        match term {
+            Term::Variable(Index(1)) => Some(1),
+            Term::Application(_, Term::Application(_, Term::Variable(Index(0)), left), right) => {
+                Some(go(left)? + go(right)?)
+            }
+            _ => None,
+        }
+    }
+    match term {
+        Term::Lambda(_, _, Term::Lambda(_, _, body)) => go(body),
+        _ => None,
+    }
+}
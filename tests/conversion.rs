@@ -0,0 +1,251 @@
+mod common;
+
+use common::Workloads;
+use rixty::{
+    domain, domain_rc,
+    index::Level,
+    meta::MetaContext,
+    parse::{parse_in, parse_open, parse_signature},
+    syntax::{self, TermRef},
+};
+
+fn conv_rc(context: usize, term1: TermRef<'_>, term2: TermRef<'_>) -> bool {
+    let value1 = term1.evaluate_rc(&mut domain_rc::Environment::free_variables(context));
+    let value2 = term2.evaluate_rc(&mut domain_rc::Environment::free_variables(context));
+    domain_rc::Value::conv(Level(context), &value1, &value2)
+}
+
+fn conv_arena(context: usize, term1: TermRef<'_>, term2: TermRef<'_>) -> bool {
+    let builder = domain::Builder::new();
+    let value1 = term1.evaluate(
+        &mut domain::Environment::free_variables(context, &builder),
+        &builder,
+    );
+    let value2 = term2.evaluate(
+        &mut domain::Environment::free_variables(context, &builder),
+        &builder,
+    );
+    domain::Value::conv(Level(context), value1, value2, &builder)
+}
+
+fn assert_conv(context: &[&str], input1: &str, input2: &str, expected: bool) {
+    let b = syntax::Builder::new();
+    let term1 = parse_open(input1, context, &b).unwrap();
+    let term2 = parse_open(input2, context, &b).unwrap();
+    assert_eq!(
+        conv_rc(context.len(), term1, term2),
+        expected,
+        "domain_rc: {input1} = {input2}"
+    );
+    assert_eq!(
+        conv_arena(context.len(), term1, term2),
+        expected,
+        "domain: {input1} = {input2}"
+    );
+}
+
+#[test]
+fn commuted_multiplication() {
+    let b = syntax::Builder::new();
+    let w = Workloads::new(&b);
+    assert!(conv_rc(0, w.n10, w.n10b));
+    assert!(conv_arena(0, w.n10, w.n10b));
+    assert!(conv_rc(0, w.n100, b.apps(w.mul, &[w.n10b, w.n10])));
+    assert!(!conv_rc(0, w.n10, w.n20));
+    assert!(!conv_arena(0, w.n20, w.n21));
+}
+
+#[test]
+fn beta() {
+    assert_conv(&["f", "x"], r"(\y. f y y) x", r"f x x", true);
+    assert_conv(&["f", "x"], r"(\y. f y y) x", r"f x f", false);
+}
+
+#[test]
+fn eta() {
+    assert_conv(&["f"], r"\x. f x", r"f", true);
+    assert_conv(&["f"], r"f", r"\x. f x", true);
+    assert_conv(&["f"], r"\x y. f x y", r"f", true);
+    assert_conv(&["f"], r"\x. \y. f y x", r"f", false);
+    assert_conv(&[], r"\f x. f x", r"\f. f", true);
+}
+
+#[test]
+fn implicit_functions() {
+    assert_conv(&["f"], r"\{x}. f {x}", r"f", true);
+    assert_conv(&["f"], r"f", r"\{x}. f {x}", true);
+    assert_conv(&["A"], r"{x : A} -> A", r"{y : A} -> A", true);
+    assert_conv(&["A"], r"{x : A} -> A", r"A -> A", false);
+}
+
+#[test]
+fn neutral_mismatches() {
+    assert_conv(&["f", "g", "x"], r"f x", r"g x", false);
+    assert_conv(&["f", "x"], r"f x", r"f x x", false);
+    assert_conv(&["f", "x", "y"], r"f x", r"f y", false);
+}
+
+#[test]
+fn holes() {
+    assert_conv(&["x"], r"?h x", r"(\y. y) (?h x)", true);
+    assert_conv(&["x"], r"?h x", r"?g x", false);
+    assert_conv(&[], r"?h", r"Type", false);
+}
+
+#[test]
+fn function_types() {
+    assert_conv(&["A"], r"(x : A) -> A", r"A -> A", true);
+    assert_conv(
+        &["F"],
+        r"(A : Type) -> F A",
+        r"(B : Type) -> (\X. F X) B",
+        true,
+    );
+    assert_conv(&["A", "B"], r"A -> B", r"B -> A", false);
+    assert_conv(&["A"], r"(B : Type) -> B", r"(B : Type) -> A", false);
+    assert_conv(&[], r"Type", r"Type -> Type", false);
+    assert_conv(&["f"], r"\x. f x", r"Type", false);
+    // Types applied to arguments are stuck, and compared as written.
+    assert_conv(&["x"], r"Type x", r"(\A. A) Type x", true);
+    assert_conv(&["x", "y"], r"Type x", r"Type y", false);
+    assert_conv(&["x"], r"Type x", r"(Type -> Type) x", false);
+}
+
+#[test]
+fn pairs() {
+    let b = syntax::Builder::new();
+    let conv = |context: &[&str], input1, input2| {
+        let term1 = parse_open(input1, context, &b).unwrap();
+        let term2 = parse_open(input2, context, &b).unwrap();
+        let result = conv_rc(context.len(), term1, term2);
+        assert_eq!(conv_arena(context.len(), term1, term2), result);
+        result
+    };
+    assert!(conv(&["p"], r"p", r"(fst p, snd p)"));
+    assert!(conv(&["p"], r"(fst p, snd p)", r"p"));
+    assert!(conv(&[], r"\p. (fst p, snd p)", r"\p. p"));
+    assert!(conv(&["p"], r"fst (snd p, p)", r"snd (fst (p, p))"));
+    assert!(!conv(&["p"], r"p", r"(snd p, fst p)"));
+    assert!(!conv(&["p"], r"fst p", r"snd p"));
+    assert!(conv(&["A"], r"(x : A) * A", r"A * A"));
+    assert!(!conv(&["A"], r"A * A", r"A -> A"));
+    assert!(conv(&[], r"fst (\x. x)", r"fst (\y. (\z. z) y)"));
+    assert!(!conv(&[], r"fst Type", r"snd Type"));
+}
+
+#[test]
+fn naturals() {
+    let b = syntax::Builder::new();
+    let conv = |context: &[&str], input1: &str, input2: &str| {
+        let term1 = parse_open(input1, context, &b).unwrap();
+        let term2 = parse_open(input2, context, &b).unwrap();
+        let result = conv_rc(context.len(), term1, term2);
+        assert_eq!(conv_arena(context.len(), term1, term2), result);
+        result
+    };
+    assert!(conv(&[], r"suc (suc zero)", r"2"));
+    assert!(!conv(&[], r"suc 2", r"2"));
+    assert!(conv(&["n"], r"suc (suc n)", r"suc (suc n)"));
+    assert!(!conv(&["n"], r"suc n", r"n"));
+    assert!(!conv(&["n"], r"suc n", r"1"));
+    let add = r"(\m n. natElim (\_. Nat) n (\_ acc. suc acc) m)";
+    assert!(conv(&["n"], &format!("{add} 2 n"), r"suc (suc n)"));
+    assert!(conv(
+        &["n"],
+        &format!("{add} n 2"),
+        r"natElim (\_. Nat) 2 (\k acc. suc acc) n"
+    ));
+    assert!(!conv(&["n"], &format!("{add} n 2"), r"suc (suc n)"));
+}
+
+#[test]
+fn primitives() {
+    let b = syntax::Builder::new();
+    let conv = |context: &[&str], input1: &str, input2: &str| {
+        let term1 = parse_open(input1, context, &b).unwrap();
+        let term2 = parse_open(input2, context, &b).unwrap();
+        let result = conv_rc(context.len(), term1, term2);
+        assert_eq!(conv_arena(context.len(), term1, term2), result);
+        result
+    };
+    assert!(conv(&[], r"addI64 2i64 2i64", r"mulI64 2i64 2i64"));
+    assert!(!conv(&[], r"4i64", r"4u64"));
+    assert!(!conv(&[], r"4u64", r"4"));
+    assert!(conv(
+        &["x"],
+        r"addU64 x (addU64 1u64 1u64)",
+        r"addU64 x 2u64"
+    ));
+    assert!(!conv(&["x"], r"addU64 x 2u64", r"addU64 2u64 x"));
+    assert!(!conv(&["x"], r"addU64 x 2u64", r"mulU64 x 2u64"));
+    assert!(conv(&[], r"addI64", r"\x y. addI64 x y"));
+    assert!(conv(&[], r"\x. addI64 1i64 x", r"addI64 1i64"));
+}
+
+#[test]
+fn successors_of_globals() {
+    let b = syntax::Builder::new();
+    let signature = parse_signature(r"n2 = 2; three = suc n2;", &b).unwrap();
+    let conv = |input1, input2| {
+        let term1 = parse_in(input1, &signature, &b).unwrap();
+        let term2 = parse_in(input2, &signature, &b).unwrap();
+        let mut environment = domain_rc::Environment::new().with_signature(&signature);
+        let value1 = term1.evaluate_rc(&mut environment);
+        let value2 = term2.evaluate_rc(&mut environment);
+        let result = domain_rc::Value::conv(Level(0), &value1, &value2);
+        let builder = domain::Builder::new();
+        let mut environment = domain::Environment::new().with_signature(&signature, &builder);
+        let value1 = term1.evaluate(&mut environment, &builder);
+        let value2 = term2.evaluate(&mut environment, &builder);
+        assert_eq!(
+            domain::Value::conv(Level(0), value1, value2, &builder),
+            result
+        );
+        result
+    };
+    assert!(conv(r"3", r"suc n2"));
+    assert!(conv(r"suc n2", r"3"));
+    assert!(conv(r"4", r"suc three"));
+    assert!(conv(r"suc (suc n2)", r"suc three"));
+    assert!(!conv(r"suc n2", r"2"));
+    assert!(!conv(r"0", r"suc n2"));
+}
+
+#[test]
+fn successors_of_solved_metas() {
+    let b = syntax::Builder::new();
+    let metas = MetaContext::new(&b);
+    let two = metas.fresh();
+    let value = b
+        .meta(two)
+        .evaluate_rc(&mut domain_rc::Environment::new().with_metas(&metas));
+    metas
+        .unify(
+            Level(0),
+            &value,
+            &b.natural(2).evaluate_rc(&mut domain_rc::Environment::new()),
+        )
+        .unwrap();
+    let two = b.meta(two);
+    fn conv<'a>(metas: &MetaContext<'a>, term1: TermRef<'a>, term2: TermRef<'a>) -> bool {
+        let mut environment = domain_rc::Environment::new().with_metas(metas);
+        let value1 = term1.evaluate_rc(&mut environment);
+        let value2 = term2.evaluate_rc(&mut environment);
+        let result = domain_rc::Value::conv(Level(0), &value1, &value2);
+        let builder = domain::Builder::new();
+        let mut environment = domain::Environment::new().with_metas(metas, &builder);
+        let value1 = term1.evaluate(&mut environment, &builder);
+        let value2 = term2.evaluate(&mut environment, &builder);
+        assert_eq!(
+            domain::Value::conv(Level(0), value1, value2, &builder),
+            result
+        );
+        result
+    }
+    let conv = |term1, term2| conv(&metas, term1, term2);
+    assert!(conv(b.natural(3), b.suc(two)));
+    assert!(conv(b.suc(two), b.natural(3)));
+    assert!(conv(b.suc(b.suc(two)), b.natural(4)));
+    assert!(!conv(b.suc(two), two));
+    assert!(!conv(b.zero(), b.suc(two)));
+}
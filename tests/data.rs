@@ -0,0 +1,128 @@
+use rixty::{
+    domain,
+    domain_rc::{self, Environment, QuoteOptions, SignatureBackend, Value},
+    index::Level,
+    normalizer::Normalizer,
+    parse::{parse_in, parse_signature},
+    reference,
+    signature::Signature,
+    syntax::{self, TermRef},
+};
+
+const DATATYPES: &str = r"
+    data Bool = true | false;
+    data List (A : Type) = nil | cons A (List A);
+    data Pair (A B : Type) = pair A B;
+    not = \b. case b { true => false; false => true };
+    head = \d xs. case xs { nil => d; cons x ys => x };
+";
+
+fn evaluate<'a>(signature: &'a Signature<'a>, term: TermRef<'a>) -> rclite::Rc<Value<'a>> {
+    term.evaluate_rc(&mut Environment::new().with_signature(signature))
+}
+
+#[test]
+fn case_analysis_selects_the_branch_of_the_constructor() {
+    let b = syntax::Builder::new();
+    let result_builder = syntax::Builder::new();
+    let signature = parse_signature(DATATYPES, &b).unwrap();
+    for (input, expected) in [
+        (r"not (not true)", "true"),
+        (r"head 0 (cons 1 (cons 2 nil))", "1"),
+        (r"head 0 nil", "0"),
+        (r"case (pair 1 2) { pair x y => y }", "2"),
+        (r"\xs. head 0 (cons 3 xs)", r"\a. 3"),
+        (r"\b. not b", r"\a. case a { true => false; false => true }"),
+        // Case analysis on a value that is not of a datatype is stuck.
+        (r"not Type", r"case Type { true => false; false => true }"),
+    ] {
+        let term = parse_in(input, &signature, &b).unwrap();
+        let actual = SignatureBackend {
+            signature: &signature,
+        }
+        .normalize(term, &result_builder);
+        let arena = domain::SignatureBackend {
+            signature: &signature,
+        }
+        .normalize(term, &result_builder);
+        let oracle = reference::Backend.normalize(signature.inline(term, &b), &result_builder);
+        assert_eq!(actual.to_string(), expected, "{input}");
+        assert_eq!(actual, oracle, "{input}");
+        assert_eq!(arena, oracle, "{input}");
+    }
+}
+
+#[test]
+fn stuck_case_analyses_keep_their_frames() {
+    let b = syntax::Builder::new();
+    let signature = parse_signature(DATATYPES, &b).unwrap();
+    let keep_globals = QuoteOptions {
+        keep_globals: true,
+        ..QuoteOptions::default()
+    };
+    // The application after the case analysis waits on the spine with it.
+    let term = parse_in(r"\p. case p { pair x y => \z. x } 5", &signature, &b).unwrap();
+    let value = evaluate(&signature, term);
+    assert_eq!(
+        value.quote_with(Level(0), &b, keep_globals).to_string(),
+        r"\a. case a { pair b c => \d. b } 5"
+    );
+    // Once the scrutinee is known, the branch is selected and applied.
+    let pair = evaluate(&signature, parse_in(r"pair 1 2", &signature, &b).unwrap());
+    let value = domain_rc::apply(&value, pair);
+    assert_eq!(value.quote(Level(0), &b).to_string(), "1");
+    // Branches are read back under binders for the fields.
+    let term = parse_in(
+        r"\xs. case xs { nil => xs; cons x ys => ys }",
+        &signature,
+        &b,
+    )
+    .unwrap();
+    let value = evaluate(&signature, term);
+    assert_eq!(value.quote(Level(0), &b), term);
+}
+
+#[test]
+fn conversion_compares_stuck_case_analyses() {
+    let b = syntax::Builder::new();
+    let signature = parse_signature(DATATYPES, &b).unwrap();
+    let conv = |input1, input2| {
+        let term1 = parse_in(input1, &signature, &b).unwrap();
+        let term2 = parse_in(input2, &signature, &b).unwrap();
+        let value1 = evaluate(&signature, term1);
+        let value2 = evaluate(&signature, term2);
+        let result = Value::conv(Level(0), &value1, &value2);
+        let builder = domain::Builder::new();
+        let mut environment = domain::Environment::new().with_signature(&signature, &builder);
+        let value1 = term1.evaluate(&mut environment.clone(), &builder);
+        let value2 = term2.evaluate(&mut environment, &builder);
+        assert_eq!(
+            domain::Value::conv(Level(0), value1, value2, &builder),
+            result,
+            "{input1} and {input2}"
+        );
+        result
+    };
+    assert!(conv(
+        r"\b. not b",
+        r"\b. case b { true => false; false => true }"
+    ));
+    assert!(conv(r"\b. not (not b)", r"\b. not (not b)"));
+    assert!(!conv(
+        r"\b. not b",
+        r"\b. case b { true => true; false => false }"
+    ));
+    assert!(!conv(
+        r"\b. not b",
+        r"\b. case b { false => true; true => false }"
+    ));
+    assert!(conv(
+        r"\xs. head 0 xs",
+        r"\xs. case xs { nil => 0; cons x ys => x }"
+    ));
+    assert!(!conv(
+        r"\xs. head 0 xs",
+        r"\xs. case xs { nil => 0; cons x ys => 0 }"
+    ));
+    assert!(!conv(r"\b. not b", r"\b. b"));
+}
@@ -0,0 +1,560 @@
+mod common;
+
+use common::Workloads;
+use rixty::{
+    domain, domain_rc,
+    index::Level,
+    meta::MetaContext,
+    normalizer::Normalizer,
+    parse::{parse, parse_in, parse_signature},
+    reference,
+    syntax::{self, Plicity, TermRef},
+};
+
+const BACKENDS: &[(&str, &dyn Normalizer)] = &[
+    ("domain", &domain::Backend),
+    ("domain_rc", &domain_rc::Backend),
+    ("domain_rc (call-by-need)", &domain_rc::LazyBackend),
+];
+
+/// Terms exercising shadowing, variable capture, partial application and
+/// duplication, alongside the numeral and tree workloads.
+fn corpus<'a>(b: &'a syntax::Builder) -> Vec<(&'static str, TermRef<'a>)> {
+    let w = Workloads::new(b);
+    let v0 = b.v(0);
+    let v1 = b.v(1);
+    let v2 = b.v(2);
+    let id = b.l(v0);
+    let k = b.l(b.l(v1));
+    let s = b.l(b.l(b.l(b.apps(v2, &[v0, b.application(v1, v0)]))));
+    let flip = b.l(b.l(b.l(b.apps(v2, &[v0, v1]))));
+    // The numerals used by the benchmarks, which apply `f` to itself.
+    let self_n2 = b.l(b.l(b.apps(v1, &[v1, v0])));
+    let self_n5 = b.l(b.l(b.apps(v1, &[v1, v1, v1, v1, v0])));
+
+    vec![
+        ("id", id),
+        ("id id", b.application(id, id)),
+        ("k", k),
+        ("k id", b.application(k, id)),
+        ("s k k", b.apps(s, &[k, k])),
+        ("s k", b.application(s, k)),
+        ("flip k", b.application(flip, k)),
+        ("flip flip", b.application(flip, flip)),
+        // \x. (\y z. y) x: the inner binder must not capture `x`.
+        ("capture", b.l(b.application(k, v0))),
+        // \x. (\y. \x. y x) x
+        (
+            "shadowing",
+            b.l(b.application(b.l(b.l(b.application(v1, v0))), v0)),
+        ),
+        // \f. (\x. f (x x)) (\y. y): duplicates a neutral application.
+        (
+            "duplication",
+            b.l(b.application(b.l(b.application(v1, b.application(v0, v0))), id)),
+        ),
+        ("let", b.let_in(id, b.application(v0, v0))),
+        (
+            "let two",
+            b.let_in(w.n2, b.apps(w.mul, &[v0, b.apps(w.mul, &[v0, v0])])),
+        ),
+        // \x. let y = x in \x. y x: the let is not captured by the inner binder.
+        ("let capture", b.l(b.let_in(v0, b.l(b.application(v1, v0))))),
+        // (let k = \x y. x in k) applied to two arguments.
+        ("let spine", b.apps(b.let_in(k, v0), &[id, k])),
+        (
+            "let argument",
+            b.application(s, b.let_in(k, b.application(v0, v0))),
+        ),
+        ("n2", w.n2),
+        ("n5", w.n5),
+        ("mul", w.mul),
+        ("mul n2", b.application(w.mul, w.n2)),
+        ("n10", w.n10),
+        ("n10b", w.n10b),
+        ("n20", w.n20),
+        ("n21", w.n21),
+        ("n100", w.n100),
+        ("tree 1k", w.tree_1k),
+        ("self n2", self_n2),
+        ("mul self-n2 self-n5", b.apps(w.mul, &[self_n2, self_n5])),
+        ("mul self-n5 self-n2", b.apps(w.mul, &[self_n5, self_n2])),
+        // (\(A : Type) (x : A). x) Type: annotations are dropped.
+        (
+            "typed id",
+            b.application(
+                b.annotated_lambda(b.universe(), b.annotated_lambda(v0, v0)),
+                b.universe(),
+            ),
+        ),
+        // (A : Type) -> (\x. x) A -> A
+        ("pi", b.pi(b.universe(), b.pi(b.application(id, v0), v1))),
+        // (\{A} (x : A). x) {Type} Type
+        (
+            "implicit application",
+            b.application(
+                b.application_with(
+                    Plicity::Implicit,
+                    b.lambda_with(Plicity::Implicit, None, b.annotated_lambda(v0, v0)),
+                    b.universe(),
+                ),
+                b.universe(),
+            ),
+        ),
+        // \f. f {Type} (\{A}. A): implicit arguments in a neutral spine.
+        (
+            "implicit spine",
+            b.l(b.application(
+                b.application_with(Plicity::Implicit, v0, b.universe()),
+                b.lambda_with(Plicity::Implicit, None, v0),
+            )),
+        ),
+        // {A : Type} -> (\x. x) A -> A
+        (
+            "implicit pi",
+            b.pi_with(
+                Plicity::Implicit,
+                b.universe(),
+                b.pi(b.application(id, v0), v1),
+            ),
+        ),
+        // Ill-typed eliminations are stuck and kept as they are written.
+        ("applied type", b.application(b.universe(), id)),
+        (
+            "applied pi",
+            b.apps(
+                b.pi(b.universe(), v0),
+                &[b.universe(), b.application(id, id)],
+            ),
+        ),
+        // \f. (\g. g f) Type
+        (
+            "type in head position",
+            b.l(b.application(b.l(b.application(v0, v1)), b.universe())),
+        ),
+    ]
+}
+
+fn pair_corpus<'a>(b: &'a syntax::Builder) -> Vec<(&'static str, TermRef<'a>)> {
+    let w = Workloads::new(b);
+    let v0 = b.v(0);
+    let v1 = b.v(1);
+    let id = b.l(v0);
+    let k = b.l(b.l(v1));
+    let swap = b.l(b.pair(b.snd(v0), b.fst(v0)));
+    vec![
+        ("fst", b.fst(b.pair(id, k))),
+        ("snd", b.snd(b.pair(id, k))),
+        ("swap", b.application(swap, b.pair(k, id))),
+        (
+            "swap swap",
+            b.l(b.application(swap, b.application(swap, v0))),
+        ),
+        // \p. fst p id (snd p): projections in a neutral spine.
+        (
+            "neutral projections",
+            b.l(b.apps(b.fst(v0), &[id, b.snd(v0)])),
+        ),
+        // (\p. snd p) (n2, mul) n2 n2: a projection in head position.
+        (
+            "projected function",
+            b.apps(
+                b.application(b.l(b.snd(v0)), b.pair(w.n2, w.mul)),
+                &[w.n2, w.n2],
+            ),
+        ),
+        ("pair of numerals", b.pair(w.n10, b.pair(w.n10b, w.n20))),
+        // (A : Type) * (\x. x) A
+        ("sigma", b.sigma(b.universe(), b.application(id, v0))),
+        ("projected lambda", b.fst(id)),
+        ("projected type", b.snd(b.universe())),
+        ("applied pair", b.apps(b.pair(id, k), &[id, k])),
+    ]
+}
+
+/// Terms over the built-in naturals.
+fn nat_corpus<'a>(b: &'a syntax::Builder) -> Vec<(&'static str, TermRef<'a>)> {
+    let v0 = b.v(0);
+    let v1 = b.v(1);
+    let v2 = b.v(2);
+    let constant_nat = b.l(b.nat());
+    // \m n. natElim (\_. Nat) n (\_ acc. suc acc) m
+    let add = b.l(b.l(b.nat_elim(constant_nat, v0, b.l(b.l(b.suc(v0))), v1)));
+    // \m n. natElim (\_. Nat) 0 (\_ acc. add n acc) m
+    let mul = b.l(b.l(b.nat_elim(
+        constant_nat,
+        b.natural(0),
+        b.l(b.l(b.apps(add, &[v2, v0]))),
+        v1,
+    )));
+    vec![
+        ("zero", b.zero()),
+        ("suc suc zero", b.suc(b.suc(b.zero()))),
+        ("suc literal", b.suc(b.natural(41))),
+        ("suc largest literal", b.suc(b.natural(u64::MAX))),
+        ("add", b.apps(add, &[b.natural(3), b.natural(4)])),
+        ("mul", b.apps(mul, &[b.natural(6), b.suc(b.natural(6))])),
+        ("add neutral", b.l(b.apps(add, &[b.natural(2), v0]))),
+        ("neutral add", b.l(b.apps(add, &[v0, b.natural(2)]))),
+        ("suc neutral", b.l(b.suc(b.suc(v0)))),
+        // \n. natElim (\_. Nat) 0 (\k _. k) (suc n): the predecessor.
+        (
+            "predecessor",
+            b.l(b.nat_elim(constant_nat, b.zero(), b.l(b.l(v1)), b.suc(v0))),
+        ),
+        (
+            "recursion on a type",
+            b.nat_elim(constant_nat, b.zero(), b.l(b.l(v0)), b.universe()),
+        ),
+        ("applied literal", b.application(b.natural(2), b.l(v0))),
+        (
+            "applied successor",
+            b.l(b.application(b.suc(v0), b.natural(1))),
+        ),
+        // The motive picks a different type for zero and for successors.
+        (
+            "dependent motive",
+            b.nat_elim(
+                b.l(b.nat_elim(b.l(b.universe()), b.nat(), b.l(b.l(b.universe())), v0)),
+                b.natural(7),
+                b.l(b.l(b.nat())),
+                b.natural(0),
+            ),
+        ),
+    ]
+}
+
+/// Terms over machine integers.
+fn integer_corpus<'a>(b: &'a syntax::Builder) -> Vec<(&'static str, TermRef<'a>)> {
+    [
+        ("add", r"addI64 2i64 3i64"),
+        ("nested", r"mulI64 (addI64 1i64 2i64) (subI64 10i64 -4i64)"),
+        ("wrapping", r"mulU64 9223372036854775808u64 2u64"),
+        ("division by zero", r"(divI64 7i64 0i64, remI64 7i64 0i64)"),
+        (
+            "minimum over minus one",
+            r"divI64 -9223372036854775808i64 -1i64",
+        ),
+        (
+            "comparisons",
+            r"(ltI64 -1i64 0i64, (ltU64 1u64 0u64, eqU64 4u64 4u64))",
+        ),
+        ("unapplied", r"addI64"),
+        ("partial", r"addI64 1i64"),
+        ("stuck", r"\x. addI64 x (addI64 1i64 2i64)"),
+        ("partial application", r"(\f. f 2u64) (addU64 3u64)"),
+        (
+            "let",
+            r"let x = subU64 5u64 2u64 in let f = mulU64 x in f (f x)",
+        ),
+        (
+            "branch",
+            r"\(x : I64). natElim (\_. I64) x (\_ _. 0i64) (ltI64 x 0i64)",
+        ),
+        ("applied result", r"(\x y. y) (addI64 1i64 2i64) (\x. x)"),
+        ("applied literal", r"2i64 3i64"),
+        ("applied comparison", r"ltI64 1i64 2i64 Type"),
+        ("projected primitive", r"fst (addI64 1i64)"),
+    ]
+    .into_iter()
+    .map(|(name, input)| (name, parse(input, b).unwrap()))
+    .collect()
+}
+
+/// Terms with holes, which normalize to neutrals.
+fn hole_corpus<'a>(b: &'a syntax::Builder) -> Vec<(&'static str, TermRef<'a>)> {
+    [
+        ("hole", r"?h"),
+        ("applied hole", r"\x. ?h x x"),
+        ("substituted hole", r"(\f. f ?h) (\y. y)"),
+        ("projected hole", r"\y. snd (fst ?p)"),
+        ("suc hole", r"suc (suc ?n)"),
+        (
+            "recursion on a hole",
+            r"natElim (\_. Nat) 0 (\_ n. suc n) ?n",
+        ),
+        ("primitive on a hole", r"addI64 ?x (addI64 1i64 2i64)"),
+        ("pair of holes", r"(?a, \x. ?b)"),
+    ]
+    .into_iter()
+    .map(|(name, input)| (name, parse(input, b).unwrap()))
+    .collect()
+}
+
+/// The definitions that [`GLOBAL_CORPUS`] refers to.
+const SIGNATURE: &str = r"
+    n2 = 2;
+    three = suc n2;
+    id = \x. x;
+    add = \m n. natElim (\_. Nat) n (\_ acc. suc acc) m;
+    four = add n2 n2;
+";
+
+/// Terms over top-level definitions. The reference normalizes them with the
+/// definitions inlined.
+const GLOBAL_CORPUS: &[(&str, &str)] = &[
+    ("global", r"four"),
+    ("suc global", r"suc n2"),
+    ("suc suc global", r"suc three"),
+    ("suc applied global", r"suc (id n2)"),
+    ("recursion on suc global", r"add three n2"),
+    ("pair of globals", r"(n2, suc four)"),
+    ("neutral", r"\n. add n n2"),
+    ("suc neutral", r"\n. suc (add n2 n)"),
+    ("applied literal global", r"n2 Type"),
+];
+
+/// Terms over two solved metas, `two` solved with `2` and `succ` with
+/// `\n. suc n`, given either as the metas or as their solutions. The reference
+/// normalizes them with the solutions in place of the metas.
+fn meta_corpus<'a>(
+    b: &'a syntax::Builder,
+    two: TermRef<'a>,
+    succ: TermRef<'a>,
+) -> Vec<(&'static str, TermRef<'a>)> {
+    let count = parse(r"\n. natElim (\_. Nat) 0 (\_ m. suc m) n", b).unwrap();
+    vec![
+        ("solved meta", two),
+        ("suc solved meta", b.suc(two)),
+        ("suc suc solved meta", b.suc(b.suc(two))),
+        ("applied solved meta", b.application(succ, two)),
+        ("suc applied solved meta", b.suc(b.application(succ, two))),
+        ("suc neutral", b.lambda(b.suc(b.application(succ, b.v(0))))),
+        (
+            "recursion on suc solved meta",
+            b.application(count, b.suc(two)),
+        ),
+        ("pair of solved metas", b.pair(b.suc(two), succ)),
+    ]
+}
+
+struct Disagreement<'a> {
+    name: &'static str,
+    term: TermRef<'a>,
+    backend: &'static str,
+    expected: TermRef<'a>,
+    actual: TermRef<'a>,
+}
+
+impl std::fmt::Display for Disagreement<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{} disagrees with the reference on {}",
+            self.backend, self.name
+        )?;
+        writeln!(f, "  term:     {}", self.term)?;
+        writeln!(f, "  expected: {}", self.expected)?;
+        write!(f, "  actual:   {}", self.actual)
+    }
+}
+
+/// De Bruijn terms are alpha-equivalent exactly when they are equal, so the
+/// normal forms can be compared with `==`.
+fn disagreements<'a>(
+    corpus: &[(&'static str, TermRef<'a>)],
+    syntax_builder: &'a syntax::Builder,
+) -> Vec<Disagreement<'a>> {
+    let mut result = Vec::new();
+    for &(name, term) in corpus {
+        let expected = reference::Backend.normalize(term, syntax_builder);
+        for &(backend, normalizer) in BACKENDS {
+            let actual = normalizer.normalize(term, syntax_builder);
+            if actual != expected {
+                result.push(Disagreement {
+                    name,
+                    term,
+                    backend,
+                    expected,
+                    actual,
+                });
+            }
+        }
+    }
+    result
+}
+
+fn assert_agreement(disagreements: Vec<Disagreement<'_>>) {
+    assert!(
+        disagreements.is_empty(),
+        "{}",
+        disagreements
+            .iter()
+            .map(|disagreement| disagreement.to_string())
+            .collect::<Vec<_>>()
+            .join("\n")
+    );
+}
+
+#[test]
+fn backends_agree_with_reference() {
+    let b = syntax::Builder::new();
+    let result_builder = syntax::Builder::new();
+    assert_agreement(disagreements(&corpus(&b), &result_builder));
+}
+
+#[test]
+fn backends_agree_with_reference_on_pairs() {
+    let b = syntax::Builder::new();
+    let result_builder = syntax::Builder::new();
+    assert_agreement(disagreements(&pair_corpus(&b), &result_builder));
+}
+
+#[test]
+fn backends_agree_with_reference_on_naturals() {
+    let b = syntax::Builder::new();
+    let result_builder = syntax::Builder::new();
+    assert_agreement(disagreements(&nat_corpus(&b), &result_builder));
+}
+
+#[test]
+fn backends_agree_with_reference_on_integers() {
+    let b = syntax::Builder::new();
+    let result_builder = syntax::Builder::new();
+    assert_agreement(disagreements(&integer_corpus(&b), &result_builder));
+}
+
+#[test]
+fn backends_agree_with_reference_on_holes() {
+    let b = syntax::Builder::new();
+    let result_builder = syntax::Builder::new();
+    assert_agreement(disagreements(&hole_corpus(&b), &result_builder));
+}
+
+#[test]
+fn backends_agree_with_reference_on_globals() {
+    let b = syntax::Builder::new();
+    let result_builder = syntax::Builder::new();
+    let signature = parse_signature(SIGNATURE, &b).unwrap();
+    let backends: [(&str, &dyn Normalizer); 2] = [
+        (
+            "domain",
+            &domain::SignatureBackend {
+                signature: &signature,
+            },
+        ),
+        (
+            "domain_rc",
+            &domain_rc::SignatureBackend {
+                signature: &signature,
+            },
+        ),
+    ];
+    let mut disagreements = Vec::new();
+    for &(name, input) in GLOBAL_CORPUS {
+        let term = parse_in(input, &signature, &b).unwrap();
+        let expected = reference::Backend.normalize(signature.inline(term, &b), &result_builder);
+        for (backend, normalizer) in backends {
+            let actual = normalizer.normalize(term, &result_builder);
+            if actual != expected {
+                disagreements.push(Disagreement {
+                    name,
+                    term,
+                    backend,
+                    expected,
+                    actual,
+                });
+            }
+        }
+    }
+    assert_agreement(disagreements);
+}
+
+#[test]
+fn backends_agree_with_reference_on_solved_metas() {
+    let b = syntax::Builder::new();
+    let result_builder = syntax::Builder::new();
+    let metas = MetaContext::new(&b);
+    let (two, succ) = (metas.fresh(), metas.fresh());
+    fn evaluate<'a>(
+        metas: &MetaContext<'a>,
+        term: TermRef<'a>,
+        level: usize,
+    ) -> domain_rc::ValueRef<'a> {
+        term.evaluate_rc(&mut domain_rc::Environment::free_variables(level).with_metas(metas))
+    }
+    let unify = |level, term1, term2| {
+        let (value1, value2) = (
+            evaluate(&metas, term1, level),
+            evaluate(&metas, term2, level),
+        );
+        metas.unify(Level(level), &value1, &value2).unwrap()
+    };
+    unify(0, b.meta(two), b.natural(2));
+    unify(1, b.application(b.meta(succ), b.v(0)), b.suc(b.v(0)));
+    let solution = |meta| metas.solution(meta).unwrap().quote(Level(0), &b);
+    let expected = meta_corpus(&b, solution(two), solution(succ));
+    let mut disagreements = Vec::new();
+    for ((name, term), (_, solved)) in meta_corpus(&b, b.meta(two), b.meta(succ))
+        .into_iter()
+        .zip(expected)
+    {
+        let expected = reference::Backend.normalize(solved, &result_builder);
+        let builder = domain::Builder::new();
+        let mut environment = domain::Environment::new().with_metas(&metas, &builder);
+        let arena =
+            term.evaluate(&mut environment, &builder)
+                .quote(Level(0), &builder, &result_builder);
+        let rc = term
+            .evaluate_rc(&mut domain_rc::Environment::new().with_metas(&metas))
+            .quote(Level(0), &result_builder);
+        for (backend, actual) in [("domain", arena), ("domain_rc", rc)] {
+            if actual != expected {
+                disagreements.push(Disagreement {
+                    name,
+                    term,
+                    backend,
+                    expected,
+                    actual,
+                });
+            }
+        }
+    }
+    assert_agreement(disagreements);
+}
+
+#[test]
+fn primitives_compute_on_literals() {
+    let b = syntax::Builder::new();
+    let result_builder = syntax::Builder::new();
+    let normal_forms: Vec<String> = integer_corpus(&b)
+        .into_iter()
+        .map(|(_, term)| {
+            domain_rc::Backend
+                .normalize(term, &result_builder)
+                .to_string()
+        })
+        .collect();
+    assert_eq!(
+        normal_forms,
+        [
+            "5i64",
+            "42i64",
+            "0u64",
+            "(0i64, 7i64)",
+            "-9223372036854775808i64",
+            "(1, (0, 1))",
+            "addI64",
+            "addI64 1i64",
+            r"\a. addI64 a 3i64",
+            "5u64",
+            "27u64",
+            r"\a. natElim (\b. I64) a (\b c. 0i64) (ltI64 a 0i64)",
+            r"\a. a",
+            "2i64 3i64",
+            "1 Type",
+            "fst (addI64 1i64)",
+        ]
+    );
+}
+
+#[test]
+fn reference_normalizes_numerals() {
+    let b = syntax::Builder::new();
+    let w = Workloads::new(&b);
+    let result_builder = syntax::Builder::new();
+    let n21 = reference::Backend.normalize(w.n21, &result_builder);
+    assert_eq!(common::to_nat(n21), Some(21));
+    let tree_1k = reference::Backend.normalize(w.tree_1k, &result_builder);
+    assert_eq!(common::tree_leaves(tree_1k), Some(1024));
+}
@@ -0,0 +1,50 @@
+mod common;
+
+use common::{numeral, to_nat};
+use rixty::{domain, domain_rc, normalizer::Normalizer, syntax};
+
+const BACKENDS: &[(&str, &dyn Normalizer)] = &[
+    ("domain", &domain::Backend),
+    ("domain_rc", &domain_rc::Backend),
+    ("domain_rc (call-by-need)", &domain_rc::LazyBackend),
+];
+
+/// `\f x. f (f (... x))` nests its arguments a million deep.
+#[test]
+fn deeply_nested_arguments() {
+    let b = syntax::Builder::new();
+    let n1m = numeral(&b, 1_000_000);
+    let result_builder = syntax::Builder::new();
+    for (name, backend) in BACKENDS {
+        let normal_form = backend.normalize(n1m, &result_builder);
+        assert_eq!(to_nat(normal_form), Some(1_000_000), "{name}");
+    }
+}
+
+/// `\y. id (id (... y))` where every argument is itself a redex.
+#[test]
+fn deeply_nested_redexes() {
+    let b = syntax::Builder::new();
+    let id = b.l(b.v(0));
+    let body = (0..1_000_000).fold(b.v(0), |body, _| b.application(id, body));
+    let term = b.l(body);
+    let result_builder = syntax::Builder::new();
+    for (name, backend) in BACKENDS {
+        let normal_form = backend.normalize(term, &result_builder);
+        assert_eq!(normal_form, id, "{name}");
+    }
+}
+
+/// `let x1 = id in let x2 = x1 in ... in x1000000`
+#[test]
+fn deeply_nested_lets() {
+    let b = syntax::Builder::new();
+    let id = b.l(b.v(0));
+    let body = (0..1_000_000).fold(b.v(0), |body, _| b.let_in(b.v(0), body));
+    let term = b.let_in(id, body);
+    let result_builder = syntax::Builder::new();
+    for (name, backend) in BACKENDS {
+        let normal_form = backend.normalize(term, &result_builder);
+        assert_eq!(normal_form, id, "{name}");
+    }
+}
@@ -0,0 +1,250 @@
+mod common;
+
+use common::{to_nat, Workloads};
+use std::time::{Duration, Instant};
+
+use rixty::{
+    domain,
+    domain_rc::{self, QuoteOptions, Strategy},
+    fuel::{CancellationToken, Fuel, Interrupted},
+    index::Level,
+    parse::{parse, parse_open},
+    syntax::{self, Plicity},
+};
+
+const OMEGA: &str = r"(\x. x x) (\x. x x)";
+
+#[test]
+fn divergent_terms_run_out() {
+    let b = syntax::Builder::new();
+    let omega = parse(OMEGA, &b).unwrap();
+    for strategy in [Strategy::CallByValue, Strategy::CallByNeed] {
+        let fuel = Fuel::new(1000);
+        let mut environment = domain_rc::Environment::new().with_strategy(strategy);
+        assert!(matches!(
+            omega.evaluate_rc_with_fuel(&mut environment, &fuel),
+            Err(Interrupted::OutOfFuel)
+        ));
+        assert_eq!(fuel.remaining(), 0);
+    }
+    let builder = domain::Builder::new();
+    let fuel = Fuel::new(1000);
+    assert!(matches!(
+        omega.evaluate_with_fuel(&mut domain::Environment::new(), &builder, &fuel),
+        Err(Interrupted::OutOfFuel)
+    ));
+}
+
+#[test]
+fn enough_fuel_gives_the_normal_form() {
+    let b = syntax::Builder::new();
+    let w = Workloads::new(&b);
+    let result_builder = syntax::Builder::new();
+
+    let fuel = Fuel::new(10_000);
+    let value = w
+        .n100
+        .evaluate_rc_with_fuel(&mut domain_rc::Environment::new(), &fuel);
+    let normal_form =
+        value
+            .unwrap()
+            .quote_with_fuel(Level(0), &result_builder, QuoteOptions::default(), &fuel);
+    assert_eq!(to_nat(normal_form.unwrap()), Some(100));
+    assert!(fuel.remaining() > 0);
+
+    let builder = domain::Builder::new();
+    let fuel = Fuel::new(10_000);
+    let value = w
+        .n100
+        .evaluate_with_fuel(&mut domain::Environment::new(), &builder, &fuel);
+    let normal_form = value
+        .unwrap()
+        .quote_with_fuel(Level(0), &builder, &result_builder, &fuel);
+    assert_eq!(to_nat(normal_form.unwrap()), Some(100));
+}
+
+#[test]
+fn application_is_metered() {
+    let b = syntax::Builder::new();
+    let self_application = parse(r"\x. x x", &b).unwrap();
+
+    let value = self_application.evaluate_rc(&mut domain_rc::Environment::new());
+    let fuel = Fuel::new(100);
+    let result = domain_rc::apply_with_fuel(&value, Plicity::Explicit, value.clone(), &fuel);
+    assert!(matches!(result, Err(Interrupted::OutOfFuel)));
+    let mut spine = domain_rc::Spine::new();
+    spine.push_front(domain_rc::Frame::Application(
+        Plicity::Explicit,
+        value.clone(),
+    ));
+    let fuel = Fuel::new(100);
+    let result = domain_rc::apply_spine_with_fuel(&value, spine, &fuel);
+    assert!(matches!(result, Err(Interrupted::OutOfFuel)));
+
+    let builder = domain::Builder::new();
+    let value = self_application.evaluate(&mut domain::Environment::new(), &builder);
+    let fuel = Fuel::new(100);
+    assert!(matches!(
+        value.apply_with_fuel(Plicity::Explicit, value, &builder, &fuel),
+        Err(Interrupted::OutOfFuel)
+    ));
+    let mut spine = domain::Spine::new();
+    spine.push_front(domain::Frame::Application(Plicity::Explicit, value));
+    let fuel = Fuel::new(100);
+    assert!(matches!(
+        value.apply_spine_with_fuel(spine, &builder, &fuel),
+        Err(Interrupted::OutOfFuel)
+    ));
+}
+
+#[test]
+fn readback_is_metered() {
+    let b = syntax::Builder::new();
+    let w = Workloads::new(&b);
+    let result_builder = syntax::Builder::new();
+
+    // Evaluation stops at the lambda; the divergence is under the binder.
+    let term = parse(&format!(r"\y. {OMEGA}"), &b).unwrap();
+    let value = term.evaluate_rc(&mut domain_rc::Environment::new());
+    let fuel = Fuel::new(1000);
+    let quoted = value.quote_with_fuel(Level(0), &result_builder, QuoteOptions::default(), &fuel);
+    assert!(matches!(quoted, Err(Interrupted::OutOfFuel)));
+
+    // Reading back a normal form takes a step per node.
+    let value = w.n10.evaluate_rc(&mut domain_rc::Environment::new());
+    let value = domain_rc::force(&value);
+    let fuel = Fuel::new(5);
+    let quoted = value.quote_with_fuel(Level(0), &result_builder, QuoteOptions::default(), &fuel);
+    assert!(matches!(quoted, Err(Interrupted::OutOfFuel)));
+
+    let builder = domain::Builder::new();
+    let value = term.evaluate(&mut domain::Environment::new(), &builder);
+    let fuel = Fuel::new(1000);
+    assert!(matches!(
+        value.quote_with_fuel(Level(0), &builder, &result_builder, &fuel),
+        Err(Interrupted::OutOfFuel)
+    ));
+}
+
+#[test]
+fn interrupted_thunks_can_be_forced_again() {
+    let b = syntax::Builder::new();
+    let w = Workloads::new(&b);
+    // `t` is bound to a thunk that takes a step to force.
+    let term = b.application(parse(r"\t g. g t", &b).unwrap(), b.apps(w.n2, &[w.n2]));
+    let mut environment = domain_rc::Environment::new().with_strategy(Strategy::CallByNeed);
+    let value = term.evaluate_rc(&mut environment);
+    let identity = parse(r"\x. x", &b)
+        .unwrap()
+        .evaluate_rc(&mut domain_rc::Environment::new());
+
+    let fuel = Fuel::new(2);
+    let result = domain_rc::apply_with_fuel(&value, Plicity::Explicit, identity.clone(), &fuel);
+    assert!(matches!(result, Err(Interrupted::OutOfFuel)));
+
+    let result = domain_rc::apply(&value, identity);
+    let result_builder = syntax::Builder::new();
+    assert_eq!(to_nat(result.quote(Level(0), &result_builder)), Some(4));
+}
+
+#[test]
+fn cancelled_runs_stop() {
+    let b = syntax::Builder::new();
+    let omega = parse(OMEGA, &b).unwrap();
+    let token = CancellationToken::new();
+    token.cancel();
+    let fuel = Fuel::unlimited().with_cancellation(token.clone());
+    assert_eq!(
+        omega
+            .evaluate_rc_with_fuel(&mut domain_rc::Environment::new(), &fuel)
+            .err(),
+        Some(Interrupted::Cancelled)
+    );
+    let builder = domain::Builder::new();
+    let result_builder = syntax::Builder::new();
+    let fuel = Fuel::unlimited().with_cancellation(token);
+    assert_eq!(
+        builder
+            .normalize_with_fuel(omega, Level(0), &result_builder, &fuel)
+            .err(),
+        Some(Interrupted::Cancelled)
+    );
+}
+
+#[test]
+fn runs_can_be_cancelled_from_another_thread() {
+    let b = syntax::Builder::new();
+    let omega = parse(OMEGA, &b).unwrap();
+    for strategy in [Strategy::CallByValue, Strategy::CallByNeed] {
+        let token = CancellationToken::new();
+        let canceller = {
+            let token = token.clone();
+            std::thread::spawn(move || {
+                std::thread::sleep(Duration::from_millis(20));
+                token.cancel();
+            })
+        };
+        let fuel = Fuel::unlimited().with_cancellation(token);
+        let mut environment = domain_rc::Environment::new().with_strategy(strategy);
+        assert_eq!(
+            omega.evaluate_rc_with_fuel(&mut environment, &fuel).err(),
+            Some(Interrupted::Cancelled)
+        );
+        canceller.join().unwrap();
+    }
+}
+
+#[test]
+fn runs_time_out() {
+    let b = syntax::Builder::new();
+    let omega = parse(OMEGA, &b).unwrap();
+    let fuel = Fuel::unlimited().with_deadline(Instant::now() + Duration::from_millis(20));
+    assert_eq!(
+        omega
+            .evaluate_rc_with_fuel(&mut domain_rc::Environment::new(), &fuel)
+            .err(),
+        Some(Interrupted::TimedOut)
+    );
+    let builder = domain::Builder::new();
+    let fuel = Fuel::unlimited().with_deadline(Instant::now() + Duration::from_millis(20));
+    assert_eq!(
+        omega
+            .evaluate_with_fuel(&mut domain::Environment::new(), &builder, &fuel)
+            .err(),
+        Some(Interrupted::TimedOut)
+    );
+}
+
+#[test]
+fn interrupted_runs_release_their_values() {
+    let b = syntax::Builder::new();
+    // Every step builds closures that capture `f`.
+    let term = parse_open(r"(\x. x x) (\x. (\y. x x) (f x))", &["f"], &b).unwrap();
+    let f = parse(r"\a. a", &b)
+        .unwrap()
+        .evaluate_rc(&mut domain_rc::Environment::new());
+    for strategy in [Strategy::CallByValue, Strategy::CallByNeed] {
+        let mut environment = domain_rc::Environment::new().with_strategy(strategy);
+        environment.extend(f.clone());
+        let references = f.strong_count();
+        let fuel = Fuel::new(10_000);
+        assert_eq!(
+            term.evaluate_rc_with_fuel(&mut environment, &fuel).err(),
+            Some(Interrupted::OutOfFuel)
+        );
+        assert_eq!(f.strong_count(), references);
+    }
+
+    // The arena keeps what the run allocated until it is reset.
+    let mut builder = domain::Builder::new();
+    let omega = parse(OMEGA, &b).unwrap();
+    let result_builder = syntax::Builder::new();
+    let fuel = Fuel::new(10_000);
+    assert!(builder
+        .normalize_with_fuel(omega, Level(0), &result_builder, &fuel)
+        .is_err());
+    builder.reset();
+    let identity = parse(r"(\x. x) (\x. x)", &b).unwrap();
+    let normal_form = builder.normalize(identity, Level(0), &result_builder);
+    assert_eq!(normal_form.to_string(), r"\a. a");
+}
@@ -0,0 +1,183 @@
+mod common;
+
+use common::to_nat;
+use rixty::{
+    domain,
+    domain_rc::{self, Environment, Head, QuoteOptions, SignatureBackend, Value},
+    index::Level,
+    normalizer::Normalizer,
+    parse::{parse_in, parse_signature, Error},
+    reference,
+    signature::{Definition, Signature},
+    syntax::{self, Term, TermRef},
+};
+
+const CHURCH: &str = r"
+    n2 = \f x. f (f x);
+    n5 = \f x. f (f (f (f (f x))));
+    mul = \m n f x. m (n f) x;
+    n10 = mul n2 n5;
+    n100 = mul n10 n10;
+    omega = (\x. x x) (\x. x x);
+";
+
+fn evaluate<'a>(signature: &'a Signature<'a>, term: TermRef<'a>) -> rclite::Rc<Value<'a>> {
+    term.evaluate_rc(&mut Environment::new().with_signature(signature))
+}
+
+#[test]
+fn globals_unfold_to_their_definitions() {
+    let b = syntax::Builder::new();
+    let result_builder = syntax::Builder::new();
+    let signature = parse_signature(CHURCH, &b).unwrap();
+    for input in [r"n100", r"mul n10", r"\f. mul n5 f", r"(\n. mul n n) n10"] {
+        let term = parse_in(input, &signature, &b).unwrap();
+        let expected = reference::Backend.normalize(signature.inline(term, &b), &result_builder);
+        let actual = SignatureBackend {
+            signature: &signature,
+        }
+        .normalize(term, &result_builder);
+        assert_eq!(actual, expected, "{input}");
+        let actual = domain::SignatureBackend {
+            signature: &signature,
+        }
+        .normalize(term, &result_builder);
+        assert_eq!(actual, expected, "{input}");
+    }
+    let term = parse_in(r"n100", &signature, &b).unwrap();
+    let normal_form = SignatureBackend {
+        signature: &signature,
+    }
+    .normalize(term, &result_builder);
+    assert_eq!(to_nat(normal_form), Some(100));
+}
+
+#[test]
+fn readback_can_keep_globals_folded() {
+    let b = syntax::Builder::new();
+    let signature = parse_signature(CHURCH, &b).unwrap();
+    let keep_globals = QuoteOptions {
+        keep_globals: true,
+        ..QuoteOptions::default()
+    };
+    let term = parse_in(r"\f. mul n10 n10 f omega", &signature, &b).unwrap();
+    let value = evaluate(&signature, term);
+    // `omega` has no normal form, so this only terminates if globals are
+    // neither evaluated nor unfolded.
+    assert_eq!(
+        value.quote_with(Level(0), &b, keep_globals).to_string(),
+        r"\a. mul n10 n10 a omega"
+    );
+    // Beta redexes with a global in head position are still reduced.
+    let term = parse_in(r"(\g. g n2) (mul n5)", &signature, &b).unwrap();
+    let value = evaluate(&signature, term);
+    assert_eq!(
+        value.quote_with(Level(0), &b, keep_globals).to_string(),
+        r"mul n5 n2"
+    );
+    assert_eq!(to_nat(value.quote(Level(0), &b)), Some(10));
+}
+
+#[test]
+fn conversion_unfolds_globals_only_when_needed() {
+    let b = syntax::Builder::new();
+    let signature = parse_signature(CHURCH, &b).unwrap();
+    let conv = |input1, input2| {
+        let value1 = evaluate(&signature, parse_in(input1, &signature, &b).unwrap());
+        let value2 = evaluate(&signature, parse_in(input2, &signature, &b).unwrap());
+        Value::conv(Level(0), &value1, &value2)
+    };
+    assert!(conv(r"n10", r"mul n5 n2"));
+    assert!(conv(r"mul n10 n10", r"n100"));
+    assert!(!conv(r"mul n10 n10", r"mul n10 n5"));
+    assert!(conv(r"\f. mul n2 n5 f", r"mul n10 (\f x. f x)"));
+    // The same global applied to the same arguments is equal without
+    // unfolding, even when unfolding would not terminate.
+    assert!(conv(r"\x. omega x", r"\x. omega x"));
+}
+
+#[test]
+fn definitions_are_scoped() {
+    let b = syntax::Builder::new();
+    let signature = parse_signature(r"id = \x. x; x = \y. id y; id = \f. f x;", &b).unwrap();
+    let term = parse_in(r"\x. id x", &signature, &b).unwrap();
+    // The local `x` shadows the global, and the second `id` the first.
+    assert_eq!(term.to_string(), r"\a. id a");
+    let value = evaluate(&signature, term);
+    assert_eq!(value.quote(Level(0), &b).to_string(), r"\a. a (\b. b)");
+    assert_eq!(
+        parse_signature(r"f = \x. g x; g = \x. x;", &b).unwrap_err(),
+        Error::UnboundVariable {
+            name: "g".to_owned(),
+            position: 8
+        }
+    );
+    assert_eq!(
+        parse_signature(r"f = \x. x", &b).unwrap_err(),
+        Error::UnexpectedEnd { expected: ";" }
+    );
+}
+
+#[test]
+fn definitions_are_evaluated_once() {
+    let b = syntax::Builder::new();
+    let signature = parse_signature(
+        r"slow = natElim (\_. Nat) 0 (\_ n. suc n) 1000; twice = (slow, slow);",
+        &b,
+    )
+    .unwrap();
+    fn unfolding<'a>(value: &Value<'a>) -> domain_rc::ValueRef<'a> {
+        match value {
+            Value::Neutral {
+                head: Head::Global { unfolding, .. },
+                ..
+            } => unfolding.clone(),
+            _ => panic!("expected a global"),
+        }
+    }
+    fn components<'a>(value: &Value<'a>) -> (domain_rc::ValueRef<'a>, domain_rc::ValueRef<'a>) {
+        match value {
+            Value::Pair(first, second) => (first.clone(), second.clone()),
+            _ => panic!("expected a pair"),
+        }
+    }
+    // Every occurrence of `slow` shares one unfolding, whether it is in the
+    // term or in another definition, for as long as the environment lives.
+    let term = parse_in(r"(slow, twice)", &signature, &b).unwrap();
+    let mut environment = Environment::new().with_signature(&signature);
+    let (slow, twice) = components(&term.evaluate_rc(&mut environment));
+    let (first, second) = components(&domain_rc::force(&unfolding(&twice)));
+    assert!(rclite::Rc::ptr_eq(&unfolding(&slow), &unfolding(&first)));
+    assert!(rclite::Rc::ptr_eq(&unfolding(&slow), &unfolding(&second)));
+}
+
+/// Each definition unfolds to the one before it.
+#[test]
+fn long_chains_of_definitions() {
+    let b = syntax::Builder::new();
+    let mut signature = Signature::new();
+    let mut term = b.universe();
+    for _ in 0..1_000_000 {
+        let global = signature.define(Definition {
+            name: "d",
+            ty: None,
+            term,
+        });
+        term = b.global(global.index, global.name);
+    }
+    assert_eq!(signature.inline(term, &b), &Term::Type);
+    let normal_form = domain::SignatureBackend {
+        signature: &signature,
+    }
+    .normalize(term, &b);
+    assert_eq!(normal_form, &Term::Type);
+}
+
+#[test]
+#[should_panic(expected = "global n2 is not in the signature")]
+fn globals_need_their_signature() {
+    let b = syntax::Builder::new();
+    let signature = parse_signature(CHURCH, &b).unwrap();
+    let term = parse_in(r"n2", &signature, &b).unwrap();
+    domain_rc::Backend.normalize(term, &b);
+}
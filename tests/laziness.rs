@@ -0,0 +1,69 @@
+mod common;
+
+use common::{to_nat, tree_leaves, Workloads};
+use rixty::{
+    domain_rc::{self, Strategy},
+    index::Level,
+    normalizer::Normalizer,
+    parse::{parse, parse_open},
+    syntax,
+};
+
+const OMEGA: &str = r"(\x. x x) (\x. x x)";
+
+#[test]
+fn unused_arguments_are_not_evaluated() {
+    let b = syntax::Builder::new();
+    let result_builder = syntax::Builder::new();
+    let term = parse(&format!(r"(\t f. t) (\x. x) ({OMEGA})"), &b).unwrap();
+    let normal_form = domain_rc::LazyBackend.normalize(term, &result_builder);
+    assert_eq!(normal_form.to_string(), r"\a. a");
+
+    let term = parse(&format!(r"let loop = {OMEGA} in \x. x"), &b).unwrap();
+    let normal_form = domain_rc::LazyBackend.normalize(term, &result_builder);
+    assert_eq!(normal_form.to_string(), r"\a. a");
+}
+
+#[test]
+fn pair_components_are_not_evaluated_until_projected() {
+    let b = syntax::Builder::new();
+    let result_builder = syntax::Builder::new();
+    let term = parse(&format!(r"fst (\x. x, {OMEGA})"), &b).unwrap();
+    let normal_form = domain_rc::LazyBackend.normalize(term, &result_builder);
+    assert_eq!(normal_form.to_string(), r"\a. a");
+}
+
+#[test]
+fn neutral_spines_stay_lazy_until_quoted() {
+    let b = syntax::Builder::new();
+    let term = parse_open(&format!(r"f ((\x y. y) ({OMEGA}))"), &["f"], &b).unwrap();
+    let mut environment =
+        domain_rc::Environment::free_variables(1).with_strategy(Strategy::CallByNeed);
+    let value = term.evaluate_rc(&mut environment);
+    let result_builder = syntax::Builder::new();
+    assert_eq!(
+        value.quote(Level(1), &result_builder).to_string(),
+        r"#0 (\a. a)"
+    );
+}
+
+#[test]
+fn closures_keep_their_strategy() {
+    let b = syntax::Builder::new();
+    let term = parse(&format!(r"\g. (\t f. t) g ({OMEGA})"), &b).unwrap();
+    let value =
+        term.evaluate_rc(&mut domain_rc::Environment::new().with_strategy(Strategy::CallByNeed));
+    let result_builder = syntax::Builder::new();
+    assert_eq!(value.quote(Level(0), &result_builder).to_string(), r"\a. a");
+}
+
+#[test]
+fn workloads() {
+    let b = syntax::Builder::new();
+    let w = Workloads::new(&b);
+    let result_builder = syntax::Builder::new();
+    let n1k = domain_rc::LazyBackend.normalize(w.n1k, &result_builder);
+    assert_eq!(to_nat(n1k), Some(1000));
+    let tree_2k = domain_rc::LazyBackend.normalize(w.tree_2k, &result_builder);
+    assert_eq!(tree_leaves(tree_2k), Some(2048));
+}
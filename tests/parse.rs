@@ -0,0 +1,422 @@
+mod common;
+
+use common::Workloads;
+use rixty::{
+    parse::{parse, parse_in, parse_open, parse_signature, Error},
+    primitive::{Integer, IntegerType, Operation, Primitive},
+    signature::Name,
+    syntax::{self, Constructor, Plicity},
+};
+
+#[test]
+fn numerals_and_mul() {
+    let b = syntax::Builder::new();
+    let w = Workloads::new(&b);
+    assert_eq!(parse(r"\f x. f (f x)", &b), Ok(w.n2));
+    assert_eq!(parse(r"λf. λx. f (f (f (f (f x))))", &b), Ok(w.n5));
+    assert_eq!(parse(r"\m n f x. m (n f) x", &b), Ok(w.mul));
+}
+
+#[test]
+fn application_associates_to_the_left() {
+    let b = syntax::Builder::new();
+    let expected = b.l(b.l(b.l(b.apps(b.v(2), &[b.v(1), b.v(0)]))));
+    assert_eq!(parse(r"\f x y. f x y", &b), Ok(expected));
+    assert_eq!(parse(r"\f x y. ((f x) y)", &b), Ok(expected));
+}
+
+#[test]
+fn lambda_as_last_argument() {
+    let b = syntax::Builder::new();
+    let expected = b.l(b.application(b.v(0), b.l(b.application(b.v(0), b.v(1)))));
+    assert_eq!(parse(r"\f. f \x. x f", &b), Ok(expected));
+}
+
+#[test]
+fn shadowing_resolves_to_the_innermost_binder() {
+    let b = syntax::Builder::new();
+    let expected = b.l(b.l(b.application(b.v(0), b.l(b.v(0)))));
+    assert_eq!(parse(r"\x x. x (\x. x)", &b), Ok(expected));
+}
+
+#[test]
+fn open_terms() {
+    let b = syntax::Builder::new();
+    let expected = b.l(b.apps(b.v(2), &[b.v(1), b.v(0)]));
+    assert_eq!(parse_open(r"\x. f y x", &["f", "y"], &b), Ok(expected));
+}
+
+#[test]
+fn errors() {
+    let b = syntax::Builder::new();
+    assert_eq!(
+        parse(r"\f x. f y", &b),
+        Err(Error::UnboundVariable {
+            name: "y".to_owned(),
+            position: 8
+        })
+    );
+    assert_eq!(
+        parse(r"\f x f", &b),
+        Err(Error::UnexpectedEnd {
+            expected: "an identifier"
+        })
+    );
+    assert_eq!(
+        parse(r"\. x", &b),
+        Err(Error::UnexpectedToken {
+            found: ".".to_owned(),
+            expected: "an identifier",
+            position: 1
+        })
+    );
+    assert_eq!(
+        parse(r"(\x. x", &b),
+        Err(Error::UnexpectedEnd { expected: ")" })
+    );
+    assert_eq!(
+        parse(r"\x. x)", &b),
+        Err(Error::UnexpectedToken {
+            found: ")".to_owned(),
+            expected: "end of input",
+            position: 5
+        })
+    );
+    assert_eq!(
+        parse(r"\x. x + x", &b),
+        Err(Error::UnexpectedCharacter {
+            character: '+',
+            position: 6
+        })
+    );
+    assert_eq!(
+        parse(r"\x. 18446744073709551616", &b),
+        Err(Error::LiteralOutOfRange {
+            literal: "18446744073709551616".to_owned(),
+            position: 4
+        })
+    );
+}
+
+#[test]
+fn lets() {
+    let b = syntax::Builder::new();
+    let w = Workloads::new(&b);
+    let expected = b.let_in(w.n2, b.apps(w.mul, &[b.v(0), b.v(0)]));
+    assert_eq!(
+        parse(
+            r"let two = \f x. f (f x) in (\m n f x. m (n f) x) two two",
+            &b
+        ),
+        Ok(expected)
+    );
+    let expected = b.l(b.let_in(b.v(0), b.let_in(b.v(0), b.v(1))));
+    assert_eq!(parse(r"\x. let y = x in let x = y in y", &b), Ok(expected));
+    assert_eq!(
+        parse(r"let x = \y. y x", &b),
+        Err(Error::UnboundVariable {
+            name: "x".to_owned(),
+            position: 14
+        })
+    );
+}
+
+#[test]
+fn types() {
+    let b = syntax::Builder::new();
+    let ty = b.universe();
+    let id_type = b.pi(ty, b.pi(b.v(0), b.v(1)));
+    assert_eq!(parse(r"(A : Type) -> A -> A", &b), Ok(id_type));
+    assert_eq!(parse(r"(A : Type) (x : A) -> A", &b), Ok(id_type));
+    assert_eq!(
+        parse(r"\(A : Type) (x : A). x", &b),
+        Ok(b.annotated_lambda(ty, b.annotated_lambda(b.v(0), b.v(0))))
+    );
+    // Each name of a group is in scope in the types of the later ones.
+    assert_eq!(
+        parse(r"(A : Type) -> (x y : A) -> A", &b),
+        Ok(b.pi(ty, b.pi(b.v(0), b.pi(b.v(1), b.v(2)))))
+    );
+    assert_eq!(
+        parse(r"\(x y : Type) z. z", &b),
+        Ok(b.annotated_lambda(ty, b.annotated_lambda(ty, b.l(b.v(0)))))
+    );
+    // Arrows associate to the right and bind looser than application.
+    let expected = b.l(b.pi(b.application(b.v(0), b.v(0)), b.pi(b.v(1), b.v(2))));
+    assert_eq!(parse(r"\f. f f -> f -> f", &b), Ok(expected));
+    assert_eq!(
+        parse(r"(Type -> Type) -> Type", &b),
+        Ok(b.pi(b.pi(ty, ty), ty))
+    );
+    assert_eq!(
+        parse(r"\f. (x : f) f", &b),
+        Err(Error::UnexpectedToken {
+            found: "f".to_owned(),
+            expected: "-> or *",
+            position: 12
+        })
+    );
+}
+
+#[test]
+fn pairs() {
+    let b = syntax::Builder::new();
+    let ty = b.universe();
+    assert_eq!(
+        parse(r"\p. (snd p, fst p)", &b),
+        Ok(b.l(b.pair(b.snd(b.v(0)), b.fst(b.v(0)))))
+    );
+    // Projections apply to one atom, and bind tighter than application.
+    assert_eq!(
+        parse(r"\p. fst p (snd (snd p))", &b),
+        Ok(b.l(b.application(b.fst(b.v(0)), b.snd(b.snd(b.v(0))))))
+    );
+    assert_eq!(
+        parse(r"(A : Type) * A -> Type", &b),
+        Ok(b.pi(b.sigma(ty, b.v(0)), ty))
+    );
+    // Products bind tighter than arrows and associate to the right.
+    assert_eq!(
+        parse(r"Type * Type * Type -> Type", &b),
+        Ok(b.pi(b.sigma(ty, b.sigma(ty, ty)), ty))
+    );
+    assert_eq!(
+        parse(r"Type -> (A : Type) * A", &b),
+        Ok(b.pi(ty, b.sigma(ty, b.v(0))))
+    );
+    assert_eq!(
+        parse(r"Type * (A : Type) * A", &b),
+        Ok(b.sigma(ty, b.sigma(ty, b.v(0))))
+    );
+}
+
+#[test]
+fn naturals() {
+    let b = syntax::Builder::new();
+    let nat = b.nat();
+    assert_eq!(
+        parse(r"\n. suc (suc n) zero 42", &b),
+        Ok(b.l(b.apps(b.suc(b.suc(b.v(0))), &[b.zero(), b.natural(42)])))
+    );
+    assert_eq!(
+        parse(r"\n. natElim (\_. Nat) 0 (\_ acc. suc acc) n", &b),
+        Ok(b.l(b.nat_elim(b.l(nat), b.natural(0), b.l(b.l(b.suc(b.v(0)))), b.v(0))))
+    );
+    assert_eq!(parse(r"Nat -> Nat", &b), Ok(b.pi(nat, nat)));
+    // Names that merely start with digits are identifiers.
+    assert_eq!(parse(r"\1x. 1x", &b), Ok(b.l(b.v(0))));
+}
+
+#[test]
+fn integers() {
+    let b = syntax::Builder::new();
+    let add = b.primitive(Primitive {
+        operation: Operation::Add,
+        ty: IntegerType::I64,
+    });
+    assert_eq!(
+        parse(r"addI64 -1i64 18446744073709551615u64", &b),
+        Ok(b.apps(
+            add,
+            &[
+                b.integer(Integer::I64(-1)),
+                b.integer(Integer::U64(u64::MAX))
+            ]
+        ))
+    );
+    let i64_type = b.integer_type(IntegerType::I64);
+    assert_eq!(
+        parse(r"I64 -> I64->I64", &b),
+        Ok(b.pi(i64_type, b.pi(i64_type, i64_type)))
+    );
+    // Only whole names are primitives.
+    assert_eq!(parse(r"\addI64s. addI64s", &b), Ok(b.l(b.v(0))));
+    assert_eq!(
+        parse(r"-1u64", &b),
+        Err(Error::LiteralOutOfRange {
+            literal: "-1u64".to_owned(),
+            position: 0
+        })
+    );
+    assert_eq!(
+        parse(r"128i64 -x", &b),
+        Err(Error::UnexpectedCharacter {
+            character: '-',
+            position: 7
+        })
+    );
+}
+
+#[test]
+fn holes() {
+    let b = syntax::Builder::new();
+    // Holes remember the names in scope, so that goals can use them.
+    assert_eq!(
+        parse(r"\f (x : Nat). f ?goal x", &b),
+        Ok(b.l(b.annotated_lambda(
+            b.nat(),
+            b.apps(b.v(1), &[b.hole("goal", &["f", "x"]), b.v(0)])
+        )))
+    );
+    assert_eq!(parse(r"?h", &b).unwrap().to_string(), "?h");
+    assert_eq!(
+        parse(r"\x. ? x", &b),
+        Err(Error::UnexpectedCharacter {
+            character: '?',
+            position: 4
+        })
+    );
+}
+
+#[test]
+fn implicits() {
+    let b = syntax::Builder::new();
+    let implicit_pi = |domain, codomain| b.pi_with(Plicity::Implicit, domain, codomain);
+    assert_eq!(
+        parse(r"{A B : Type} -> A -> B", &b),
+        Ok(implicit_pi(
+            b.universe(),
+            implicit_pi(b.universe(), b.pi(b.v(1), b.v(1)))
+        ))
+    );
+    let implicit_lambda = |body| b.lambda_with(Plicity::Implicit, None, body);
+    assert_eq!(
+        parse(r"\{A B} (x : A). x", &b),
+        Ok(implicit_lambda(implicit_lambda(
+            b.annotated_lambda(b.v(1), b.v(0))
+        )))
+    );
+    assert_eq!(
+        parse(r"\f. f {Nat -> Nat} 0", &b),
+        Ok(b.l(b.application(
+            b.application_with(Plicity::Implicit, b.v(0), b.pi(b.nat(), b.nat())),
+            b.natural(0)
+        )))
+    );
+    // Only explicit groups can bind pair types.
+    assert_eq!(
+        parse(r"{A : Type} * A", &b),
+        Err(Error::UnexpectedToken {
+            found: "*".to_owned(),
+            expected: "->",
+            position: 11
+        })
+    );
+    assert_eq!(
+        parse(r"\{A. A", &b),
+        Err(Error::UnexpectedToken {
+            found: ".".to_owned(),
+            expected: "an identifier",
+            position: 3
+        })
+    );
+}
+
+#[test]
+fn datatypes() {
+    let b = syntax::Builder::new();
+    let signature = parse_signature(
+        r"data List (A : Type) = nil | cons A (List A);
+          data Void = ;",
+        &b,
+    )
+    .unwrap();
+    let list = &signature.datatypes()[0];
+    assert_eq!(list.parameters, [b.universe()]);
+    // Each field is under binders for the parameters and the fields before it.
+    let fields: Vec<_> = list
+        .constructors
+        .iter()
+        .map(|constructor| (constructor.name, constructor.fields))
+        .collect();
+    assert_eq!(
+        fields,
+        [
+            ("nil", &[][..]),
+            (
+                "cons",
+                &[b.v(0), b.application(b.type_constructor(0, "List"), b.v(1))][..]
+            )
+        ]
+    );
+    assert!(signature.datatypes()[1].constructors.is_empty());
+    let cons = Constructor {
+        datatype: 0,
+        index: 1,
+        name: "cons",
+    };
+    assert_eq!(signature.lookup("cons"), Some(Name::Constructor(cons)));
+    let nil = Constructor {
+        index: 0,
+        name: "nil",
+        ..cons
+    };
+    assert_eq!(
+        parse_in(
+            r"\xs. case xs { nil => xs; cons x ys => ys }",
+            &signature,
+            &b
+        ),
+        Ok(b.l(b.case(
+            b.v(0),
+            &[b.branch(nil, 0, b.v(0)), b.branch(cons, 2, b.v(0))]
+        )))
+    );
+    assert_eq!(
+        parse_in(r"\xs. case xs {}", &signature, &b),
+        Ok(b.l(b.case(b.v(0), &[])))
+    );
+    // Patterns start with a constructor.
+    assert_eq!(
+        parse_in(r"\xs. case xs { List => xs }", &signature, &b),
+        Err(Error::UnexpectedToken {
+            found: "List".to_owned(),
+            expected: "a constructor",
+            position: 15
+        })
+    );
+    assert_eq!(
+        parse_signature(r"data Bool = true false;", &b).unwrap_err(),
+        Error::UnboundVariable {
+            name: "false".to_owned(),
+            position: 17
+        }
+    );
+    assert_eq!(
+        parse_signature(r"data Bool = true | false", &b).unwrap_err(),
+        Error::UnexpectedEnd { expected: "| or ;" }
+    );
+}
+
+#[test]
+fn records() {
+    let b = syntax::Builder::new();
+    // Each field of a record type is in scope of the types after it.
+    assert_eq!(
+        parse(r"sig { A : Type; x : A }", &b),
+        Ok(b.record_type(&[("A", b.universe()), ("x", b.v(0))]))
+    );
+    assert_eq!(
+        parse(r"\a. struct { x = a; y = struct {} }", &b),
+        Ok(b.l(b.record(&[("x", b.v(0)), ("y", b.record(&[]))])))
+    );
+    // Projections bind tighter than application.
+    assert_eq!(
+        parse(r"\f r. f r.x.y", &b),
+        Ok(b.l(b.l(b.application(b.v(1), b.project(b.project(b.v(0), "x"), "y")))))
+    );
+    assert_eq!(
+        parse(r"\r. r.", &b),
+        Err(Error::UnexpectedEnd {
+            expected: "an identifier"
+        })
+    );
+    assert_eq!(
+        parse(r"struct { x : Type }", &b),
+        Err(Error::UnexpectedToken {
+            found: ":".to_owned(),
+            expected: "=",
+            position: 11
+        })
+    );
+}
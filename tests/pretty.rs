@@ -0,0 +1,208 @@
+mod common;
+
+use common::Workloads;
+use rixty::{
+    domain_rc,
+    normalizer::Normalizer,
+    parse::{parse, parse_in, parse_open, parse_signature},
+    pretty::render,
+    syntax,
+};
+
+#[test]
+fn groups_binders_and_applications() {
+    let b = syntax::Builder::new();
+    let w = Workloads::new(&b);
+    assert_eq!(w.n2.to_string(), r"\a b. a (a b)");
+    assert_eq!(w.mul.to_string(), r"\a b c d. a (b c) d");
+    let term = parse(r"\f. (\x. f x) (\x. x) f", &b).unwrap();
+    assert_eq!(term.to_string(), r"\a. (\b. a b) (\b. b) a");
+}
+
+#[test]
+fn context_names_are_not_reused() {
+    let b = syntax::Builder::new();
+    let term = parse_open(r"\x y. a x (b y)", &["a", "b"], &b).unwrap();
+    assert_eq!(render(term, &["a", "b"], 80), r"\c d. a c (b d)");
+    assert_eq!(render(term, &[], 80), r"\a b. #1 a (#0 b)");
+}
+
+#[test]
+fn wraps_to_the_width() {
+    let b = syntax::Builder::new();
+    let result_builder = syntax::Builder::new();
+    let tree = domain_rc::Backend.normalize(
+        parse(r"\leaf node. node (node leaf leaf) (node leaf leaf)", &b).unwrap(),
+        &result_builder,
+    );
+    assert_eq!(format!("{tree:30}"), r"\a b. b (b a a) (b a a)");
+    assert_eq!(
+        format!("{tree:15}"),
+        "\\a b.\n  b\n    (b a a)\n    (b a a)"
+    );
+}
+
+#[test]
+fn lets() {
+    let b = syntax::Builder::new();
+    let term = parse(r"\f. let x = f f in let y = \z. z in x y", &b).unwrap();
+    assert_eq!(term.to_string(), r"\a. let b = a a in let c = \c. c in b c");
+    assert_eq!(
+        format!("{term:20}"),
+        "\\a.\n  let b = a a in\n  let c = \\c. c in\n  b c"
+    );
+    let term = parse(r"\f. f (let x = f in x)", &b).unwrap();
+    assert_eq!(term.to_string(), r"\a. a (let b = a in b)");
+}
+
+#[test]
+fn types() {
+    let b = syntax::Builder::new();
+    let term = parse(r"(A : Type) -> (A -> A) -> A -> A", &b).unwrap();
+    assert_eq!(term.to_string(), r"(a : Type) -> (a -> a) -> a -> a");
+    let term = parse(r"\(A : Type) (f : A -> A) x. f x", &b).unwrap();
+    assert_eq!(term.to_string(), r"\(a : Type) (b : a -> a) c. b c");
+    let term = parse(r"(F : Type -> Type) -> F ((A : Type) -> A)", &b).unwrap();
+    assert_eq!(
+        term.to_string(),
+        r"(a : Type -> Type) -> a ((b : Type) -> b)"
+    );
+    let term = parse(r"\f. f (Type -> Type) (\x. x) -> Type", &b).unwrap();
+    assert_eq!(term.to_string(), r"\a. a (Type -> Type) (\b. b) -> Type");
+}
+
+#[test]
+fn implicits() {
+    let b = syntax::Builder::new();
+    let term = parse(r"{A : Type} -> A -> A", &b).unwrap();
+    assert_eq!(term.to_string(), r"{a : Type} -> a -> a");
+    let term = parse_open(r"\{A} {B : Type} (x : A). f {A -> B} {Type} x", &["f"], &b).unwrap();
+    assert_eq!(
+        render(term, &["f"], 80),
+        r"\{a} {b : Type} (c : a). f {a -> b} {Type} c"
+    );
+}
+
+#[test]
+fn pairs() {
+    let b = syntax::Builder::new();
+    let term = parse(r"\p. (snd p, fst p (snd (snd p)))", &b).unwrap();
+    assert_eq!(term.to_string(), r"\a. (snd a, fst a (snd (snd a)))");
+    let term = parse(r"(A : Type) * (A -> A) * A", &b).unwrap();
+    assert_eq!(term.to_string(), r"(a : Type) * (a -> a) * a");
+    let term = parse(r"(Type * Type) * (Type * Type -> Type)", &b).unwrap();
+    assert_eq!(term.to_string(), r"(Type * Type) * (Type * Type -> Type)");
+    let term = parse(r"\f. f (fst f) ((\x. x) f)", &b).unwrap();
+    assert_eq!(term.to_string(), r"\a. a (fst a) ((\b. b) a)");
+}
+
+#[test]
+fn naturals() {
+    let b = syntax::Builder::new();
+    let term = parse(r"\n. natElim (\_. Nat) 0 (\_ acc. suc acc) (suc n)", &b).unwrap();
+    assert_eq!(
+        term.to_string(),
+        r"\a. natElim (\b. Nat) 0 (\b c. suc c) (suc a)"
+    );
+    let term = parse(r"(Nat -> Nat) -> suc zero", &b).unwrap();
+    assert_eq!(term.to_string(), r"(Nat -> Nat) -> suc zero");
+}
+
+#[test]
+fn round_trips_through_the_parser() {
+    let b = syntax::Builder::new();
+    let w = Workloads::new(&b);
+    let result_builder = syntax::Builder::new();
+    let lets = parse(r"\f. let x = f f in let y = \z. z in x (y x)", &b).unwrap();
+    let types = parse(
+        r"(A : Type) -> (P : A -> Type) -> (\(x : A). P x -> P x) -> (Type -> A) -> A",
+        &b,
+    )
+    .unwrap();
+    let pairs = parse(
+        r"\(p : (A : Type) * A * Type) q. (fst p, (snd (snd p), fst (q (fst p) (snd p))))",
+        &b,
+    )
+    .unwrap();
+    let naturals = parse(
+        r"\(P : Nat -> Type) n. natElim P (suc 0) (\k (p : P (suc k)). p) (suc (suc n))",
+        &b,
+    )
+    .unwrap();
+    let integers = parse(
+        r"\(x : I64) (y : U64). (addI64 x -3i64, ltU64 (divU64 y 18446744073709551615u64) y)",
+        &b,
+    )
+    .unwrap();
+    let implicits = parse(
+        r"\(f : {A : Type} -> A -> A) {B} (x : B). ({C : Type} -> C, f {B} x)",
+        &b,
+    )
+    .unwrap();
+    for term in [lets, types, pairs, naturals, integers, implicits] {
+        for width in [10, 80] {
+            let rendered = format!("{term:width$}");
+            assert_eq!(parse(&rendered, &b), Ok(term), "{rendered}");
+        }
+    }
+    for term in [w.n21, w.tree_1k, w.mul, b.application(w.mul, w.n2)] {
+        let normal_form = domain_rc::Backend.normalize(term, &result_builder);
+        for width in [10, 80] {
+            let rendered = format!("{normal_form:width$}");
+            assert_eq!(parse(&rendered, &b), Ok(normal_form), "{rendered}");
+        }
+    }
+}
+
+#[test]
+fn case_analyses() {
+    let b = syntax::Builder::new();
+    let signature = parse_signature(r"data List (A : Type) = nil | cons A (List A);", &b).unwrap();
+    let term = parse_in(
+        r"\xs. case xs { nil => xs; cons x ys => case ys { nil => xs; cons y zs => cons x zs } }",
+        &signature,
+        &b,
+    )
+    .unwrap();
+    assert_eq!(
+        format!("{term:50}"),
+        "\\a.
+  case a {
+    nil => a;
+    cons b c =>
+      case c { nil => a; cons d e => cons b e }
+  }"
+    );
+    for width in [10, 80] {
+        let rendered = format!("{term:width$}");
+        assert_eq!(parse_in(&rendered, &signature, &b), Ok(term), "{rendered}");
+    }
+}
+
+#[test]
+fn records() {
+    let b = syntax::Builder::new();
+    let term = parse(r"\r. struct { x = r.x; y = (\s. s) r.y }.y.z", &b).unwrap();
+    assert_eq!(
+        term.to_string(),
+        r"\a. struct { x = a.x; y = (\b. b) a.y }.y.z"
+    );
+    // Generated names do not clash with the names of fields in scope.
+    let term = parse(
+        r"sig { a : Type; f : (x : a) -> (P : a -> Type) -> P x; e : sig {} }",
+        &b,
+    )
+    .unwrap();
+    assert_eq!(
+        term.to_string(),
+        r"sig { a : Type; f : (b : a) -> (c : a -> Type) -> c b; e : sig {} }"
+    );
+    assert_eq!(
+        format!("{term:24}"),
+        "sig {\n  a : Type;\n  f :\n    (b : a) ->\n    (c : a -> Type) ->\n    c b;\n  e : sig {}\n}"
+    );
+    for width in [10, 80] {
+        let rendered = format!("{term:width$}");
+        assert_eq!(parse(&rendered, &b), Ok(term), "{rendered}");
+    }
+}
@@ -0,0 +1,238 @@
+mod common;
+
+use common::{to_nat, Workloads};
+use rixty::{
+    domain,
+    domain_rc::{self, Environment, QuoteOptions},
+    index::{Index, Level},
+    meta::MetaContext,
+    normalizer::Normalizer,
+    parse::parse_open,
+    pretty::render,
+    signature::{Definition, Signature},
+    syntax::{self, Term, TermRef},
+};
+
+/// The normal form of `n1M` is a million nested applications, far deeper than
+/// a recursive readback can go on a test thread's stack.
+#[test]
+fn deep_numerals() {
+    let b = syntax::Builder::new();
+    let w = Workloads::new(&b);
+    let n1m = b.apps(w.mul, &[w.n1k, w.n1k]);
+    let result_builder = syntax::Builder::new();
+    let normal_form = domain_rc::Backend.normalize(n1m, &result_builder);
+    assert_eq!(to_nat(normal_form), Some(1_000_000));
+    let normal_form = domain::Backend.normalize(n1m, &result_builder);
+    assert_eq!(to_nat(normal_form), Some(1_000_000));
+}
+
+#[test]
+fn deep_neutrals() {
+    let f = domain_rc::Value::variable(Level(0));
+    let mut value = domain_rc::Value::variable(Level(1));
+    for _ in 0..1_000_000 {
+        value = domain_rc::apply(&f, value);
+    }
+    let result_builder = syntax::Builder::new();
+    let mut term = value.quote(Level(2), &result_builder);
+    let mut depth = 0;
+    while let Term::Application(_, _, argument) = term {
+        depth += 1;
+        term = argument;
+    }
+    assert_eq!(depth, 1_000_000);
+    drop(value);
+}
+
+/// Solving `?0 f x` with a million applications of `f` renames, evaluates
+/// and reads back a solution that deep.
+#[test]
+fn deep_solutions() {
+    let b = syntax::Builder::new();
+    let metas = MetaContext::new(&b);
+    let meta = metas.fresh();
+    let flex = b
+        .apps(b.meta(meta), &[b.v(1), b.v(0)])
+        .evaluate_rc(&mut domain_rc::Environment::free_variables(2).with_metas(&metas));
+    let f = domain_rc::Value::variable(Level(0));
+    let mut value = domain_rc::Value::variable(Level(1));
+    for _ in 0..1_000_000 {
+        value = domain_rc::apply(&f, value);
+    }
+    assert_eq!(metas.unify(Level(2), &flex, &value), Ok(()));
+    let result_builder = syntax::Builder::new();
+    let solution = metas.solution(meta).unwrap();
+    assert_eq!(
+        to_nat(solution.quote(Level(0), &result_builder)),
+        Some(1_000_000)
+    );
+    let builder = domain::Builder::new();
+    let mut environment = domain::Environment::new().with_metas(&metas, &builder);
+    let solution = b.meta(meta).evaluate(&mut environment, &builder).quote(
+        Level(0),
+        &builder,
+        &result_builder,
+    );
+    assert_eq!(to_nat(solution), Some(1_000_000));
+    drop(value);
+}
+
+#[test]
+fn deep_pairs_are_dropped() {
+    let b = syntax::Builder::new();
+    let pair = (0..1_000_000).fold(b.v(0), |second, _| b.pair(b.v(0), second));
+    drop(pair.evaluate_rc(&mut domain_rc::Environment::free_variables(1)));
+}
+
+#[test]
+fn deep_successors_are_dropped() {
+    let b = syntax::Builder::new();
+    let n = (0..1_000_000).fold(b.v(0), |predecessor, _| b.suc(predecessor));
+    drop(n.evaluate_rc(&mut domain_rc::Environment::free_variables(1)));
+}
+
+/// Each definition unfolds to the one before it, so once they are all
+/// unfolded the value of the last one is a chain of a million unfoldings.
+#[test]
+fn deep_unfoldings_are_dropped() {
+    let b = syntax::Builder::new();
+    let mut signature = Signature::new();
+    let mut term = b.universe();
+    for _ in 0..1_000_000 {
+        let global = signature.define(Definition {
+            name: "d",
+            ty: None,
+            term,
+        });
+        term = b.global(global.index, global.name);
+    }
+    let value = term.evaluate_rc(&mut domain_rc::Environment::new().with_signature(&signature));
+    let result_builder = syntax::Builder::new();
+    assert_eq!(value.quote(Level(0), &result_builder), &Term::Type);
+    drop(value);
+}
+
+#[test]
+fn deep_domains_are_dropped() {
+    let b = syntax::Builder::new();
+    let ty = (0..1_000_000).fold(b.universe(), |domain, _| b.pi(domain, b.universe()));
+    drop(ty.evaluate_rc(&mut domain_rc::Environment::new()));
+}
+
+const ETA: QuoteOptions = QuoteOptions {
+    keep_globals: false,
+    hide_implicits: false,
+    eta_contract: true,
+};
+
+/// Eta-reduces a term of the lambda calculus bottom up, the obvious way.
+fn eta_reduce<'b>(term: TermRef<'_>, b: &'b syntax::Builder) -> TermRef<'b> {
+    match term {
+        Term::Variable(index) => b.variable(*index),
+        Term::Application(plicity, function, argument) => {
+            b.application_with(*plicity, eta_reduce(function, b), eta_reduce(argument, b))
+        }
+        Term::Lambda(plicity, None, body) => match eta_reduce(body, b) {
+            Term::Application(argument_plicity, function, Term::Variable(Index(0)))
+                if argument_plicity == plicity && !is_free(function, 0) =>
+            {
+                lower(function, 0, b)
+            }
+            body => b.lambda_with(*plicity, None, body),
+        },
+        _ => panic!("not a term of the lambda calculus"),
+    }
+}
+
+fn is_free(term: TermRef<'_>, index: usize) -> bool {
+    match term {
+        Term::Variable(Index(variable)) => *variable == index,
+        Term::Application(_, function, argument) => {
+            is_free(function, index) || is_free(argument, index)
+        }
+        Term::Lambda(_, None, body) => is_free(body, index + 1),
+        _ => panic!("not a term of the lambda calculus"),
+    }
+}
+
+fn lower<'b>(term: TermRef<'b>, cutoff: usize, b: &'b syntax::Builder) -> TermRef<'b> {
+    match term {
+        Term::Variable(Index(index)) if *index > cutoff => b.v(index - 1),
+        Term::Variable(_) => term,
+        Term::Application(plicity, function, argument) => b.application_with(
+            *plicity,
+            lower(function, cutoff, b),
+            lower(argument, cutoff, b),
+        ),
+        Term::Lambda(plicity, None, body) => {
+            b.lambda_with(*plicity, None, lower(body, cutoff + 1, b))
+        }
+        _ => panic!("not a term of the lambda calculus"),
+    }
+}
+
+#[test]
+fn eta_contraction_agrees_with_post_hoc_eta_reduction() {
+    let b = syntax::Builder::new();
+    let result_builder = syntax::Builder::new();
+    let context = ["f", "g"];
+    for (input, expected) in [
+        (r"\x. f x", "f"),
+        (r"\x y. f x y", "f"),
+        (r"\x. g f x", "g f"),
+        (r"\x y. f y x", r"\a b. f b a"),
+        (r"\x. f x x", r"\a. f a a"),
+        (r"\x. x f", r"\a. a f"),
+        (r"\x. g (\y. f y) x", "g f"),
+        (r"\x y. g x (f y) y", r"\a b. g a (f b) b"),
+        (r"\h. (\k x. k x) h", r"\a. a"),
+        (r"\h {A} x. h {A} x", r"\a. a"),
+        (r"\{A}. f A", r"\{a}. f a"),
+        (r"\x. f {x}", r"\a. f {a}"),
+    ] {
+        let term = parse_open(input, &context, &b).unwrap();
+        let value = term.evaluate_rc(&mut Environment::free_variables(2));
+        let eta_short = value.quote_with(Level(2), &result_builder, ETA);
+        assert_eq!(render(eta_short, &context, 80), expected, "{input}");
+        let normal_form = value.quote(Level(2), &result_builder);
+        assert_eq!(
+            eta_short,
+            eta_reduce(normal_form, &result_builder),
+            "{input}"
+        );
+    }
+
+    let w = Workloads::new(&b);
+    for term in [w.n2, w.n10, w.mul, b.application(w.mul, w.n2), w.tree_1k] {
+        let value = term.evaluate_rc(&mut Environment::new());
+        let eta_short = value.quote_with(Level(0), &result_builder, ETA);
+        let normal_form = value.quote(Level(0), &result_builder);
+        assert_eq!(eta_short, eta_reduce(normal_form, &result_builder));
+    }
+}
+
+/// The function of a contracted lambda is a million applications deep.
+#[test]
+fn eta_contraction_of_deep_functions() {
+    let b = syntax::Builder::new();
+    let f = b.v(1);
+    let deep = (0..1_000_000).fold(b.v(2), |term, _| b.application(f, term));
+    // \x. g (f (f … y)) x, under g, y and f.
+    let term = b.l(b.apps(b.v(3), &[deep, b.v(0)]));
+    let value = term.evaluate_rc(&mut Environment::free_variables(3));
+    let result_builder = syntax::Builder::new();
+    let term = value.quote_with(Level(3), &result_builder, ETA);
+    let Term::Application(_, g, mut argument) = term else {
+        panic!("not eta-contracted");
+    };
+    assert_eq!(**g, Term::Variable(Index(2)));
+    let mut depth = 0;
+    while let Term::Application(_, f, inner) = argument {
+        assert_eq!(**f, Term::Variable(Index(0)));
+        depth += 1;
+        argument = inner;
+    }
+    assert_eq!(*argument, Term::Variable(Index(1)));
+    assert_eq!(depth, 1_000_000);
+}
@@ -0,0 +1,89 @@
+use rixty::{
+    domain,
+    domain_rc::{self, Environment, Value},
+    index::Level,
+    normalizer::Normalizer,
+    parse::parse,
+    reference, syntax,
+};
+
+#[test]
+fn projection_selects_the_field() {
+    let b = syntax::Builder::new();
+    let result_builder = syntax::Builder::new();
+    for (input, expected) in [
+        (r"struct { x = 1; y = 2 }.y", "2"),
+        (r"(\r. r.x) struct { x = \a. a; y = 0 } 5", "5"),
+        (r"\a. struct { f = a; g = struct { h = a } }.g.h", r"\a. a"),
+        (r"\r. struct { x = r.y; y = r.x }.x", r"\a. a.y"),
+        (
+            r"sig { A : Type; x : A; f : A -> A }",
+            r"sig { A : Type; x : A; f : A -> A }",
+        ),
+        // Ill-typed projections are stuck.
+        (r"struct { x = 1 }.y", r"struct { x = 1 }.y"),
+        (r"(\a. a).x", r"(\a. a).x"),
+    ] {
+        let term = parse(input, &b).unwrap();
+        let actual = domain_rc::Backend.normalize(term, &result_builder);
+        let arena = domain::Backend.normalize(term, &result_builder);
+        let oracle = reference::Backend.normalize(term, &result_builder);
+        assert_eq!(actual.to_string(), expected, "{input}");
+        assert_eq!(actual, oracle, "{input}");
+        assert_eq!(arena, oracle, "{input}");
+    }
+}
+
+#[test]
+fn stuck_projections_keep_their_frames() {
+    let b = syntax::Builder::new();
+    let term = parse(r"\r. r.f r.x", &b).unwrap();
+    let value = term.evaluate_rc(&mut Environment::new());
+    assert_eq!(value.quote(Level(0), &b), term);
+    // Once the record is known, the projections are taken out of it.
+    let record = parse(r"struct { f = \a. struct { y = a }; x = 7 }", &b).unwrap();
+    let record = record.evaluate_rc(&mut Environment::new());
+    let value = domain_rc::apply(&value, record);
+    let value = domain_rc::project_field(&value, "y");
+    assert_eq!(value.quote(Level(0), &b).to_string(), "7");
+}
+
+#[test]
+fn conversion_is_eta_for_records() {
+    let b = syntax::Builder::new();
+    let conv = |input1, input2| {
+        let term1 = parse(input1, &b).unwrap();
+        let term2 = parse(input2, &b).unwrap();
+        let value1 = term1.evaluate_rc(&mut Environment::new());
+        let value2 = term2.evaluate_rc(&mut Environment::new());
+        let result = Value::conv(Level(0), &value1, &value2);
+        let builder = domain::Builder::new();
+        let value1 = term1.evaluate(&mut domain::Environment::new(), &builder);
+        let value2 = term2.evaluate(&mut domain::Environment::new(), &builder);
+        assert_eq!(
+            domain::Value::conv(Level(0), value1, value2, &builder),
+            result,
+            "{input1} and {input2}"
+        );
+        result
+    };
+    assert!(conv(r"\r. struct { x = r.x; y = r.y }", r"\r. r"));
+    assert!(conv(r"\r. r", r"\r. struct { x = r.x; y = r.y }"));
+    assert!(conv(r"\r. struct {}", r"\r. struct {}"));
+    assert!(!conv(r"\r. struct { x = r.y; y = r.x }", r"\r. r"));
+    assert!(!conv(r"\r. struct { x = r.x }", r"\r. struct { y = r.x }"));
+    assert!(conv(r"\r. r.x", r"\r. struct { x = r.x }.x"));
+    assert!(!conv(r"\r. r.x", r"\r. r.y"));
+    assert!(conv(
+        r"sig { A : Type; x : A }",
+        r"sig { A : Type; x : (\C. C) A }"
+    ));
+    assert!(!conv(
+        r"sig { A : Type; x : A }",
+        r"sig { x : Type; A : x }"
+    ));
+    assert!(!conv(
+        r"sig { A : Type; x : A }",
+        r"sig { A : Type; x : Type }"
+    ));
+}
@@ -0,0 +1,250 @@
+use rixty::{
+    domain_rc::{Environment, QuoteOptions, ValueRef},
+    index::Level,
+    meta::{MetaContext, UnifyError},
+    parse::{parse_in, parse_open, parse_signature},
+    syntax::{self, Meta, TermRef},
+};
+
+/// The free variables the problems are posed in, outermost first.
+const CONTEXT: [&str; 3] = ["A", "x", "y"];
+
+fn evaluate<'a>(metas: &MetaContext<'a>, term: TermRef<'a>) -> ValueRef<'a> {
+    term.evaluate_rc(&mut Environment::free_variables(CONTEXT.len()).with_metas(metas))
+}
+
+/// `meta` applied to the variables of `CONTEXT` at `levels`.
+fn flex<'a>(b: &'a syntax::Builder, meta: Meta, levels: &[usize]) -> TermRef<'a> {
+    let arguments: Vec<_> = levels
+        .iter()
+        .map(|level| b.v(CONTEXT.len() - level - 1))
+        .collect();
+    b.apps(b.meta(meta), &arguments)
+}
+
+/// Unifies `lhs` with the term `rhs` over `CONTEXT`.
+fn unify<'a>(
+    metas: &MetaContext<'a>,
+    b: &'a syntax::Builder,
+    lhs: TermRef<'a>,
+    rhs: &str,
+) -> Result<(), UnifyError> {
+    let rhs = parse_open(rhs, &CONTEXT, b).unwrap();
+    metas.unify(
+        Level(CONTEXT.len()),
+        &evaluate(metas, lhs),
+        &evaluate(metas, rhs),
+    )
+}
+
+fn solution(metas: &MetaContext<'_>, meta: Meta, b: &syntax::Builder) -> String {
+    metas.solution(meta).unwrap().quote(Level(0), b).to_string()
+}
+
+#[test]
+fn patterns_are_solved() {
+    let b = syntax::Builder::new();
+    let metas = MetaContext::new(&b);
+    let meta = metas.fresh();
+    assert_eq!(
+        unify(&metas, &b, flex(&b, meta, &[1, 2]), r"\f. f y (x y)"),
+        Ok(())
+    );
+    assert_eq!(solution(&metas, meta, &b), r"\a b c. c b (a b)");
+    // The arguments can come in any order, and need not be all variables.
+    let meta = metas.fresh();
+    assert_eq!(
+        unify(&metas, &b, flex(&b, meta, &[2, 0]), r"(z : A) -> y z"),
+        Ok(())
+    );
+    assert_eq!(solution(&metas, meta, &b), r"\a b. (c : b) -> a c");
+    assert!(metas.unsolved().is_empty());
+}
+
+#[test]
+fn solutions_are_forced() {
+    let b = syntax::Builder::new();
+    let metas = MetaContext::new(&b);
+    let meta = metas.fresh();
+    // Made while the meta is unsolved, and applied after it is solved.
+    let function = evaluate(&metas, b.lambda(b.application(b.meta(meta), b.v(0))));
+    assert_eq!(function.quote(Level(3), &b).to_string(), r"\a. ?0 a");
+    assert_eq!(unify(&metas, &b, b.meta(meta), r"\f z. f z z"), Ok(()));
+    assert_eq!(function.quote(Level(3), &b).to_string(), r"\a b. a b b");
+    let applied = b.apps(b.meta(meta), &[b.v(1), b.natural(2)]);
+    let applied = evaluate(&metas, applied);
+    assert_eq!(applied.quote(Level(3), &b).to_string(), "#1 2 2");
+    // Solved metas are equal to their solutions.
+    assert_eq!(
+        unify(&metas, &b, flex(&b, meta, &[2]), r"\z. y z z"),
+        Ok(())
+    );
+    assert_eq!(
+        unify(&metas, &b, flex(&b, meta, &[2]), r"\z. y z y"),
+        Err(UnifyError::Mismatch)
+    );
+}
+
+#[test]
+fn solutions_under_binders() {
+    let b = syntax::Builder::new();
+    let metas = MetaContext::new(&b);
+    let meta = metas.fresh();
+    // `(B : Type) -> ?0 B x =?= (B : Type) -> B -> A`
+    let lhs = b.pi(b.universe(), b.apps(b.meta(meta), &[b.v(0), b.v(2)]));
+    assert_eq!(
+        unify(&metas, &b, lhs, "(B : Type) -> B -> A"),
+        Err(UnifyError::Scope {
+            meta,
+            variable: Level(0)
+        })
+    );
+    let meta = metas.fresh();
+    let lhs = b.pi(b.universe(), b.apps(b.meta(meta), &[b.v(0), b.v(3)]));
+    assert_eq!(unify(&metas, &b, lhs, "(B : Type) -> B -> A"), Ok(()));
+    assert_eq!(solution(&metas, meta, &b), r"\a b. a -> b");
+    // Eta: lambdas are compared by applying both sides to a variable.
+    let meta = metas.fresh();
+    let lhs = b.lambda(b.apps(b.meta(meta), &[b.v(1), b.v(0)]));
+    assert_eq!(unify(&metas, &b, lhs, r"\z. z y"), Ok(()));
+    assert_eq!(solution(&metas, meta, &b), r"\a b. b a");
+    let meta = metas.fresh();
+    let lhs = b.lambda(b.apps(b.meta(meta), &[b.v(1), b.v(0)]));
+    assert_eq!(unify(&metas, &b, lhs, "y"), Ok(()));
+    assert_eq!(solution(&metas, meta, &b), r"\a b. a b");
+}
+
+#[test]
+fn failures_are_structured() {
+    let b = syntax::Builder::new();
+    let metas = MetaContext::new(&b);
+    let meta = metas.fresh();
+    assert_eq!(
+        unify(&metas, &b, flex(&b, meta, &[1]), "y x"),
+        Err(UnifyError::Scope {
+            meta,
+            variable: Level(2)
+        })
+    );
+    // `?0 x =?= x -> ?0 x`
+    let rhs = b.pi(b.v(1), b.apps(b.meta(meta), &[b.v(2)]));
+    assert_eq!(
+        metas.unify(
+            Level(3),
+            &evaluate(&metas, flex(&b, meta, &[1])),
+            &evaluate(&metas, rhs)
+        ),
+        Err(UnifyError::Occurs { meta })
+    );
+    assert_eq!(
+        unify(&metas, &b, flex(&b, meta, &[1, 1]), "x"),
+        Err(UnifyError::NotPattern { meta })
+    );
+    let applied = b.application(b.meta(meta), b.natural(1));
+    assert_eq!(
+        unify(&metas, &b, applied, "1"),
+        Err(UnifyError::NotPattern { meta })
+    );
+    // The same meta on both sides: the arguments must agree.
+    let same = |levels1, levels2| {
+        metas.unify(
+            Level(3),
+            &evaluate(&metas, flex(&b, meta, levels1)),
+            &evaluate(&metas, flex(&b, meta, levels2)),
+        )
+    };
+    assert_eq!(same(&[1], &[2]), Err(UnifyError::Mismatch));
+    assert_eq!(same(&[1, 2], &[1, 2]), Ok(()));
+    assert_eq!(metas.unsolved(), vec![meta]);
+    assert_eq!(
+        UnifyError::Scope {
+            meta,
+            variable: Level(2)
+        }
+        .to_string(),
+        "the solution of ?0 would mention x2, which it is not applied to"
+    );
+}
+
+#[test]
+fn flex_flex() {
+    let b = syntax::Builder::new();
+    let metas = MetaContext::new(&b);
+    let meta1 = metas.fresh();
+    let meta2 = metas.fresh();
+    let lhs = flex(&b, meta1, &[1, 2]);
+    let rhs = flex(&b, meta2, &[2]);
+    assert_eq!(
+        metas.unify(Level(3), &evaluate(&metas, lhs), &evaluate(&metas, rhs)),
+        Ok(())
+    );
+    assert_eq!(solution(&metas, meta1, &b), r"\a b. ?1 b");
+    assert_eq!(metas.unsolved(), vec![meta2]);
+    // When one side is not a pattern, the other is solved.
+    let meta3 = metas.fresh();
+    let lhs = b.application(b.meta(meta2), b.natural(0));
+    let rhs = flex(&b, meta3, &[0]);
+    assert_eq!(
+        metas.unify(Level(3), &evaluate(&metas, lhs), &evaluate(&metas, rhs)),
+        Ok(())
+    );
+    assert_eq!(metas.unsolved(), vec![meta2]);
+    assert_eq!(solution(&metas, meta3, &b), r"\a. ?1 0");
+}
+
+#[test]
+fn solutions_keep_globals() {
+    let b = syntax::Builder::new();
+    let signature = parse_signature(
+        r"Church = (A : Type) -> (A -> A) -> A -> A; id = \x. x;",
+        &b,
+    )
+    .unwrap();
+    let metas = MetaContext::with_signature(&signature, &b);
+    let environment = Environment::new()
+        .with_signature(&signature)
+        .with_metas(&metas);
+    let meta = metas.fresh();
+    let lhs = b.pi(b.meta(meta), b.meta(meta));
+    let rhs = parse_in("Church -> Church", &signature, &b).unwrap();
+    assert_eq!(
+        metas.unify(
+            Level(0),
+            &lhs.evaluate_rc(&mut environment.clone()),
+            &rhs.evaluate_rc(&mut environment.clone())
+        ),
+        Ok(())
+    );
+    let keep_globals = QuoteOptions {
+        keep_globals: true,
+        ..QuoteOptions::default()
+    };
+    let solution = metas.solution(meta).unwrap();
+    assert_eq!(
+        solution.quote_with(Level(0), &b, keep_globals).to_string(),
+        "Church"
+    );
+    assert_eq!(
+        solution.quote(Level(0), &b).to_string(),
+        "(a : Type) -> (a -> a) -> a -> a"
+    );
+    // Globals are unfolded to expose metas.
+    let meta = metas.fresh();
+    let lhs = b.application(parse_in("id", &signature, &b).unwrap(), b.meta(meta));
+    assert_eq!(
+        metas.unify(
+            Level(0),
+            &lhs.evaluate_rc(&mut environment.clone()),
+            &rhs.evaluate_rc(&mut environment.clone())
+        ),
+        Ok(())
+    );
+    assert_eq!(
+        metas
+            .solution(meta)
+            .unwrap()
+            .quote_with(Level(0), &b, keep_globals)
+            .to_string(),
+        "Church -> Church"
+    );
+}
@@ -0,0 +1,137 @@
+mod common;
+
+use common::Workloads;
+use rixty::{
+    domain,
+    domain_rc::{self, Environment, Frame, Value},
+    index::Level,
+    parse::{parse, parse_in, parse_open, parse_signature},
+    syntax::{self, TermRef},
+};
+
+/// Reads a value of the lambda calculus back by hand, through the public
+/// accessors.
+fn read_back<'b>(value: &Value<'_>, level: Level, b: &'b syntax::Builder) -> TermRef<'b> {
+    match value {
+        Value::Lambda(_, closure) => {
+            let body = closure.apply(Value::variable(level));
+            b.lambda(read_back(&domain_rc::unfold(&body), level + 1, b))
+        }
+        _ => {
+            let head = value.head().and_then(|head| head.variable()).unwrap();
+            let mut term = b.variable(head.to_index(level));
+            for frame in value.spine() {
+                match frame {
+                    Frame::Application(_, argument) => {
+                        let argument = read_back(&domain_rc::unfold(argument), level, b);
+                        term = b.application(term, argument);
+                    }
+                    _ => panic!("not a term of the lambda calculus"),
+                }
+            }
+            term
+        }
+    }
+}
+
+fn read_back_arena<'a, 'b>(
+    value: &domain::Value<'a>,
+    level: Level,
+    builder: &'a domain::Builder,
+    b: &'b syntax::Builder,
+) -> TermRef<'b> {
+    match value {
+        domain::Value::Lambda(_, closure) => {
+            let body = closure.apply(builder.variable(level), builder);
+            b.lambda(read_back_arena(body, level + 1, builder, b))
+        }
+        _ => {
+            let head = value.head().and_then(|head| head.variable()).unwrap();
+            let mut term = b.variable(head.to_index(level));
+            for frame in value.spine() {
+                match frame {
+                    domain::Frame::Application(_, argument) => {
+                        let argument = read_back_arena(argument, level, builder, b);
+                        term = b.application(term, argument);
+                    }
+                    _ => panic!("not a term of the lambda calculus"),
+                }
+            }
+            term
+        }
+    }
+}
+
+#[test]
+fn whnf_stops_at_the_head() {
+    let b = syntax::Builder::new();
+    let term = parse(r"(\x. x) (\y. (\z. z) y)", &b).unwrap();
+    let value = term.whnf_rc(&mut Environment::new());
+    assert!(matches!(&*value, Value::Lambda(..)));
+
+    let term = parse_open(r"(\g. g ((\x. x) a) b) f", &["f", "a", "b"], &b).unwrap();
+    let value = term.whnf_rc(&mut Environment::free_variables(3));
+    assert_eq!(
+        value.head().and_then(|head| head.variable()),
+        Some(Level(0))
+    );
+    assert_eq!(value.spine().count(), 2);
+    let arguments: Vec<_> = (value.spine())
+        .map(|frame| match frame {
+            Frame::Application(_, argument) => argument.head().and_then(|head| head.variable()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(arguments, [Some(Level(1)), Some(Level(2))]);
+
+    let builder = domain::Builder::new();
+    let value = term.whnf(
+        &mut domain::Environment::free_variables(3, &builder),
+        &builder,
+    );
+    assert_eq!(
+        value.head().and_then(|head| head.variable()),
+        Some(Level(0))
+    );
+    assert_eq!(value.spine().count(), 2);
+}
+
+#[test]
+fn whnf_unfolds_globals() {
+    let b = syntax::Builder::new();
+    let signature = parse_signature(r"id = \x. x; two = \f x. f (f x);", &b).unwrap();
+    let term = parse_in(r"id two", &signature, &b).unwrap();
+    let value = term.evaluate_rc(&mut Environment::new().with_signature(&signature));
+    assert_eq!(
+        value.head().and_then(|head| head.global()).unwrap().name,
+        "id"
+    );
+    let value = term.whnf_rc(&mut Environment::new().with_signature(&signature));
+    assert!(matches!(&*value, Value::Lambda(..)));
+    assert!(value.head().is_none());
+
+    let builder = domain::Builder::new();
+    let mut environment = domain::Environment::new().with_signature(&signature, &builder);
+    let value = term.whnf(&mut environment, &builder);
+    assert!(matches!(value, domain::Value::Lambda(..)));
+}
+
+#[test]
+fn readback_can_be_driven_by_hand() {
+    let b = syntax::Builder::new();
+    let w = Workloads::new(&b);
+    let result_builder = syntax::Builder::new();
+    for term in [w.n2, w.n10, w.mul, w.tree_1k] {
+        let value = term.whnf_rc(&mut Environment::new());
+        assert_eq!(
+            read_back(&value, Level(0), &result_builder),
+            value.quote(Level(0), &result_builder)
+        );
+        let builder = domain::Builder::new();
+        let value = term.whnf(&mut domain::Environment::new(), &builder);
+        assert_eq!(
+            read_back_arena(value, Level(0), &builder, &result_builder),
+            value.quote(Level(0), &builder, &result_builder)
+        );
+    }
+}